@@ -0,0 +1,71 @@
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard,
+    Board, Color, Piece, ALL_COLORS, ALL_PIECES, EMPTY, NUM_COLORS, NUM_PIECES,
+};
+
+/// The king of `color` and the squares next to it
+pub(crate) fn king_zone(board: &Board, color: Color) -> BitBoard {
+    let king = board.king_square(color);
+    get_king_moves(king) | BitBoard::from_square(king)
+}
+
+/// The squares each piece type of each side attacks, built once per eval for the terms that
+/// need them
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AttackMaps {
+    /// By color index, then piece index
+    by_piece: [[BitBoard; NUM_PIECES]; NUM_COLORS],
+    /// Pieces of each color, the king left out, attacking at least one square of the enemy king
+    /// zone, by color index
+    king_zone_attackers: [u32; NUM_COLORS],
+}
+
+impl AttackMaps {
+    pub(crate) fn new(board: &Board) -> Self {
+        let blockers = *board.combined();
+        let mut by_piece = [[EMPTY; NUM_PIECES]; NUM_COLORS];
+        let mut king_zone_attackers = [0; NUM_COLORS];
+        for color in ALL_COLORS {
+            let enemy_king_zone = king_zone(board, !color);
+            for piece in ALL_PIECES {
+                for square in board.pieces(piece) & board.color_combined(color) {
+                    let attacks = match piece {
+                        Piece::Pawn => get_pawn_attacks(square, color, !EMPTY),
+                        Piece::Knight => get_knight_moves(square),
+                        Piece::Bishop => get_bishop_moves(square, blockers),
+                        Piece::Rook => get_rook_moves(square, blockers),
+                        Piece::Queen => {
+                            get_bishop_moves(square, blockers) | get_rook_moves(square, blockers)
+                        }
+                        Piece::King => get_king_moves(square),
+                    };
+                    by_piece[color.to_index()][piece.to_index()] |= attacks;
+                    if piece != Piece::King && attacks & enemy_king_zone != EMPTY {
+                        king_zone_attackers[color.to_index()] += 1;
+                    }
+                }
+            }
+        }
+        AttackMaps {
+            by_piece,
+            king_zone_attackers,
+        }
+    }
+
+    /// Squares attacked by the pieces of type `piece` of `color`
+    pub(crate) fn by_piece(&self, color: Color, piece: Piece) -> BitBoard {
+        self.by_piece[color.to_index()][piece.to_index()]
+    }
+
+    /// How many pieces of `color`, the king left out, attack the king zone of the other color
+    pub(crate) fn king_zone_attackers(&self, color: Color) -> u32 {
+        self.king_zone_attackers[color.to_index()]
+    }
+
+    /// Squares attacked by any piece of `color`
+    pub(crate) fn by_color(&self, color: Color) -> BitBoard {
+        self.by_piece[color.to_index()]
+            .iter()
+            .fold(EMPTY, |attacks, &piece_attacks| attacks | piece_attacks)
+    }
+}
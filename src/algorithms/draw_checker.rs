@@ -1,20 +1,84 @@
-use std::collections::HashMap;
+use chess::{BitBoard, Board, Piece};
 
-use chess::Board;
+use crate::common::constants::FIFTY_MOVE_PLIES;
+use crate::common::utils;
 
-pub fn uncount_board(board_played_times_prediction: &mut HashMap<u64, u32>, new_board: &Board) {
-    let hash = new_board.get_hash();
-    board_played_times_prediction.insert(
-        // TODO Hash it to avoid copying, we need a good hash function for Board
-        hash,
-        *board_played_times_prediction.get(&hash).unwrap_or(&0) - 1,
-    );
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    hash: u64,
+    /// Plies since the last capture or pawn move
+    halfmove_clock: u32,
+    pawns: BitBoard,
+    pieces: u32,
 }
 
-pub fn count_board(board_played_times_prediction: &mut HashMap<u64, u32>, new_board: &Board) {
-    let hash = new_board.get_hash();
-    board_played_times_prediction.insert(
-        hash,
-        *board_played_times_prediction.get(&hash).unwrap_or(&0) + 1,
-    );
+/// Hashes of the positions played in the game, followed by those on the path that is being
+/// searched. Pushed when a move is made and popped when it is taken back.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RepetitionStack {
+    entries: Vec<Entry>,
+}
+
+impl RepetitionStack {
+    pub(crate) fn push(&mut self, board: &Board) {
+        let pawns = *board.pieces(Piece::Pawn);
+        let pieces = board.combined().popcnt();
+        let halfmove_clock = match self.entries.last() {
+            Some(last) if !utils::is_irreversible(last.pawns, last.pieces, pawns, pieces) => {
+                last.halfmove_clock + 1
+            }
+            _ => 0,
+        };
+        self.entries.push(Entry {
+            hash: board.get_hash(),
+            halfmove_clock,
+            pawns,
+            pieces,
+        });
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.entries.pop();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// How many times the board occurs in the game and on the search path. Only the positions
+    /// since the last capture or pawn move are scanned, as no earlier one can come back.
+    pub(crate) fn occurrences(&self, board: &Board) -> usize {
+        let Some(last) = self.entries.last() else {
+            return 0;
+        };
+        let hash = board.get_hash();
+        self.entries
+            .iter()
+            .rev()
+            .take(last.halfmove_clock as usize + 1)
+            .filter(|entry| entry.hash == hash)
+            .count()
+    }
+
+    /// Plies since the last capture or pawn move before `board`, which is either the last board
+    /// pushed or one move past it, like the leaves of quiescence search
+    pub(crate) fn halfmove_clock(&self, board: &Board) -> u32 {
+        let pawns = *board.pieces(Piece::Pawn);
+        let pieces = board.combined().popcnt();
+        match self.entries.last() {
+            Some(last) if last.hash == board.get_hash() => last.halfmove_clock,
+            Some(last) if !utils::is_irreversible(last.pawns, last.pieces, pawns, pieces) => {
+                last.halfmove_clock + 1
+            }
+            _ => 0,
+        }
+    }
+
+    /// Whether the board is the last one pushed and fifty moves by each side were played
+    /// without a capture or pawn move before it
+    pub(crate) fn fifty_move_draw(&self, board: &Board) -> bool {
+        self.entries.last().is_some_and(|last| {
+            last.hash == board.get_hash() && last.halfmove_clock >= FIFTY_MOVE_PLIES
+        })
+    }
 }
@@ -1,24 +1,152 @@
 use crate::algorithms::utils::Evaluation;
+use crate::common::constants::{MATE_SCORE, MAX_MATE_PLIES};
 use chess::{Board, Color};
+use std::fmt;
+
+/// The terms a static eval is made of, for ANALYZE. Each term is in centipawns from white's
+/// point of view, as it is before the eval scale. Terms of disabled modules are 0, and so are
+/// all of them when a known ending or NNUE evaluated the position instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct EvalBreakdown {
+    pub(crate) material: i32,
+    pub(crate) material_imbalance: i32,
+    /// Every PSQT together, after the PSQT scale
+    pub(crate) psqt: i32,
+    pub(crate) square_control: i32,
+    pub(crate) pawn_structure: i32,
+    pub(crate) rook_files: i32,
+    pub(crate) passed_pawns: i32,
+    pub(crate) pawn_majority: i32,
+    pub(crate) rook_coordination: i32,
+    pub(crate) rook_on_seventh: i32,
+    pub(crate) king_tropism: i32,
+    pub(crate) king_activity: i32,
+    pub(crate) king_danger: i32,
+    pub(crate) pawn_shield: i32,
+    pub(crate) trapped_pieces: i32,
+    pub(crate) bishop_color: i32,
+    pub(crate) threats: i32,
+    pub(crate) tempo: i32,
+    pub(crate) early_queen: i32,
+    /// Taken off the winning side when the losing side is close to stalemate, after the
+    /// drawishness scale
+    pub(crate) stalemate_trap: i32,
+    /// Of a known ending, from white's point of view
+    pub(crate) known_ending: Option<i32>,
+    /// Of the network, from white's point of view
+    pub(crate) nnue: Option<i32>,
+    /// How much of the sum of the terms is kept for drawishness, out of
+    /// `endgames::NORMAL_SCALE`
+    pub(crate) drawishness_scale: Option<i32>,
+    /// Percent of the sum of the terms kept for the halfmove clock
+    pub(crate) halfmove_scale: Option<i32>,
+    /// The whole eval from the point of view of the side to move, as `Algorithm::eval` returns it
+    pub(crate) total: i32,
+}
+
+/// Lists the terms that aren't 0, then the total
+impl fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = [
+            ("material", Some(self.material)),
+            ("material_imbalance", Some(self.material_imbalance)),
+            ("psqt", Some(self.psqt)),
+            ("square_control", Some(self.square_control)),
+            ("pawn_structure", Some(self.pawn_structure)),
+            ("rook_files", Some(self.rook_files)),
+            ("passed_pawns", Some(self.passed_pawns)),
+            ("pawn_majority", Some(self.pawn_majority)),
+            ("rook_coordination", Some(self.rook_coordination)),
+            ("rook_on_seventh", Some(self.rook_on_seventh)),
+            ("king_tropism", Some(self.king_tropism)),
+            ("king_activity", Some(self.king_activity)),
+            ("king_danger", Some(self.king_danger)),
+            ("pawn_shield", Some(self.pawn_shield)),
+            ("trapped_pieces", Some(self.trapped_pieces)),
+            ("bishop_color", Some(self.bishop_color)),
+            ("threats", Some(self.threats)),
+            ("tempo", Some(self.tempo)),
+            ("early_queen", Some(self.early_queen)),
+            ("known_ending", self.known_ending),
+            ("nnue", self.nnue),
+            ("drawishness_scale", self.drawishness_scale),
+            ("halfmove_scale", self.halfmove_scale),
+            ("stalemate_trap", Some(self.stalemate_trap)),
+        ];
+        for (name, value) in terms {
+            if let Some(value) = value.filter(|&value| value != 0) {
+                write!(f, "{} {}, ", name, value)?;
+            }
+        }
+        write!(f, "total {}", self.total)
+    }
+}
+
+/// Expected score, from 0 for a loss to 1 for a win, of the side whose point of view `eval` is
+/// from. `scale` is the eval at which that side is expected to score ten times as much as the
+/// other, e.g. `Algorithm::win_probability_scale`. Mate scores map to 0 and 1.
+pub(crate) fn win_probability(eval: f32, scale: f32) -> f32 {
+    1. / (1. + 10f32.powf(-eval / scale))
+}
+
+/// The score of the side to move being checkmated `ply` plies from the root
+pub(crate) fn mated_score(ply: u32) -> i32 {
+    -(MATE_SCORE - ply.min(MAX_MATE_PLIES) as i32)
+}
+
+/// Turns an eval from white's point of view into one from `color`'s point of view. The same
+/// conversion also goes back the other way.
+pub(crate) fn from_perspective(color: Color, eval: i32) -> i32 {
+    if color == Color::White {
+        eval
+    } else {
+        -eval
+    }
+}
+
+pub(crate) fn is_mate_score(eval: i32) -> bool {
+    eval.saturating_abs() >= MATE_SCORE - MAX_MATE_PLIES as i32
+}
+
+/// Number of plies from the root to the mate, None if the eval isn't a mate
+pub(crate) fn mate_plies(eval: i32) -> Option<u32> {
+    is_mate_score(eval).then(|| (MATE_SCORE - eval.abs()) as u32)
+}
+
+/// Mate scores count plies from the node instead of from the root, as stored in the
+/// transposition table where the same position may be reached at any ply
+pub(crate) fn mate_score_to_node(eval: Option<i32>, ply: u32) -> Option<i32> {
+    eval.map(|eval| {
+        if is_mate_score(eval) {
+            eval + eval.signum() * ply as i32
+        } else {
+            eval
+        }
+    })
+}
+
+/// Mate scores count plies from the root again, the inverse of `mate_score_to_node`
+pub(crate) fn mate_score_from_node(eval: Option<i32>, ply: u32) -> Option<i32> {
+    eval.map(|eval| {
+        if is_mate_score(eval) {
+            eval - eval.signum() * ply as i32
+        } else {
+            eval
+        }
+    })
+}
 
 /// Investigate the reason for there being no legal moves, and return a score based on that.
-pub fn eval_no_legal_moves(board: &Board) -> f32 {
+pub fn eval_no_legal_moves(board: &Board, ply: u32) -> i32 {
     if board.checkers().popcnt() == 0 {
         // Is Stalemate, no checking pieces
-        return 0.;
+        return 0;
     }
 
     // If we arrive at here while it is checkmate, then we know that the side playing
     // has been checkmated.
-    if board.side_to_move() == Color::White {
-        f32::MIN
-    } else {
-        f32::MAX
-    }
+    mated_score(ply)
 }
-pub(crate) fn new_eval_is_better(maximise: bool, old: &Evaluation, new: &Evaluation) -> bool {
-    new.eval.is_some()
-        && (old.eval.is_none()
-            || maximise && new.eval.unwrap() > old.eval.unwrap()
-            || !maximise && new.eval.unwrap() < old.eval.unwrap())
+pub(crate) fn new_eval_is_better(old: &Evaluation, new: &Evaluation) -> bool {
+    new.eval.is_some() && (old.eval.is_none() || new.eval.unwrap() > old.eval.unwrap())
 }
@@ -0,0 +1,242 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::io::{check_weights_version, weights_version_header};
+use crate::modules::{
+    bishop_color, early_queen, halfmove_scaling, king_activity, king_danger, king_tropism,
+    material_imbalance, passed_pawns, pawn_majority, pawn_shield, pawn_structure,
+    rook_coordination, rook_files, rook_on_seventh, square_control, stalemate_trap, tempo, threats,
+    trapped_pieces,
+};
+
+/// Centipawns per pawn of material
+const DEFAULT_MATERIAL_WEIGHT: i32 = 100;
+/// Every PSQT is scaled by this many percent
+const DEFAULT_PSQT_SCALE: i32 = 100;
+
+/// A weight of the eval made of one or more numbers, e.g. a middlegame and endgame pair
+trait Weight {
+    fn values(&self) -> Vec<i32>;
+    /// Whether there is a number at `index` to set
+    fn set(&mut self, index: usize, value: i32) -> bool;
+}
+
+impl Weight for i32 {
+    fn values(&self) -> Vec<i32> {
+        vec![*self]
+    }
+
+    fn set(&mut self, index: usize, value: i32) -> bool {
+        if index == 0 {
+            *self = value;
+        }
+        index == 0
+    }
+}
+
+impl Weight for (i32, i32) {
+    fn values(&self) -> Vec<i32> {
+        vec![self.0, self.1]
+    }
+
+    fn set(&mut self, index: usize, value: i32) -> bool {
+        match index {
+            0 => self.0 = value,
+            1 => self.1 = value,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl<const N: usize> Weight for [i32; N] {
+    fn values(&self) -> Vec<i32> {
+        self.to_vec()
+    }
+
+    fn set(&mut self, index: usize, value: i32) -> bool {
+        self.get_mut(index).map(|weight| *weight = value).is_some()
+    }
+}
+
+/// Declares the fields of `EvalParams` once, together with their defaults, so that they can
+/// also be reached by name
+macro_rules! eval_params {
+    ($($(#[$doc:meta])* $name:ident: $type:ty = $default:expr,)*) => {
+        /// Every weight of the handcrafted eval. Each `Algorithm` carries its own, so that two
+        /// algorithms can differ only in their weights.
+        #[derive(Clone, Debug, PartialEq, Hash)]
+        pub(crate) struct EvalParams {
+            $($(#[$doc])* pub(crate) $name: $type,)*
+        }
+
+        impl Default for EvalParams {
+            fn default() -> Self {
+                EvalParams {
+                    $($name: $default,)*
+                }
+            }
+        }
+
+        impl EvalParams {
+            /// Names of the weights, in the order they are written
+            const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+
+            fn weight(&self, name: &str) -> Option<&dyn Weight> {
+                match name {
+                    $(stringify!($name) => Some(&self.$name),)*
+                    _ => None,
+                }
+            }
+
+            fn weight_mut(&mut self, name: &str) -> Option<&mut dyn Weight> {
+                match name {
+                    $(stringify!($name) => Some(&mut self.$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+eval_params! {
+    material_weight: i32 = DEFAULT_MATERIAL_WEIGHT,
+    knight_pawn_adjustment: i32 = material_imbalance::DEFAULT_KNIGHT_PAWN_ADJUSTMENT,
+    bishop_pawn_adjustment: i32 = material_imbalance::DEFAULT_BISHOP_PAWN_ADJUSTMENT,
+    /// In percent, for NAIVE_PSQT and both tapered PeSTO PSQTs
+    psqt_scale: i32 = DEFAULT_PSQT_SCALE,
+    /// Pawn, knight, bishop, rook, queen and king
+    control_weights: [i32; 6] = square_control::DEFAULT_CONTROL_WEIGHTS,
+    center_control_bonus: i32 = square_control::DEFAULT_CENTER_CONTROL_BONUS,
+    king_zone_control_bonus: i32 = square_control::DEFAULT_KING_ZONE_CONTROL_BONUS,
+    isolated_penalty: (i32, i32) = pawn_structure::DEFAULT_ISOLATED_PENALTY,
+    doubled_penalty: (i32, i32) = pawn_structure::DEFAULT_DOUBLED_PENALTY,
+    backward_penalty: (i32, i32) = pawn_structure::DEFAULT_BACKWARD_PENALTY,
+    passed_mg_rank_bonus: [i32; 8] = passed_pawns::DEFAULT_MG_RANK_BONUS,
+    passed_eg_rank_bonus: [i32; 8] = passed_pawns::DEFAULT_EG_RANK_BONUS,
+    passed_protected_bonus: i32 = passed_pawns::DEFAULT_PROTECTED_BONUS,
+    passed_connected_bonus: i32 = passed_pawns::DEFAULT_CONNECTED_BONUS,
+    candidate_mg_rank_bonus: [i32; 8] = passed_pawns::DEFAULT_CANDIDATE_MG_RANK_BONUS,
+    candidate_eg_rank_bonus: [i32; 8] = passed_pawns::DEFAULT_CANDIDATE_EG_RANK_BONUS,
+    blockade_penalty: i32 = passed_pawns::DEFAULT_BLOCKADE_PENALTY,
+    knight_blockade_penalty: i32 = passed_pawns::DEFAULT_KNIGHT_BLOCKADE_PENALTY,
+    outside_passer_bonus: i32 = pawn_majority::DEFAULT_OUTSIDE_PASSER_BONUS,
+    pawn_majority_bonus: i32 = pawn_majority::DEFAULT_PAWN_MAJORITY_BONUS,
+    open_file_bonus: i32 = rook_files::DEFAULT_OPEN_FILE_BONUS,
+    semi_open_file_bonus: i32 = rook_files::DEFAULT_SEMI_OPEN_FILE_BONUS,
+    behind_own_passer_bonus: i32 = rook_coordination::DEFAULT_BEHIND_OWN_PASSER_BONUS,
+    behind_enemy_passer_bonus: i32 = rook_coordination::DEFAULT_BEHIND_ENEMY_PASSER_BONUS,
+    connected_rooks_bonus: i32 = rook_coordination::DEFAULT_CONNECTED_ROOKS_BONUS,
+    rook_on_seventh_bonus: (i32, i32) = rook_on_seventh::DEFAULT_ROOK_ON_SEVENTH_BONUS,
+    /// Knight, bishop, rook and queen
+    tropism_weights: [i32; 4] = king_tropism::DEFAULT_TROPISM_WEIGHTS,
+    /// Pawn, knight, bishop, rook, queen and king
+    king_attack_weights: [i32; 6] = king_danger::DEFAULT_KING_ATTACK_WEIGHTS,
+    shield_penalty: [i32; 8] = pawn_shield::DEFAULT_SHIELD_PENALTY,
+    storm_penalty: [i32; 8] = pawn_shield::DEFAULT_STORM_PENALTY,
+    trapped_bishop_penalty: i32 = trapped_pieces::DEFAULT_TRAPPED_BISHOP_PENALTY,
+    trapped_knight_penalty: i32 = trapped_pieces::DEFAULT_TRAPPED_KNIGHT_PENALTY,
+    trapped_rook_penalty: i32 = trapped_pieces::DEFAULT_TRAPPED_ROOK_PENALTY,
+    hanging_bonus: i32 = threats::DEFAULT_HANGING_BONUS,
+    lower_attacker_bonus: i32 = threats::DEFAULT_LOWER_ATTACKER_BONUS,
+    tempo_bonus: (i32, i32) = tempo::DEFAULT_TEMPO_BONUS,
+    /// In plies
+    halfmove_scale_start: i32 = halfmove_scaling::DEFAULT_HALFMOVE_SCALE_START,
+    stalemate_winning_margin: i32 = stalemate_trap::DEFAULT_STALEMATE_WINNING_MARGIN,
+    stalemate_trap_penalty: i32 = stalemate_trap::DEFAULT_STALEMATE_TRAP_PENALTY,
+    early_queen_penalty: i32 = early_queen::DEFAULT_EARLY_QUEEN_PENALTY,
+    king_center_bonus: i32 = king_activity::DEFAULT_KING_CENTER_BONUS,
+    king_own_passer_bonus: i32 = king_activity::DEFAULT_KING_OWN_PASSER_BONUS,
+    king_enemy_passer_bonus: i32 = king_activity::DEFAULT_KING_ENEMY_PASSER_BONUS,
+    bad_bishop_penalty: (i32, i32) = bishop_color::DEFAULT_BAD_BISHOP_PENALTY,
+    color_weakness_penalty: i32 = bishop_color::DEFAULT_COLOR_WEAKNESS_PENALTY,
+}
+
+impl EvalParams {
+    /// Reads weights written as lines of a name followed by its numbers, e.g.
+    /// `isolated_penalty 10 20`, after the header of `weights_version_header`. Weights that
+    /// aren't in the file keep their defaults and lines starting with `#` are skipped. Fails if
+    /// the weights were written for another eval version.
+    pub(crate) fn load(path: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line + 1, message),
+            )
+        };
+        let mut params = EvalParams::default();
+        let contents = fs::read_to_string(path)?;
+        check_weights_version(contents.lines().next().unwrap_or_default())?;
+        for (i, line) in contents.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let Some(name) = words.next().filter(|name| !name.starts_with('#')) else {
+                continue;
+            };
+            let values = words
+                .map(str::parse)
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|error| invalid(i, error.to_string()))?;
+            let weight = params
+                .weight_mut(name)
+                .ok_or_else(|| invalid(i, format!("unknown weight {}", name)))?;
+            if values.len() != weight.values().len() {
+                return Err(invalid(
+                    i,
+                    format!("{} needs {} numbers", name, weight.values().len()),
+                ));
+            }
+            for (index, value) in values.into_iter().enumerate() {
+                weight.set(index, value);
+            }
+        }
+        Ok(params)
+    }
+
+    /// The number called `name`, either `weight[index]` or just `weight` for its first number
+    pub(crate) fn get(&self, name: &str) -> Option<i32> {
+        let (name, index) = split_index(name)?;
+        self.weight(name)?.values().get(index).copied()
+    }
+
+    /// Sets the number called `name`, named as in `get`
+    pub(crate) fn set(&mut self, name: &str, value: i32) -> Result<(), String> {
+        let set = split_index(name)
+            .and_then(|(weight, index)| {
+                self.weight_mut(weight)
+                    .map(|weight| weight.set(index, value))
+            })
+            .unwrap_or(false);
+        if set {
+            Ok(())
+        } else {
+            Err(format!("Unknown eval weight {}", name))
+        }
+    }
+}
+
+/// Writes the weights in the format `EvalParams::load` reads
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", weights_version_header())?;
+        for name in EvalParams::NAMES {
+            let values: Vec<String> = self
+                .weight(name)
+                .map(|weight| weight.values())
+                .unwrap_or_default()
+                .iter()
+                .map(i32::to_string)
+                .collect();
+            writeln!(f, "{} {}", name, values.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `weight[index]` into the weight and the index, with index 0 when there is none
+fn split_index(name: &str) -> Option<(&str, usize)> {
+    match name.split_once('[') {
+        Some((weight, index)) => Some((weight, index.strip_suffix(']')?.parse().ok()?)),
+        None => Some((name, 0)),
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::common::constants::modules::{
+    BISHOP_COLOR, EARLY_QUEEN, ENDGAMES, HALFMOVE_SCALING, KING_ACTIVITY, KING_DANGER,
+    KING_TROPISM, MATERIAL_IMBALANCE, NNUE, PASSED_PAWNS, PAWN_MAJORITY, PAWN_SHIELD,
+    PAWN_STRUCTURE, ROOK_COORDINATION, ROOK_FILES, ROOK_ON_SEVENTH, STALEMATE_TRAP,
+    TAPERED_INCREMENTAL_PESTO_PSQT, TEMPO, THREATS, TRAPPED_PIECES,
+};
+
+/// Where the profiles that aren't compiled in read their weights, PSQTs and network from
+const TUNED_2024_EVAL_PARAMS_FILE: &str = "./profiles/tuned-2024-params.txt";
+const TUNED_2024_PSQT_FILE: &str = "./profiles/tuned-2024-psqt.txt";
+const NNUE_NETWORK_FILE: &str = "./profiles/nnue.net";
+
+/// A whole generation of the eval, so that generations can be compared against each other with
+/// the same search instead of one module at a time. See `Algorithm::with_eval_profile`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum EvalProfile {
+    /// Nothing but material
+    MaterialOnly,
+    /// Material and the tapered PeSTO PSQT
+    Pesto,
+    /// Every handcrafted term, with weights and PSQTs tuned by self-play
+    Tuned2024,
+    /// The network, with known endings still recognized
+    Nnue,
+}
+
+pub(crate) const ALL_EVAL_PROFILES: [EvalProfile; 4] = [
+    EvalProfile::MaterialOnly,
+    EvalProfile::Pesto,
+    EvalProfile::Tuned2024,
+    EvalProfile::Nnue,
+];
+
+impl EvalProfile {
+    /// Eval modules of the profile. They replace every eval module of the module set that the
+    /// profile is used with.
+    pub(crate) fn modules(self) -> u64 {
+        match self {
+            EvalProfile::MaterialOnly => 0,
+            EvalProfile::Pesto => TAPERED_INCREMENTAL_PESTO_PSQT,
+            EvalProfile::Tuned2024 => {
+                TAPERED_INCREMENTAL_PESTO_PSQT
+                    | MATERIAL_IMBALANCE
+                    | PAWN_STRUCTURE
+                    | PASSED_PAWNS
+                    | PAWN_MAJORITY
+                    | ROOK_FILES
+                    | ROOK_COORDINATION
+                    | ROOK_ON_SEVENTH
+                    | KING_TROPISM
+                    | KING_ACTIVITY
+                    | KING_DANGER
+                    | PAWN_SHIELD
+                    | TRAPPED_PIECES
+                    | BISHOP_COLOR
+                    | THREATS
+                    | TEMPO
+                    | EARLY_QUEEN
+                    | ENDGAMES
+                    | STALEMATE_TRAP
+                    | HALFMOVE_SCALING
+            }
+            EvalProfile::Nnue => NNUE | ENDGAMES,
+        }
+    }
+
+    /// File of eval weights to load, as written by `EvalParams`' Display
+    pub(crate) fn eval_params_file(self) -> Option<&'static str> {
+        (self == EvalProfile::Tuned2024).then_some(TUNED_2024_EVAL_PARAMS_FILE)
+    }
+
+    /// File of PSQTs to load, as written by `PsqtTables`' Display
+    pub(crate) fn psqt_file(self) -> Option<&'static str> {
+        (self == EvalProfile::Tuned2024).then_some(TUNED_2024_PSQT_FILE)
+    }
+
+    /// File of the network to load
+    pub(crate) fn network_file(self) -> Option<&'static str> {
+        (self == EvalProfile::Nnue).then_some(NNUE_NETWORK_FILE)
+    }
+}
+
+impl fmt::Display for EvalProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EvalProfile::MaterialOnly => "material-only",
+            EvalProfile::Pesto => "pesto",
+            EvalProfile::Tuned2024 => "tuned-2024",
+            EvalProfile::Nnue => "nnue",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for EvalProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_EVAL_PROFILES
+            .into_iter()
+            .find(|profile| profile.to_string() == s)
+            .ok_or(format!(
+                "Unknown eval profile {}, expected material-only, pesto, tuned-2024 or nnue",
+                s
+            ))
+    }
+}
@@ -1,4 +1,13 @@
+pub(crate) mod attack_maps;
 mod draw_checker;
-mod eval;
+pub(crate) mod eval;
+pub(crate) mod eval_params;
+pub(crate) mod eval_profile;
+mod pawn_hash;
+pub(crate) mod principal_variation;
+pub(crate) mod psqt_tables;
+mod search_stack;
 pub(crate) mod the_algorithm;
+pub(crate) mod time_management;
 pub(crate) mod utils;
+mod zobrist;
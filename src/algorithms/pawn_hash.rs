@@ -0,0 +1,164 @@
+use chess::{BitBoard, Board, Piece, ALL_COLORS};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::DARK_SQUARES;
+use crate::modules::passed_pawns;
+use crate::modules::pawn_structure;
+
+/// Entries of the table, a power of two so that the low bits of the pawn key index it
+const PAWN_HASH_ENTRIES: usize = 1 << 12;
+/// Random keys of a pawn of each color on each square, XORed together into the pawn key
+const PAWN_KEYS: [[u64; 64]; 2] = pawn_keys();
+
+/// Fills the key table with splitmix64, so that the keys are the same every run
+const fn pawn_keys() -> [[u64; 64]; 2] {
+    let mut keys = [[0; 64]; 2];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < 128 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i / 64][i % 64] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Everything about a pawn structure that other eval terms reuse, by color index
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PawnEntry {
+    /// Middlegame and endgame pawn structure scores of each side
+    pub(crate) structure: [(i32, i32); 2],
+    /// Passed pawns of each side
+    pub(crate) passed: [BitBoard; 2],
+    /// Candidate passed pawns of each side, see `passed_pawns::candidate_passers`
+    pub(crate) candidates: [BitBoard; 2],
+    /// Pawns of each side on dark and light squares, for the bishops of those colors
+    pub(crate) dark_pawns: [BitBoard; 2],
+    pub(crate) light_pawns: [BitBoard; 2],
+}
+
+impl PawnEntry {
+    fn new(pawns: [BitBoard; 2], params: &EvalParams) -> Self {
+        PawnEntry {
+            structure: ALL_COLORS.map(|color| {
+                pawn_structure::pawn_structure_score(
+                    pawns[color.to_index()].to_size(0) as u64,
+                    color,
+                    params,
+                )
+            }),
+            passed: ALL_COLORS.map(|color| {
+                passed_pawns::passed_pawns(
+                    pawns[color.to_index()],
+                    pawns[(!color).to_index()],
+                    color,
+                )
+            }),
+            candidates: ALL_COLORS.map(|color| {
+                passed_pawns::candidate_passers(
+                    pawns[color.to_index()],
+                    pawns[(!color).to_index()],
+                    color,
+                )
+            }),
+            dark_pawns: pawns.map(|pawns| pawns & BitBoard::new(DARK_SQUARES)),
+            light_pawns: pawns.map(|pawns| pawns & !BitBoard::new(DARK_SQUARES)),
+        }
+    }
+}
+
+/// Pawns rarely move, so everything that only depends on them is hashed by a key of the pawns
+/// of both sides. The table has a fixed number of entries indexed by the low bits of the key,
+/// and each entry keeps the pawns it was computed for, so that structures whose keys collide
+/// replace each other instead of being mixed up.
+#[derive(Clone, Debug)]
+pub(crate) struct PawnHashTable {
+    entries: Vec<Option<([BitBoard; 2], PawnEntry)>>,
+}
+
+impl Default for PawnHashTable {
+    fn default() -> Self {
+        PawnHashTable {
+            entries: vec![None; PAWN_HASH_ENTRIES],
+        }
+    }
+}
+
+impl PawnHashTable {
+    /// The entry of the pawns on `board`, computed and stored if they aren't in the table.
+    /// The table has to be cleared when `params` change.
+    pub(crate) fn probe(&mut self, board: &Board, params: &EvalParams) -> PawnEntry {
+        let pawns = ALL_COLORS.map(|color| board.pieces(Piece::Pawn) & board.color_combined(color));
+        self.probe_pawns(pawns, params)
+    }
+
+    fn probe_pawns(&mut self, pawns: [BitBoard; 2], params: &EvalParams) -> PawnEntry {
+        let index = pawn_key(pawns) as usize & (PAWN_HASH_ENTRIES - 1);
+        match self.entries[index] {
+            Some((stored_pawns, entry)) if stored_pawns == pawns => entry,
+            _ => {
+                let entry = PawnEntry::new(pawns, params);
+                self.entries[index] = Some((pawns, entry));
+                entry
+            }
+        }
+    }
+}
+
+fn pawn_key(pawns: [BitBoard; 2]) -> u64 {
+    let mut key = 0;
+    for color in ALL_COLORS {
+        for square in pawns[color.to_index()] {
+            key ^= PAWN_KEYS[color.to_index()][square.to_index()];
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::BitBoard;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{pawn_key, PawnEntry, PawnHashTable, PAWN_HASH_ENTRIES};
+    use crate::algorithms::eval_params::EvalParams;
+
+    /// Pawns of both sides on random squares of the second to seventh ranks
+    fn random_pawns(rng: &mut StdRng) -> [BitBoard; 2] {
+        let ranks = 0x00FF_FFFF_FFFF_FF00;
+        let white = rng.gen::<u64>() & rng.gen::<u64>() & ranks;
+        let black = rng.gen::<u64>() & rng.gen::<u64>() & ranks & !white;
+        [BitBoard::new(white), BitBoard::new(black)]
+    }
+
+    fn check_entry(entry: PawnEntry, pawns: [BitBoard; 2], params: &EvalParams) {
+        let expected = PawnEntry::new(pawns, params);
+        assert_eq!(entry.structure, expected.structure);
+        assert_eq!(entry.passed, expected.passed);
+        assert_eq!(entry.candidates, expected.candidates);
+    }
+
+    #[test]
+    fn colliding_structures_are_not_mixed_up() {
+        let params = EvalParams::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut seen = vec![None; PAWN_HASH_ENTRIES];
+        let (first, second) = loop {
+            let pawns = random_pawns(&mut rng);
+            let index = pawn_key(pawns) as usize & (PAWN_HASH_ENTRIES - 1);
+            match seen[index] {
+                Some(other) if other != pawns => break (other, pawns),
+                _ => seen[index] = Some(pawns),
+            }
+        };
+
+        let mut table = PawnHashTable::default();
+        for pawns in [first, second, first, first, second] {
+            check_entry(table.probe_pawns(pawns, &params), pawns, &params);
+        }
+    }
+}
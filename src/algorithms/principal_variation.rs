@@ -0,0 +1,73 @@
+use std::fmt;
+
+use chess::ChessMove;
+
+use crate::common::utils;
+
+/// A line from the root together with its eval
+#[derive(Clone, Debug)]
+pub(crate) struct ScoredLine {
+    /// Positive when white is better
+    pub(crate) eval: Option<i32>,
+    pub(crate) line: Vec<ChessMove>,
+}
+
+impl fmt::Display for ScoredLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.eval {
+            Some(eval) => write!(f, "{}: {}", eval, utils::line_to_string(&self.line)),
+            None => write!(f, "-: {}", utils::line_to_string(&self.line)),
+        }
+    }
+}
+
+/// Triangular table of principal variations, the lines both sides are expected to play. The line
+/// at a ply is the best one found from the node being searched there.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PvTable {
+    lines: Vec<Vec<ChessMove>>,
+}
+
+impl PvTable {
+    /// Forgets the line at `ply`, before a node there gets searched
+    pub(crate) fn clear(&mut self, ply: u32) {
+        let ply = ply as usize;
+        self.lines
+            .resize_with(self.lines.len().max(ply + 1), Vec::new);
+        self.lines[ply].clear();
+    }
+
+    /// A new best move was found at `ply`, so its line becomes that move followed by the line of
+    /// the child it leads to
+    pub(crate) fn update(&mut self, ply: u32, chess_move: ChessMove) {
+        let ply = ply as usize;
+        self.lines
+            .resize_with(self.lines.len().max(ply + 2), Vec::new);
+        let (parents, children) = self.lines.split_at_mut(ply + 1);
+        let line = &mut parents[ply];
+        line.clear();
+        line.push(chess_move);
+        line.extend_from_slice(&children[0]);
+    }
+
+    /// The line from the node at `ply`
+    pub(crate) fn line(&self, ply: u32) -> Vec<ChessMove> {
+        self.lines.get(ply as usize).cloned().unwrap_or_default()
+    }
+
+    /// The line from the root of the last search
+    pub(crate) fn root_line(&self) -> Vec<ChessMove> {
+        self.line(0)
+    }
+
+    /// Sets the line at `ply`, for nodes that weren't searched, e.g. on a transposition hit
+    pub(crate) fn set_line(&mut self, ply: u32, line: Vec<ChessMove>) {
+        self.clear(ply);
+        self.lines[ply as usize] = line;
+    }
+
+    /// Sets the line from the root, for root moves that were searched elsewhere
+    pub(crate) fn set_root_line(&mut self, line: Vec<ChessMove>) {
+        self.set_line(0, line);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+use chess::NUM_PIECES;
+
+use crate::common::constants::naive_psqt_tables::{
+    NAIVE_PSQT_TABLE_BISHOP, NAIVE_PSQT_TABLE_KING, NAIVE_PSQT_TABLE_KNIGHT, NAIVE_PSQT_TABLE_PAWN,
+    NAIVE_PSQT_TABLE_QUEEN, NAIVE_PSQT_TABLE_ROOK,
+};
+use crate::common::constants::tapered_pesto_psqt_tables::{TAPERED_EG_PESTO, TAPERED_MG_PESTO};
+use crate::io::{check_weights_version, weights_version_header};
+
+/// Names of the pieces in the names of the tables, by piece index
+const PIECE_NAMES: [&str; NUM_PIECES] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+/// The square tables of NAIVE_PSQT and of both tapered PeSTO PSQTs, by piece index. Every table
+/// is laid out from a8 as seen by white. Each `Algorithm` carries its own, so that two sets of
+/// tables can compete without recompiling.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub(crate) struct PsqtTables {
+    pub(crate) naive: [[i32; 64]; NUM_PIECES],
+    /// Middlegame and endgame tables of TAPERED_EVERY_PESTO_PSQT and
+    /// TAPERED_INCREMENTAL_PESTO_PSQT
+    pub(crate) mg: [[i32; 64]; NUM_PIECES],
+    pub(crate) eg: [[i32; 64]; NUM_PIECES],
+}
+
+impl Default for PsqtTables {
+    fn default() -> Self {
+        PsqtTables {
+            naive: [
+                NAIVE_PSQT_TABLE_PAWN,
+                NAIVE_PSQT_TABLE_KNIGHT,
+                NAIVE_PSQT_TABLE_BISHOP,
+                NAIVE_PSQT_TABLE_ROOK,
+                NAIVE_PSQT_TABLE_QUEEN,
+                NAIVE_PSQT_TABLE_KING,
+            ],
+            mg: TAPERED_MG_PESTO,
+            eg: TAPERED_EG_PESTO,
+        }
+    }
+}
+
+impl PsqtTables {
+    /// Reads tables written as lines of a name followed by its 64 numbers, e.g.
+    /// `mg_knight -167 -89 ...`, after the header of `weights_version_header`. Names are
+    /// `naive`, `mg` or `eg` and the piece, joined by `_`. Tables that aren't in the file keep
+    /// their defaults and lines starting with `#` are skipped. Fails if the tables were written
+    /// for another eval version.
+    pub(crate) fn load(path: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", line + 1, message),
+            )
+        };
+        let mut tables = PsqtTables::default();
+        let contents = fs::read_to_string(path)?;
+        check_weights_version(contents.lines().next().unwrap_or_default())?;
+        for (i, line) in contents.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let Some(name) = words.next().filter(|name| !name.starts_with('#')) else {
+                continue;
+            };
+            let values = words
+                .map(str::parse)
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|error| invalid(i, error.to_string()))?;
+            let table = tables
+                .table_mut(name)
+                .ok_or_else(|| invalid(i, format!("unknown table {}", name)))?;
+            *table = values
+                .try_into()
+                .map_err(|_| invalid(i, format!("{} needs 64 numbers", name)))?;
+        }
+        Ok(tables)
+    }
+
+    fn table_mut(&mut self, name: &str) -> Option<&mut [i32; 64]> {
+        let (set, piece) = name.split_once('_')?;
+        let piece = PIECE_NAMES
+            .iter()
+            .position(|&piece_name| piece_name == piece)?;
+        match set {
+            "naive" => Some(&mut self.naive[piece]),
+            "mg" => Some(&mut self.mg[piece]),
+            "eg" => Some(&mut self.eg[piece]),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the tables in the format `PsqtTables::load` reads
+impl fmt::Display for PsqtTables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", weights_version_header())?;
+        for (set, tables) in [("naive", &self.naive), ("mg", &self.mg), ("eg", &self.eg)] {
+            for (piece, table) in PIECE_NAMES.iter().zip(tables) {
+                let values: Vec<String> = table.iter().map(i32::to_string).collect();
+                writeln!(f, "{}_{} {}", set, piece, values.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::mem;
+
+use chess::{Board, ChessMove};
+
+use crate::algorithms::psqt_tables::PsqtTables;
+use crate::common::utils::Stats;
+use crate::modules::incremental_psqt::PsqtAccumulator;
+use crate::modules::nnue::{Network, NnueAccumulator};
+use crate::modules::quiescence::MAX_QUIESCENCE_DEPTH;
+
+/// Plies from the root after which the main search only evaluates, so that the per-ply state
+/// has a fixed size however far extensions go
+pub(crate) const MAX_PLY: u32 = 128;
+
+/// State of the node being searched at one ply
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Frame {
+    /// The move being searched from this node
+    // Not read yet, kept for heuristics that look at the moves leading to a node
+    #[allow(dead_code)]
+    pub(crate) current_move: Option<ChessMove>,
+    /// Eval of the position without searching it, if it was evaluated
+    pub(crate) static_eval: Option<i32>,
+    /// Quiet moves that caused beta cutoffs in nodes at this ply, most recent first. Siblings
+    /// often have the same refutation.
+    pub(crate) killers: [Option<ChessMove>; 2],
+    /// PSQT sums of the position at this ply, for TAPERED_INCREMENTAL_PESTO_PSQT
+    pub(crate) psqt: PsqtAccumulator,
+    /// Hidden layer of the NNUE network for the position at this ply
+    pub(crate) nnue: NnueAccumulator,
+}
+
+/// Counts of what happens at every node, kept as plain integers while searching and only
+/// folded into `Stats` when the search is done
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NodeCounters {
+    pub(crate) nodes_visited: u32,
+    pub(crate) leaves_visited: u32,
+    /// Boards evaluated, the ones decided by the rules left out
+    pub(crate) evals: u32,
+    pub(crate) quiescence_nodes: u32,
+    pub(crate) alpha_beta_breaks: u32,
+    pub(crate) max_depth: u32,
+    pub(crate) seldepth: u32,
+}
+
+impl NodeCounters {
+    pub(crate) fn fold_into(self, stats: &mut Stats) {
+        stats.nodes_visited += self.nodes_visited;
+        stats.leaves_visited += self.leaves_visited;
+        stats.evals += self.evals;
+        stats.quiescence_nodes += self.quiescence_nodes;
+        stats.alpha_beta_breaks += self.alpha_beta_breaks;
+        stats.max_depth = stats.max_depth.max(self.max_depth);
+        stats.seldepth = stats.seldepth.max(self.seldepth);
+    }
+}
+
+/// Per-ply state of the nodes on the path that is being searched, so that nodes can share it
+/// without passing it down through every call
+#[derive(Debug, Clone)]
+pub(crate) struct SearchStack {
+    frames: Vec<Frame>,
+    /// Turned off only to measure what counting costs
+    pub(crate) counting: bool,
+    counters: NodeCounters,
+}
+
+impl Default for SearchStack {
+    fn default() -> Self {
+        SearchStack {
+            frames: vec![Frame::default(); (MAX_PLY + MAX_QUIESCENCE_DEPTH + 1) as usize],
+            counting: true,
+            counters: NodeCounters::default(),
+        }
+    }
+}
+
+impl SearchStack {
+    pub(crate) fn frame(&self, ply: u32) -> &Frame {
+        &self.frames[ply as usize]
+    }
+
+    pub(crate) fn frame_mut(&mut self, ply: u32) -> &mut Frame {
+        &mut self.frames[ply as usize]
+    }
+
+    pub(crate) fn counters(&self) -> &NodeCounters {
+        &self.counters
+    }
+
+    /// Updates the counters, unless counting is turned off
+    pub(crate) fn count(&mut self, update: impl FnOnce(&mut NodeCounters)) {
+        if self.counting {
+            update(&mut self.counters);
+        }
+    }
+
+    /// The counters since they were last taken, starting them over from zero
+    pub(crate) fn take_counters(&mut self) -> NodeCounters {
+        mem::take(&mut self.counters)
+    }
+
+    /// Forgets the state of the previous node at `ply` when a new one is entered. Killers are
+    /// kept, as they are shared between all nodes at the ply.
+    pub(crate) fn enter(&mut self, ply: u32) {
+        let frame = self.frame_mut(ply);
+        frame.current_move = None;
+        frame.static_eval = None;
+    }
+
+    /// Updates the PSQT sums of the child at `ply + 1` for `chess_move` being made on `board`
+    /// at `ply`. Unmaking the move needs nothing, as the sums at `ply` are left as they were.
+    pub(crate) fn make_psqt_move(
+        &mut self,
+        ply: u32,
+        board: &Board,
+        chess_move: ChessMove,
+        tables: &PsqtTables,
+    ) {
+        self.frame_mut(ply + 1).psqt = self.frame(ply).psqt.after_move(board, chess_move, tables);
+    }
+
+    /// Updates the NNUE hidden layer of the child at `ply + 1` like `make_psqt_move`
+    pub(crate) fn make_nnue_move(
+        &mut self,
+        ply: u32,
+        board: &Board,
+        chess_move: ChessMove,
+        network: &Network,
+    ) {
+        self.frame_mut(ply + 1).nnue = network.after_move(self.frame(ply).nnue, board, chess_move);
+    }
+
+    /// Makes a move that caused a beta cutoff the newest killer at `ply`, if it is quiet
+    pub(crate) fn record_killer(&mut self, ply: u32, board: &Board, chess_move: ChessMove) {
+        let quiet =
+            board.piece_on(chess_move.get_dest()).is_none() && chess_move.get_promotion().is_none();
+        let killers = &mut self.frame_mut(ply).killers;
+        if quiet && killers[0] != Some(chess_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(chess_move);
+        }
+    }
+}
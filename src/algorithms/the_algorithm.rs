@@ -1,31 +1,175 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 
-use chess::{Action, BitBoard, Board, BoardStatus, ChessMove, Color, MoveGen, Piece};
+use chess::{Action, BitBoard, Board, BoardStatus, ChessMove, MoveGen, Piece};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use tokio::time::{Duration, Instant};
 
-use crate::algorithms::{draw_checker, eval};
-use crate::common::constants::{modules::*, naive_psqt_tables::*, tapered_pesto_psqt_tables::*};
-use crate::common::utils::{self, module_enabled, piece_value, Stats};
+use crate::algorithms::attack_maps::AttackMaps;
+use crate::algorithms::draw_checker::RepetitionStack;
+use crate::algorithms::eval::{self, EvalBreakdown};
+use crate::algorithms::eval_params::EvalParams;
+use crate::algorithms::eval_profile::EvalProfile;
+use crate::algorithms::psqt_tables::PsqtTables;
+use crate::common::constants::{
+    modules::*, DEFAULT_EVAL_CLAMP, DEFAULT_EVAL_SCALE, DEFAULT_WIN_PROBABILITY_SCALE, MATE_SCORE,
+    TIMING_SAMPLE_INTERVAL,
+};
+use crate::common::utils::{self, module_enabled, Stats};
+use crate::io::StableHasher;
 use crate::modules::{alpha_beta, analyze};
-use crate::modules::search_extensions;
-use crate::modules::skip_bad_moves;
-use crate::modules::transposition_table::{self, TranspositionEntry};
+use crate::modules::aspiration_windows::{self, FULL_WINDOW};
+use crate::modules::bishop_color;
+use crate::modules::early_queen;
+use crate::modules::early_termination;
+use crate::modules::easy_move;
+use crate::modules::endgames;
+use crate::modules::halfmove_scaling;
+use crate::modules::history_heuristic::HistoryTable;
+use crate::modules::incremental_psqt::PsqtAccumulator;
+use crate::modules::internal_iterative_deepening;
+use crate::modules::iteration_prediction::{self, DEFAULT_BRANCHING_FACTOR};
+use crate::modules::king_activity;
+use crate::modules::king_danger;
+use crate::modules::king_tropism;
+use crate::modules::late_move_pruning;
+use crate::modules::material_imbalance;
+use crate::modules::multi_cut;
+use crate::modules::nnue::Network;
+use crate::modules::passed_pawns;
+use crate::modules::pawn_majority;
+use crate::modules::pawn_shield;
+use crate::modules::principal_variation_search;
+use crate::modules::probcut::{self, DEFAULT_PROBCUT_MARGIN, DEFAULT_PROBCUT_MIN_DEPTH};
+use crate::modules::quiescence::{self, MAX_QUIESCENCE_DEPTH};
+use crate::modules::rook_coordination;
+use crate::modules::rook_files;
+use crate::modules::rook_on_seventh;
+use crate::modules::root_splitting::{self, DEFAULT_SEARCH_THREADS};
+use crate::modules::search_extensions::ExtensionPolicy;
+use crate::modules::skip_bad_moves::{self, DEFAULT_SKIP_FRACTION};
+use crate::modules::square_control;
+use crate::modules::stalemate_trap;
+use crate::modules::tempo;
+use crate::modules::threats;
+use crate::modules::transposition_table::{
+    self, ClearPolicy, TranspositionEntry, TranspositionTable, DEFAULT_TRANSPOSITION_TABLE_MB,
+};
+use crate::modules::trapped_pieces;
 
-use super::utils::Evaluation;
+use super::pawn_hash::PawnHashTable;
+use super::principal_variation::{PvTable, ScoredLine};
+use super::search_stack::{SearchStack, MAX_PLY};
+use super::utils::{Bound, Evaluation, LastMove, NodeType};
+
+/// Names of the numeric search settings that `Algorithm::parameter` and
+/// `Algorithm::set_parameter` can reach at runtime, e.g. for tuning. The eval weights can be
+/// reached too, by the names `EvalParams::get` takes.
+pub(crate) const TUNABLE_PARAMETERS: [&str; 6] = [
+    "lazy_eval_margin",
+    "probcut_margin",
+    "probcut_min_depth",
+    "skip_fraction",
+    "max_extensions_middlegame",
+    "max_extensions_endgame",
+];
 
 #[derive(Clone, Debug)]
 pub(crate) struct Algorithm {
-    pub(crate) modules: u32,
+    pub(crate) modules: u64,
     pub(crate) time_per_move: Duration,
-    /// Number of times that a given board has been played
-    pub(crate) board_played_times: HashMap<Board, u32>,
-    pub(crate) pawn_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_pawn_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_rook_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_king_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_queen_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_knight_hash: HashMap<BitBoard, f32>,
-    pub(crate) naive_psqt_bishop_hash: HashMap<BitBoard, f32>,
+    /// Positions played in the game, followed by the positions on the path being searched
+    pub(crate) position_history: RepetitionStack,
+    /// Middlegame and endgame pawn structure scores of one side's pawns
+    pub(crate) pawn_hash: PawnHashTable,
+    pub(crate) naive_psqt_pawn_hash: HashMap<BitBoard, i32>,
+    pub(crate) naive_psqt_rook_hash: HashMap<BitBoard, i32>,
+    pub(crate) naive_psqt_king_hash: HashMap<BitBoard, i32>,
+    pub(crate) naive_psqt_queen_hash: HashMap<BitBoard, i32>,
+    pub(crate) naive_psqt_knight_hash: HashMap<BitBoard, i32>,
+    pub(crate) naive_psqt_bishop_hash: HashMap<BitBoard, i32>,
+    /// When the transposition table is cleared. Competitions play every game pair on copies of
+    /// the algorithms, so there `Never` keeps the table of the algorithm it started from for
+    /// every pair, like `EveryGamePair`.
+    pub(crate) transposition_clear_policy: ClearPolicy,
+    pub(crate) transposition_table: TranspositionTable,
+    /// The sum of all eval terms is multiplied by this, so that every module set reports evals
+    /// in the same unit
+    pub(crate) eval_scale: f32,
+    /// Scaled evals are clamped to plus/minus this many centipawns. Checkmates are not affected.
+    pub(crate) eval_clamp: i32,
+    /// Maps the evals of this algorithm to expected scores, see `win_probability`. Calibrate it
+    /// for each module set so that their evals can be compared.
+    pub(crate) win_probability_scale: f32,
+    /// Weights of the handcrafted eval terms
+    pub(crate) eval_params: EvalParams,
+    /// Tables of NAIVE_PSQT and the tapered PeSTO PSQTs. Set them with `load_psqt_tables` so
+    /// that no sums of the old tables are kept around.
+    pub(crate) psqt_tables: Arc<PsqtTables>,
+    /// Evals that only material and the PSQTs put this far outside of the window skip the other
+    /// terms. `None` always computes the full eval.
+    pub(crate) lazy_eval_margin: Option<i32>,
+    /// Network that NNUE evaluates with instead of the handcrafted terms. NNUE does nothing
+    /// until one is loaded.
+    pub(crate) nnue: Option<Arc<Network>>,
+    /// Also search quiet checking moves on the first layer of quiescence search
+    pub(crate) quiescence_checks: bool,
+    /// Skip captures that lose material by static exchange evaluation in quiescence search
+    pub(crate) quiescence_see_pruning: bool,
+    /// Quiet moves that caused beta cutoffs, its size and decay policy can be set on it
+    pub(crate) history_table: HistoryTable,
+    /// How far past the window a ProbCut shallow search has to get for the node to be cut
+    pub(crate) probcut_margin: i32,
+    /// ProbCut is only tried this far or further from the leaves
+    pub(crate) probcut_min_depth: u32,
+    /// SKIP_BAD_MOVES skips the moves after this fraction of the legal moves
+    pub(crate) skip_fraction: f32,
+    /// Which moves SEARCH_EXTENSIONS extends
+    pub(crate) extension_policy: ExtensionPolicy,
+    /// Iterative deepening stops after this depth even if there is time left. `None` keeps
+    /// going until the deadline.
+    pub(crate) max_depth: Option<u32>,
+    /// Every move is searched to exactly this depth, ignoring the deadline and any module that
+    /// would stop iterative deepening early. Makes the moves independent of the hardware.
+    pub(crate) fixed_depth: Option<u32>,
+    /// Whether the search counts its nodes into the stats. Only turned off to measure what
+    /// counting costs.
+    pub(crate) collect_stats: bool,
+    /// Running estimate of how many times longer an iteration takes than the previous one, kept
+    /// between moves
+    pub(crate) branching_factor: f32,
+    /// Threads that ROOT_SPLITTING splits the root moves between
+    pub(crate) search_threads: usize,
+    /// How many lines, each starting with a different root move, that
+    /// `next_action_iterative_deepening` returns. The lines after the first are searched after
+    /// the deadline, to the same depth as the first.
+    pub(crate) multi_pv: usize,
+    /// Root moves get uniform random noise of up to this many centipawns added to their evals,
+    /// to vary the games, e.g. when generating training games. 0 turns it off.
+    pub(crate) root_noise: i32,
+    /// Seed of the root noise, taken when the algorithm is reset for a new game
+    pub(crate) noise_seed: u64,
+    noise_rng: StdRng,
+    /// Root moves that the search leaves out, as they start lines that were already found
+    excluded_root_moves: Vec<ChessMove>,
+    /// Best lines found by the current search
+    principal_variation: PvTable,
+    /// State of the nodes on the path being searched
+    search_stack: SearchStack,
+    /// Root moves of the last iteration ordered by their evals, best first. The next, deeper
+    /// iteration searches them in this order.
+    root_move_order: Vec<ChessMove>,
+    /// How much better the best root move of the last iteration was than the next best one.
+    /// The other evals are only upper bounds, so the real margin is at least this.
+    root_eval_margin: Option<i32>,
+    /// The board after our previous move in the game, to see what the opponent's move captured
+    last_position: Option<Board>,
 }
 
 struct NodeData {
@@ -42,253 +186,1172 @@ impl NodeData {
     }
 }
 
+/// What a node is searched with
+#[derive(Clone, Copy)]
+struct NodeArgs {
+    board: Board,
+    depth: u32,
+    alpha: i32,
+    beta: i32,
+    /// Whether this is the root of the search
+    original: bool,
+    node_type: NodeType,
+    ply: u32,
+    last_move: Option<LastMove>,
+    num_extensions: u32,
+}
+
+/// A node that has been entered, with the state it keeps between the searches below it
+struct Node {
+    /// The window is narrowed as moves are searched
+    args: NodeArgs,
+    /// Evals outside of the window the node was entered with are only bounds
+    entry_window: (i32, i32),
+    num_legal_moves: usize,
+    best_evaluation: Evaluation,
+    board_entry: Option<TranspositionEntry>,
+    /// The moves in the order they are searched, with the boards they lead to
+    boards: Vec<(ChessMove, Board, Option<TranspositionEntry>)>,
+    /// Moves left out of `boards` until the transposition move turns out not to cause a cutoff
+    remaining_moves: Option<MoveGen>,
+    /// Index of the next move of `boards` to search
+    next: usize,
+    searching_skipped_tail: bool,
+    /// The best eval before the skipped tail was searched, to tell whether skipping it would
+    /// have changed the result
+    eval_before_tail: Option<i32>,
+    /// The module whose cutoff is being checked by searching the node in full anyway
+    verifying_cutoff: Option<u64>,
+    root_evals: Vec<(ChessMove, i32)>,
+    /// Moves searched without a cutoff, penalized in the history table if a later one causes
+    /// a cutoff
+    searched_moves: Vec<ChessMove>,
+    debug_data: Option<Vec<String>>,
+}
+
+impl Node {
+    /// What the child that the move at `index` leads to is searched with. `window` is from the
+    /// point of view of this node.
+    fn child(
+        &self,
+        index: usize,
+        depth: u32,
+        window: (i32, i32),
+        node_type: NodeType,
+        extend_by: u32,
+    ) -> NodeArgs {
+        let (chess_move, new_board, _) = self.boards[index];
+        NodeArgs {
+            board: new_board,
+            depth,
+            alpha: -window.1,
+            beta: -window.0,
+            original: false,
+            node_type,
+            ply: self.args.ply + 1,
+            last_move: Some(LastMove {
+                chess_move,
+                captured: self.args.board.piece_on(chess_move.get_dest()),
+            }),
+            num_extensions: self.args.num_extensions + extend_by,
+        }
+    }
+}
+
+/// Progress of the reduced searches of MULTI_CUT
+#[derive(Clone, Copy)]
+struct MultiCut {
+    /// Index of the move being searched
+    tried: usize,
+    cutoffs: u32,
+    /// The lowest eval of the moves that failed high, which the node is at least worth
+    cutoff_eval: i32,
+    path_dependent: bool,
+}
+
+/// The search that a suspended node is waiting on
+#[derive(Clone, Copy)]
+enum Stage {
+    /// The shallow ProbCut search of the node itself, with a null window at `bound`
+    ProbCut {
+        bound: i32,
+    },
+    /// The shallower search of the node itself for a move to search first
+    InternalIterativeDeepening,
+    MultiCut(MultiCut),
+    /// The child of the move at `index`, searched with `window`
+    Move {
+        index: usize,
+        window: (i32, i32),
+        extend_by: u32,
+        verifying_pruning: bool,
+    },
+}
+
+/// What the search does next
+enum Step {
+    /// Search a node, keeping the current one suspended until it returns
+    Search(NodeArgs),
+    /// Return to the suspended node, or out of the search if there is none
+    Return(NodeData),
+}
+
 impl Algorithm {
-    pub(crate) fn new(modules: u32, time_per_move: Duration) -> Self {
+    pub(crate) fn new(modules: u64, time_per_move: Duration) -> Self {
         Self {
             modules,
             time_per_move,
-            board_played_times: HashMap::new(),
-            pawn_hash: HashMap::new(),
+            position_history: RepetitionStack::default(),
+            pawn_hash: PawnHashTable::default(),
             naive_psqt_knight_hash: HashMap::new(),
             naive_psqt_pawn_hash: HashMap::new(),
             naive_psqt_rook_hash: HashMap::new(),
             naive_psqt_bishop_hash: HashMap::new(),
             naive_psqt_queen_hash: HashMap::new(),
             naive_psqt_king_hash: HashMap::new(),
+            transposition_clear_policy: ClearPolicy::default(),
+            transposition_table: TranspositionTable::new(DEFAULT_TRANSPOSITION_TABLE_MB),
+            eval_scale: DEFAULT_EVAL_SCALE,
+            eval_clamp: DEFAULT_EVAL_CLAMP,
+            win_probability_scale: DEFAULT_WIN_PROBABILITY_SCALE,
+            eval_params: EvalParams::default(),
+            psqt_tables: Arc::new(PsqtTables::default()),
+            lazy_eval_margin: None,
+            nnue: None,
+            quiescence_checks: false,
+            quiescence_see_pruning: false,
+            history_table: HistoryTable::default(),
+            probcut_margin: DEFAULT_PROBCUT_MARGIN,
+            probcut_min_depth: DEFAULT_PROBCUT_MIN_DEPTH,
+            skip_fraction: DEFAULT_SKIP_FRACTION,
+            extension_policy: ExtensionPolicy::default(),
+            max_depth: None,
+            fixed_depth: None,
+            collect_stats: true,
+            branching_factor: DEFAULT_BRANCHING_FACTOR,
+            search_threads: DEFAULT_SEARCH_THREADS,
+            multi_pv: 1,
+            root_noise: 0,
+            noise_seed: 0,
+            noise_rng: StdRng::seed_from_u64(0),
+            excluded_root_moves: Vec::new(),
+            principal_variation: PvTable::default(),
+            search_stack: SearchStack::default(),
+            root_move_order: Vec::new(),
+            root_eval_margin: None,
+            last_position: None,
         }
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn node_eval_recursive(
+    /// Like `new`, but with the eval modules of `modules` replaced by those of `profile`, and
+    /// with the weights, PSQTs and network of the profile loaded
+    pub(crate) fn with_eval_profile(
+        modules: u64,
+        time_per_move: Duration,
+        profile: EvalProfile,
+    ) -> std::io::Result<Self> {
+        let mut algorithm = Algorithm::new(
+            (modules & !ALL_EVAL_MODULES) | profile.modules(),
+            time_per_move,
+        );
+        if let Some(path) = profile.eval_params_file() {
+            algorithm.load_eval_params(path)?;
+        }
+        if let Some(path) = profile.psqt_file() {
+            algorithm.load_psqt_tables(path)?;
+        }
+        if let Some(path) = profile.network_file() {
+            algorithm.load_nnue(path)?;
+        }
+        Ok(algorithm)
+    }
+
+    /// Loads a transposition table saved by `save_tt` into a table of the current size and keeps
+    /// it from now on, so that long analysis sessions can pick up where the last one stopped
+    pub(crate) fn load_tt(&mut self, path: &str) -> std::io::Result<()> {
+        self.transposition_table = transposition_table::load_transposition_table(
+            self.modules,
+            self.eval_weights_hash(),
+            path,
+            self.transposition_table.size_mb(),
+        )?;
+        self.transposition_clear_policy = ClearPolicy::Never;
+        Ok(())
+    }
+
+    /// Replaces the transposition table with an empty one of `size_mb` megabytes
+    pub(crate) fn resize_transposition_table(&mut self, size_mb: usize) {
+        self.transposition_table = TranspositionTable::new(size_mb);
+    }
+
+    /// Loads eval weights written by `EvalParams`' Display, missing weights keep their defaults
+    pub(crate) fn load_eval_params(&mut self, path: &str) -> std::io::Result<()> {
+        self.eval_params = EvalParams::load(path)?;
+        self.pawn_hash = PawnHashTable::default();
+        Ok(())
+    }
+
+    /// Loads the tables of NAIVE_PSQT and the tapered PeSTO PSQTs, see `PsqtTables::load`
+    pub(crate) fn load_psqt_tables(&mut self, path: &str) -> std::io::Result<()> {
+        self.psqt_tables = Arc::new(PsqtTables::load(path)?);
+        // The cached NAIVE_PSQT sums were computed with the old tables
+        self.clear_naive_psqt_hashes();
+        Ok(())
+    }
+
+    /// Loads the weights of the network that NNUE evaluates with
+    pub(crate) fn load_nnue(&mut self, path: &str) -> std::io::Result<()> {
+        self.nnue = Some(Arc::new(Network::load(path)?));
+        Ok(())
+    }
+
+    /// The value of the tunable parameter `name`, `None` if there is no such parameter or it is
+    /// turned off
+    pub(crate) fn parameter(&self, name: &str) -> Option<f32> {
+        match name {
+            "lazy_eval_margin" => self.lazy_eval_margin.map(|margin| margin as f32),
+            "probcut_margin" => Some(self.probcut_margin as f32),
+            "probcut_min_depth" => Some(self.probcut_min_depth as f32),
+            "skip_fraction" => Some(self.skip_fraction),
+            "max_extensions_middlegame" => {
+                Some(self.extension_policy.max_extensions_middlegame as f32)
+            }
+            "max_extensions_endgame" => Some(self.extension_policy.max_extensions_endgame as f32),
+            _ => self.eval_params.get(name).map(|weight| weight as f32),
+        }
+    }
+
+    /// Sets the tunable parameter `name`. Integer parameters are rounded to the nearest valid
+    /// value.
+    pub(crate) fn set_parameter(&mut self, name: &str, value: f32) -> Result<(), String> {
+        let whole = value.round().max(0.) as u32;
+        match name {
+            "lazy_eval_margin" => self.lazy_eval_margin = Some(value.round() as i32),
+            "probcut_margin" => self.probcut_margin = value.round() as i32,
+            "probcut_min_depth" => self.probcut_min_depth = whole,
+            "skip_fraction" => self.skip_fraction = value.clamp(0., 1.),
+            "max_extensions_middlegame" => self.extension_policy.max_extensions_middlegame = whole,
+            "max_extensions_endgame" => self.extension_policy.max_extensions_endgame = whole,
+            _ => {
+                self.eval_params
+                    .set(name, value.round() as i32)
+                    .map_err(|_| {
+                        format!(
+                            "Unknown parameter {}, expected an eval weight or one of {}",
+                            name,
+                            TUNABLE_PARAMETERS.join(", ")
+                        )
+                    })?;
+                // Pawn hash entries were computed with the old weights
+                self.pawn_hash = PawnHashTable::default();
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the transposition table in a compact binary format, see `load_tt`
+    pub(crate) fn save_tt(&self, path: &str) -> std::io::Result<()> {
+        transposition_table::save_transposition_table(
+            &self.transposition_table,
+            self.modules,
+            self.eval_weights_hash(),
+            path,
+        )
+    }
+
+    /// Hash of the eval weights, PSQT tables and network, which decide the evals together with
+    /// the modules. Stored evals are stamped with it.
+    pub(crate) fn eval_weights_hash(&self) -> u64 {
+        let mut hasher = StableHasher::default();
+        self.eval_params.hash(&mut hasher);
+        self.psqt_tables.hash(&mut hasher);
+        self.nnue.as_deref().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Searches the node of `args` and every node below it. Nodes waiting on a child search are
+    /// kept on an explicit stack instead of the call stack, so that deep extensions can't
+    /// overflow it. Quiescence search still recurses, as it is at most
+    /// `MAX_QUIESCENCE_DEPTH` deep.
+    fn search_node(
         &mut self,
-        board: &Board,
-        depth: u32,
-        mut alpha: f32,
-        mut beta: f32,
-        original: bool,
+        args: NodeArgs,
         deadline: Option<Instant>,
         stats: &mut Stats,
-        num_extensions: u32,
-        board_played_times_prediction: &mut HashMap<u64, u32>,
-        mut mg_incremental_psqt_eval: f32,
-        mut eg_incremental_psqt_eval: f32,
-        transposition_table: &mut HashMap<u64, TranspositionEntry>,
+        transposition_table: &TranspositionTable,
     ) -> NodeData {
-        if depth == 0 {
-            stats.leaves_visited += 1;
-            let eval = self.eval(
-                board,
-                board_played_times_prediction,
-                mg_incremental_psqt_eval,
-                eg_incremental_psqt_eval,
-            );
+        let mut nodes = Vec::new();
+        let mut step = self.enter_node(args, &mut nodes, deadline, stats, transposition_table);
+        loop {
+            step = match step {
+                Step::Search(child) => {
+                    self.enter_node(child, &mut nodes, deadline, stats, transposition_table)
+                }
+                Step::Return(data) => match nodes.pop() {
+                    Some((node, stage)) => self.resume_node(
+                        node,
+                        stage,
+                        data,
+                        &mut nodes,
+                        deadline,
+                        stats,
+                        transposition_table,
+                    ),
+                    None => return data,
+                },
+            };
+        }
+    }
 
-            let evaluation = Evaluation::new(
-                Some(eval),
-                None,
-                Some(mg_incremental_psqt_eval + eg_incremental_psqt_eval),
+    /// Starts searching a node, up to the first search it has to wait on
+    fn enter_node(
+        &mut self,
+        args: NodeArgs,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        let NodeArgs {
+            board,
+            depth,
+            mut alpha,
+            mut beta,
+            original,
+            node_type,
+            ply,
+            ..
+        } = args;
+        utils::search_assert!(alpha <= beta, "alpha {} > beta {} on entry", alpha, beta);
+        self.search_stack
+            .count(|counters| counters.seldepth = counters.seldepth.max(ply));
+        self.search_stack.enter(ply);
+        // Nothing below this node can be better than being mated, or mating, right here. Skip
+        // the node if a faster mate was already found.
+        if module_enabled(self.modules, ALPHA_BETA) && ply > 0 {
+            let mate_bound = MATE_SCORE - ply as i32;
+            if mate_bound < alpha || -mate_bound > beta {
+                let eval = if mate_bound < alpha {
+                    mate_bound
+                } else {
+                    -mate_bound
+                };
+                let mut evaluation = Evaluation::new(Some(eval), None);
+                evaluation.bound = alpha_beta::bound(eval, alpha, beta);
+                return Step::Return(NodeData::new(evaluation, None));
+            }
+            alpha = alpha.max(-mate_bound);
+            beta = beta.min(mate_bound);
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            self.search_stack
+                .count(|counters| counters.leaves_visited += 1);
+            let eval = if module_enabled(self.modules, QUIESCENCE) {
+                self.quiescence_search(&board, alpha, beta, 0, ply, stats)
+            } else {
+                self.eval(&board, ply, stats, Some((alpha, beta)))
+            };
+            utils::search_assert!(
+                eval::is_mate_score(eval) || eval.abs() <= self.eval_clamp,
+                "eval {} outside of bounds",
+                eval
             );
-            // if module_enabled(self.modules, TRANSPOSITION_TABLE) {
-            //     transposition_table::insert_in_transposition_table(
-            //         transposition_table,
-            //         board,
-            //         depth,
-            //         stats,
-            //         evaluation,
-            //     );
-            // }
+            if !module_enabled(self.modules, QUIESCENCE) {
+                self.search_stack.frame_mut(ply).static_eval = Some(eval);
+            }
 
-            return NodeData::new(evaluation, None);
+            let mut evaluation = Evaluation::new(Some(eval), None);
+            evaluation.path_dependent = self.is_path_draw(&board);
+            // Quiescence fails soft, so its eval may be outside of the window
+            evaluation.bound = alpha_beta::bound(eval, alpha, beta);
+            return Step::Return(NodeData::new(evaluation, None));
         }
 
-        // Whether we should try to maximise the eval
-        let maximise: bool = board.side_to_move() == Color::White;
-        let mut best_evaluation = Evaluation::empty();
+        let mut legal_moves = MoveGen::new_legal(&board);
+        if legal_moves.len() == 0 {
+            let mut evaluation = Evaluation::empty();
+            evaluation.eval = Some(eval::eval_no_legal_moves(&board, ply));
+            return Step::Return(NodeData::new(evaluation, None));
+        }
+        if original {
+            self.exclude_root_moves(&mut legal_moves);
+        }
+        let mut node = Node {
+            args: NodeArgs {
+                alpha,
+                beta,
+                ..args
+            },
+            // Evals outside of the window the node was searched with are only bounds
+            entry_window: (alpha, beta),
+            num_legal_moves: legal_moves.len(),
+            best_evaluation: Evaluation::empty(),
+            board_entry: None,
+            boards: Vec::new(),
+            remaining_moves: Some(legal_moves),
+            next: 0,
+            searching_skipped_tail: false,
+            eval_before_tail: None,
+            verifying_cutoff: None,
+            root_evals: Vec::new(),
+            searched_moves: Vec::new(),
+            debug_data: None,
+        };
 
-        let legal_moves = MoveGen::new_legal(board);
-        let num_legal_moves = legal_moves.len();
-        if num_legal_moves == 0 {
-            best_evaluation.eval = Some(eval::eval_no_legal_moves(board));
-            return NodeData::new(best_evaluation, None);
+        // A shallow search far past the window makes it likely that the full search also
+        // causes a cutoff
+        if module_enabled(self.modules, PROBCUT)
+            && module_enabled(self.modules, ALPHA_BETA)
+            && probcut::applies(node_type, depth, self.probcut_min_depth)
+        {
+            if let Some(bound) = probcut::raised_bound(beta, self.probcut_margin) {
+                let shallow = NodeArgs {
+                    depth: depth - probcut::REDUCTION,
+                    alpha: bound,
+                    beta: bound,
+                    original: false,
+                    ..node.args
+                };
+                nodes.push((node, Stage::ProbCut { bound }));
+                return Step::Search(shallow);
+            }
         }
+        self.probe_node(node, nodes, deadline, stats, transposition_table)
+    }
 
-        let mut boards = Self::create_board_list(
+    /// Continues `node` with the result of the search it was waiting on at `stage`
+    #[allow(clippy::too_many_arguments)]
+    fn resume_node(
+        &mut self,
+        mut node: Node,
+        stage: Stage,
+        data: NodeData,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        match stage {
+            Stage::ProbCut { bound } => {
+                if probcut::proves_cutoff(bound, &data.evaluation) {
+                    stats.probcut_prunes += 1;
+                    if !stats
+                        .probcut_prunes
+                        .is_multiple_of(probcut::VERIFICATION_INTERVAL)
+                    {
+                        let mut evaluation = data.evaluation;
+                        evaluation.bound = Bound::Lower;
+                        return Step::Return(NodeData::new(evaluation, None));
+                    }
+                    node.verifying_cutoff = Some(PROBCUT);
+                }
+                self.probe_node(node, nodes, deadline, stats, transposition_table)
+            }
+            Stage::InternalIterativeDeepening => {
+                internal_iterative_deepening::order_first(
+                    &mut node.boards,
+                    data.evaluation.next_action,
+                );
+                self.start_multi_cut(node, nodes, deadline, stats, transposition_table)
+            }
+            Stage::MultiCut(mut multi_cut) => {
+                let evaluation = data.evaluation.negated();
+                self.position_history.pop();
+                multi_cut.path_dependent |= evaluation.path_dependent;
+                if multi_cut::fails_high(node.args.beta, &evaluation) {
+                    multi_cut.cutoffs += 1;
+                    multi_cut.cutoff_eval = multi_cut.cutoff_eval.min(evaluation.eval.unwrap());
+                }
+                if multi_cut.cutoffs >= multi_cut::CUTOFFS_NEEDED {
+                    stats.multi_cut_prunes += 1;
+                    if stats
+                        .multi_cut_prunes
+                        .is_multiple_of(multi_cut::VERIFICATION_INTERVAL)
+                    {
+                        node.verifying_cutoff = Some(MULTI_CUT);
+                        return self.start_moves(node, nodes, deadline, stats, transposition_table);
+                    }
+                    let chess_move = node.boards[multi_cut.tried].0;
+                    let mut evaluation = Evaluation::new(
+                        Some(multi_cut.cutoff_eval),
+                        Some(Action::MakeMove(chess_move)),
+                    );
+                    evaluation.path_dependent = multi_cut.path_dependent;
+                    evaluation.bound = Bound::Lower;
+                    return Step::Return(NodeData::new(evaluation, None));
+                }
+                multi_cut.tried += 1;
+                self.next_multi_cut_move(
+                    node,
+                    multi_cut,
+                    nodes,
+                    deadline,
+                    stats,
+                    transposition_table,
+                )
+            }
+            Stage::Move {
+                index,
+                window,
+                extend_by,
+                verifying_pruning,
+            } => {
+                let mut evaluation = data.evaluation.negated();
+                let (alpha, beta) = (node.args.alpha, node.args.beta);
+                // Every null window search is checked, so its fail rate is the re-search rate
+                let researching = window != (alpha, beta)
+                    && principal_variation_search::needs_research(alpha, beta, &evaluation);
+                if window != (alpha, beta) {
+                    utils::record_verification(stats, PRINCIPAL_VARIATION_SEARCH, researching);
+                }
+                if researching {
+                    stats.principal_variation_researches += 1;
+                    let child = node.child(
+                        index,
+                        node.args.depth - 1 + extend_by,
+                        (alpha, beta),
+                        node.args.node_type.child(true),
+                        extend_by,
+                    );
+                    let stage = Stage::Move {
+                        index,
+                        window: (alpha, beta),
+                        extend_by,
+                        verifying_pruning,
+                    };
+                    nodes.push((node, stage));
+                    return Step::Search(child);
+                }
+                self.position_history.pop();
+                node.debug_data = data.debug_data;
+                if self.record_move(&mut node, index, &mut evaluation, verifying_pruning, stats) {
+                    return self.finish_node(node, stats, transposition_table);
+                }
+                self.next_move(node, nodes, deadline, stats, transposition_table)
+            }
+        }
+    }
+
+    /// Looks the node up in the transposition table and lists its moves, then runs the internal
+    /// iterative deepening search if there is no best move to start with
+    fn probe_node(
+        &mut self,
+        mut node: Node,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        let NodeArgs {
             board,
-            stats,
-            legal_moves,
-            if module_enabled(self.modules, TRANSPOSITION_TABLE) {
-                Some(transposition_table)
-            } else {
-                None
-            },
+            depth,
+            original,
+            node_type,
+            ply,
+            ..
+        } = node.args;
+        node.board_entry = if module_enabled(self.modules, TRANSPOSITION_TABLE) {
+            transposition_table::get_transposition_entry(transposition_table, stats, &board)
+        } else {
+            None
+        };
+        // The best move stored for this board is searched before the other moves are generated
+        // and sorted, which isn't needed at all if it causes a cutoff
+        let transposition_move = node
+            .board_entry
+            .and_then(|entry| entry.legal_best_move(&board, stats))
+            .filter(|chess_move| !(original && self.excluded_root_moves.contains(chess_move)));
+        if let Some(chess_move) = transposition_move {
+            stats.transposition_ordering_hits += 1;
+            if let Some(legal_moves) = node.remaining_moves.as_mut() {
+                legal_moves.remove_move(chess_move);
+            }
+            node.boards = Self::create_board_list(
+                &board,
+                stats,
+                std::iter::once(chess_move),
+                Some(transposition_table),
+            );
+        }
+        // Multi-cut tries the first few moves of the full list
+        if transposition_move.is_none()
+            || module_enabled(self.modules, MULTI_CUT) && multi_cut::applies(node_type, depth)
+        {
+            let legal_moves = node.remaining_moves.take().unwrap();
+            let boards = self.ordered_board_list(
+                &board,
+                original,
+                ply,
+                stats,
+                legal_moves,
+                transposition_table,
+            );
+            node.boards.extend(boards);
+        }
+
+        // Without a transposition entry there's no best move from earlier searches, so get one
+        // from a shallower search
+        if module_enabled(self.modules, INTERNAL_ITERATIVE_DEEPENING)
+            && internal_iterative_deepening::should_search(depth, node.board_entry.is_some())
+        {
+            let shallow = NodeArgs {
+                depth: depth - internal_iterative_deepening::REDUCTION,
+                original: false,
+                ..node.args
+            };
+            nodes.push((node, Stage::InternalIterativeDeepening));
+            return Step::Search(shallow);
+        }
+        self.start_multi_cut(node, nodes, deadline, stats, transposition_table)
+    }
+
+    fn start_multi_cut(
+        &mut self,
+        node: Node,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        if module_enabled(self.modules, MULTI_CUT)
+            && multi_cut::applies(node.args.node_type, node.args.depth)
+        {
+            let multi_cut = MultiCut {
+                tried: 0,
+                cutoffs: 0,
+                cutoff_eval: i32::MAX,
+                path_dependent: false,
+            };
+            return self.next_multi_cut_move(
+                node,
+                multi_cut,
+                nodes,
+                deadline,
+                stats,
+                transposition_table,
+            );
+        }
+        self.start_moves(node, nodes, deadline, stats, transposition_table)
+    }
+
+    /// Searches the next of the first few moves with a reduced depth, to see if enough of them
+    /// fail high for the node to be cut without a full search
+    fn next_multi_cut_move(
+        &mut self,
+        node: Node,
+        multi_cut: MultiCut,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        let tried = multi_cut.tried;
+        if tried >= multi_cut::MOVES_TRIED.min(node.boards.len()) {
+            return self.start_moves(node, nodes, deadline, stats, transposition_table);
+        }
+        let (chess_move, new_board, _) = node.boards[tried];
+        self.position_history.push(&new_board);
+        self.make_accumulator_move(node.args.ply, &node.args.board, chess_move);
+        let child = node.child(
+            tried,
+            node.args.depth - 1 - multi_cut::REDUCTION,
+            (node.args.alpha, node.args.beta),
+            node.args.node_type.child(false),
+            0,
         );
+        nodes.push((node, Stage::MultiCut(multi_cut)));
+        Step::Search(child)
+    }
 
-        // Sort by eval
-        Self::sort_by_eval(maximise, &mut boards);
+    fn start_moves(
+        &mut self,
+        node: Node,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        self.principal_variation.clear(node.args.ply);
+        self.next_move(node, nodes, deadline, stats, transposition_table)
+    }
 
-        let mut debug_data = None;
+    /// Goes through the moves of the node until one has to be searched, or the node is done
+    fn next_move(
+        &mut self,
+        mut node: Node,
+        nodes: &mut Vec<(Node, Stage)>,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        let NodeArgs {
+            board,
+            depth,
+            original,
+            node_type,
+            ply,
+            last_move,
+            num_extensions,
+            ..
+        } = node.args;
+        loop {
+            if node.next == node.boards.len() {
+                // Only the transposition move has been searched, it didn't cause a cutoff
+                match node.remaining_moves.take() {
+                    Some(legal_moves) => {
+                        let boards = self.ordered_board_list(
+                            &board,
+                            original,
+                            ply,
+                            stats,
+                            legal_moves,
+                            transposition_table,
+                        );
+                        node.boards.extend(boards);
+                        continue;
+                    }
+                    None => return self.finish_node(node, stats, transposition_table),
+                }
+            }
+            let i = node.next;
+            node.next += 1;
+            let (chess_move, new_board, transposition_entry) = node.boards[i];
+            let num_legal_moves = node.num_legal_moves;
 
-        for (i, (chess_move, new_board, transposition_entry)) in boards.into_iter().enumerate() {
             if deadline.is_some_and(utils::passed_deadline) {
                 // The previous value of progress_on_next_layer comes from deeper layers returning.
                 // We want these contributions to be proportional to the contribution from a single
                 // node on our layer
                 stats.progress_on_next_layer *= 1. / num_legal_moves as f32;
                 stats.progress_on_next_layer += i.saturating_sub(1) as f32 / num_legal_moves as f32;
-                return NodeData::new(best_evaluation, None);
+                return Step::Return(NodeData::new(node.best_evaluation, None));
             };
 
-            if depth > stats.max_depth {
-                stats.max_depth = depth;
-            }
+            self.search_stack
+                .count(|counters| counters.max_depth = counters.max_depth.max(depth));
 
+            // Once the tail is found to be needed it is searched in full
             if module_enabled(self.modules, SKIP_BAD_MOVES)
-                && skip_bad_moves::should_skip(num_legal_moves, i)
+                && !node.searching_skipped_tail
+                && skip_bad_moves::should_skip(num_legal_moves, i, self.skip_fraction)
+            {
+                let static_eval = self.node_static_eval(&board, ply, stats, node.board_entry);
+                if !skip_bad_moves::must_search_tail(
+                    node_type,
+                    node.entry_window.0,
+                    node.best_evaluation.eval,
+                    static_eval,
+                ) {
+                    // The skipped moves might have been better
+                    node.best_evaluation.bound = Bound::Lower;
+                    return Step::Return(NodeData::new(node.best_evaluation, None));
+                }
+                stats.skipped_tail_searches += 1;
+                node.searching_skipped_tail = true;
+                node.eval_before_tail = node.best_evaluation.eval;
+            }
+
+            let mut verifying_pruning = false;
+            // Root moves are all searched, as they are all needed for the root evals and the
+            // move order of the next iteration
+            if module_enabled(self.modules, LATE_MOVE_PRUNING)
+                && !original
+                && late_move_pruning::should_prune(&board, chess_move, depth, i)
             {
-                return NodeData::new(best_evaluation, None);
+                stats.late_move_prunes += 1;
+                if !stats
+                    .late_move_prunes
+                    .is_multiple_of(late_move_pruning::VERIFICATION_INTERVAL)
+                {
+                    continue;
+                }
+                verifying_pruning = true;
             }
+            // Stays empty if the move is not searched, unless it is rebuilt from the table
+            self.principal_variation.clear(ply + 1);
+            self.search_stack.frame_mut(ply).current_move = Some(chess_move);
+            self.make_accumulator_move(ply, &board, chess_move);
 
             let extend_by = if module_enabled(self.modules, SEARCH_EXTENSIONS) {
-                search_extensions::calculate(num_extensions, num_legal_moves, new_board)
+                self.extension_policy.calculate(
+                    num_extensions,
+                    num_legal_moves,
+                    &board,
+                    chess_move,
+                    &new_board,
+                    last_move,
+                )
             } else {
                 0
             };
 
-            let evaluation = if transposition_entry.is_some_and(|entry| entry.depth >= depth) {
-                transposition_entry.unwrap().evaluation
-            } else {
-                draw_checker::count_board(board_played_times_prediction, &new_board);
-                let evaluation = self.node_eval_recursive(
-                    &new_board,
-                    depth - 1 + extend_by,
-                    alpha,
-                    beta,
-                    false,
-                    deadline,
-                    stats,
-                    num_extensions + extend_by,
-                    board_played_times_prediction,
-                    mg_incremental_psqt_eval,
-                    eg_incremental_psqt_eval,
-                    transposition_table,
+            let child_depth = depth - 1 + extend_by;
+            let (alpha, beta) = (node.args.alpha, node.args.beta);
+            // Entries from shallower searches than the child's are only used to order its moves,
+            // and bounds are only used when they settle the move for the current window
+            let stored_evaluation = transposition_entry
+                .filter(|entry| entry.depth() >= child_depth)
+                .map(|entry| {
+                    let mut evaluation = entry.evaluation().negated();
+                    evaluation.eval = eval::mate_score_from_node(evaluation.eval, ply + 1);
+                    evaluation
+                })
+                .filter(|evaluation| transposition_table::settles(evaluation, alpha, beta));
+            if let Some(mut evaluation) = stored_evaluation {
+                utils::search_assert!(
+                    transposition_entry.is_some_and(|entry| entry.depth() >= child_depth),
+                    "transposition eval used from a search shallower than depth {}",
+                    child_depth
                 );
-                draw_checker::uncount_board(board_played_times_prediction, &new_board);
-                debug_data = evaluation.debug_data;
-                evaluation.evaluation
-            };
-
-            stats.nodes_visited += 1;
-
-            // Replace best_eval if ours is better
-            if eval::new_eval_is_better(maximise, &best_evaluation, &evaluation) {
-                if original && module_enabled(self.modules, ANALYZE) {
-                    debug_data = Some(analyze::get_debug_data(
-                        self.modules,
-                        maximise,
-                        &best_evaluation,
-                        &chess_move,
-                        &evaluation,
-                    ));
+                utils::search_assert!(
+                    !evaluation.path_dependent,
+                    "path dependent eval in transposition table"
+                );
+                stats.transposition_cutoff_hits += 1;
+                // The child's line is rebuilt from the best moves stored after it
+                self.principal_variation
+                    .set_line(ply + 1, transposition_table.line(&new_board, depth));
+                if self.record_move(&mut node, i, &mut evaluation, verifying_pruning, stats) {
+                    return self.finish_node(node, stats, transposition_table);
                 }
+                continue;
+            }
 
-                best_evaluation.eval = evaluation.eval;
-                best_evaluation.next_action = Some(Action::MakeMove(chess_move));
+            self.position_history.push(&new_board);
+            // Moves after the first one are only searched to prove they are worse
+            let null_window = module_enabled(self.modules, PRINCIPAL_VARIATION_SEARCH)
+                && module_enabled(self.modules, ALPHA_BETA)
+                && node.best_evaluation.eval.is_some();
+            let window = if null_window {
+                principal_variation_search::null_window(alpha, beta)
+            } else {
+                (alpha, beta)
+            };
+            let child_type = node_type.child(node.best_evaluation.eval.is_none());
+            let child = node.child(i, child_depth, window, child_type, extend_by);
+            let stage = Stage::Move {
+                index: i,
+                window,
+                extend_by,
+                verifying_pruning,
+            };
+            nodes.push((node, stage));
+            return Step::Search(child);
+        }
+    }
+
+    /// Takes the evaluation of the move at `index` into the node. Returns whether it caused a
+    /// cutoff, so that the rest of the moves don't need to be searched.
+    fn record_move(
+        &mut self,
+        node: &mut Node,
+        index: usize,
+        evaluation: &mut Evaluation,
+        verifying_pruning: bool,
+        stats: &mut Stats,
+    ) -> bool {
+        let NodeArgs {
+            board,
+            depth,
+            original,
+            ply,
+            ..
+        } = node.args;
+        let chess_move = node.boards[index].0;
+        self.search_stack
+            .count(|counters| counters.nodes_visited += 1);
+        if original {
+            self.add_root_noise(evaluation);
+            if let Some(eval) = evaluation.eval {
+                node.root_evals.push((chess_move, eval));
             }
+        }
+        if verifying_pruning {
+            let failed = eval::new_eval_is_better(&node.best_evaluation, evaluation);
+            utils::record_verification(stats, LATE_MOVE_PRUNING, failed);
+        }
+        if evaluation.path_dependent {
+            node.best_evaluation.path_dependent = true;
+        }
 
-            if module_enabled(self.modules, ALPHA_BETA) {
-                (alpha, beta) = alpha_beta::calc_new(alpha, beta, maximise, evaluation);
-                if alpha > beta {
-                    stats.alpha_beta_breaks += 1;
-                    break;
-                }
+        // Replace best_eval if ours is better
+        if eval::new_eval_is_better(&node.best_evaluation, evaluation) {
+            if original && module_enabled(self.modules, ANALYZE) {
+                node.debug_data = Some(analyze::get_debug_data(
+                    self.modules,
+                    &node.best_evaluation,
+                    &chess_move,
+                    evaluation,
+                ));
             }
 
-            if module_enabled(self.modules, TAPERED_INCREMENTAL_PESTO_PSQT) {
-                fn calc_increment(piece_type: Piece, location: usize, mg_eg: bool) -> f32 {
-                    if mg_eg {
-                        TAPERED_MG_PESTO[piece_type.to_index()][location]
-                    } else {
-                        TAPERED_EG_PESTO[piece_type.to_index()][location]
-                    }
-                }
-                let moved_piece_type = board.piece_on(chess_move.get_source()).unwrap();
+            node.best_evaluation.eval = evaluation.eval;
+            node.best_evaluation.next_action = Some(Action::MakeMove(chess_move));
+            self.principal_variation.update(ply, chess_move);
+        }
 
-                let multiplier = if board.side_to_move() == Color::White {
-                    1
-                } else {
-                    -1
-                };
-                let mut mg_incremental_psqt_eval_change = 0.;
-                let mut eg_incremental_psqt_eval_change = 0.;
-                if mg_incremental_psqt_eval_change == 0. || eg_incremental_psqt_eval_change == 0. {
-                    for i in 0..5 {
-                        mg_incremental_psqt_eval_change +=
-                            Self::calc_tapered_psqt_eval(board, i, true);
-                        mg_incremental_psqt_eval_change +=
-                            Self::calc_tapered_psqt_eval(board, i, false);
-                    }
-                } else {
-                    //Remove the eval from the previous square we stood on.
-                    let source: usize = (56 - chess_move.get_source().to_int()
-                        + 2 * (chess_move.get_source().to_int() % 8))
-                        as usize;
-                    mg_incremental_psqt_eval_change +=
-                        calc_increment(moved_piece_type, source, true);
-                    eg_incremental_psqt_eval_change +=
-                        calc_increment(moved_piece_type, source, false);
-
-                    //Increase the eval at the destination
-                    let dest: usize = (56 - chess_move.get_dest().to_int()
-                        + 2 * (chess_move.get_dest().to_int() % 8))
-                        as usize;
-                    mg_incremental_psqt_eval_change += calc_increment(moved_piece_type, dest, true);
-                    eg_incremental_psqt_eval_change +=
-                        calc_increment(moved_piece_type, dest, false);
-
-                    // Decrement enemy eval from potential capture
-                    if let Some(attacked_piece_type) = board.piece_on(chess_move.get_dest()) {
-                        mg_incremental_psqt_eval_change +=
-                            calc_increment(attacked_piece_type, dest, true);
-                        eg_incremental_psqt_eval_change +=
-                            calc_increment(attacked_piece_type, dest, false);
-                    }
+        if module_enabled(self.modules, ALPHA_BETA) {
+            node.args.alpha = alpha_beta::calc_new(node.args.alpha, *evaluation);
+            if node.args.alpha >= node.args.beta {
+                self.search_stack
+                    .count(|counters| counters.alpha_beta_breaks += 1);
+                if node.remaining_moves.is_some() {
+                    stats.transposition_move_cutoffs += 1;
                 }
-                mg_incremental_psqt_eval += mg_incremental_psqt_eval_change * multiplier as f32;
-                eg_incremental_psqt_eval += eg_incremental_psqt_eval_change * multiplier as f32;
+                if module_enabled(self.modules, KILLER_MOVES) {
+                    self.search_stack.record_killer(ply, &board, chess_move);
+                }
+                if module_enabled(self.modules, HISTORY_HEURISTIC) {
+                    self.history_table.record_cutoff(
+                        &board,
+                        chess_move,
+                        &node.searched_moves,
+                        depth,
+                    );
+                }
+                return true;
             }
-            best_evaluation.incremental_psqt_eval =
-                Some(mg_incremental_psqt_eval + eg_incremental_psqt_eval);
         }
+        if module_enabled(self.modules, HISTORY_HEURISTIC) {
+            node.searched_moves.push(chess_move);
+        }
+        false
+    }
 
-        if module_enabled(self.modules, TRANSPOSITION_TABLE) {
+    /// Stores the result of a node whose moves have all been searched, or that had a cutoff
+    fn finish_node(
+        &mut self,
+        node: Node,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> Step {
+        let Node {
+            args,
+            entry_window,
+            mut best_evaluation,
+            board_entry,
+            root_evals,
+            mut debug_data,
+            searching_skipped_tail,
+            eval_before_tail,
+            verifying_cutoff,
+            ..
+        } = node;
+        let NodeArgs {
+            board,
+            depth,
+            original,
+            ply,
+            ..
+        } = args;
+        if let Some(eval) = best_evaluation.eval {
+            best_evaluation.bound = alpha_beta::bound(eval, entry_window.0, entry_window.1);
+        }
+        // The pruned node had to be worth at least beta
+        if let Some(module) = verifying_cutoff {
+            let failed = best_evaluation
+                .eval
+                .is_none_or(|eval| eval < entry_window.1);
+            utils::record_verification(stats, module, failed);
+        }
+        // The tail was needed if one of its moves became the best move
+        if searching_skipped_tail {
+            let failed = best_evaluation.eval != eval_before_tail;
+            utils::record_verification(stats, SKIP_BAD_MOVES, failed);
+        }
+        if original {
+            self.remember_root_order(root_evals, best_evaluation.next_action);
+        }
+
+        // Lines that leave out the best root moves don't give the eval of the root
+        if module_enabled(self.modules, TRANSPOSITION_TABLE)
+            && !best_evaluation.path_dependent
+            && (!original || self.excluded_root_moves.is_empty())
+        {
+            utils::search_assert!(
+                best_evaluation.eval.is_some(),
+                "transposition entry without eval at depth {}",
+                depth
+            );
+            // Mates are stored counted from this node, so that they are right wherever the
+            // position is reached again
+            utils::search_assert!(
+                best_evaluation
+                    .eval
+                    .and_then(eval::mate_plies)
+                    .is_none_or(|plies| plies >= ply),
+                "mate {:?} closer to the root than the node at ply {}",
+                best_evaluation.eval,
+                ply
+            );
+            let mut entry_evaluation = best_evaluation;
+            entry_evaluation.eval = eval::mate_score_to_node(best_evaluation.eval, ply);
+            // Kept from the entry that was probed if the board wasn't evaluated this time
+            let static_eval = self
+                .search_stack
+                .frame(ply)
+                .static_eval
+                .or(board_entry.and_then(|entry| entry.static_eval()));
             transposition_table::insert_in_transposition_table(
                 transposition_table,
-                board,
+                &board,
                 depth,
                 stats,
-                best_evaluation,
+                entry_evaluation,
+                static_eval,
+            );
+        }
+
+        if let Some(debug_data) = debug_data.as_mut() {
+            if let Some(Action::MakeMove(next_move)) = best_evaluation.next_action {
+                utils::vector_push_debug!(debug_data, best_evaluation.eval, next_move.to_string());
+            }
+        }
+        Step::Return(NodeData::new(best_evaluation, debug_data))
+    }
+
+    /// Static eval of the node at `ply`, taken from its transposition entry if one was stored
+    /// with it instead of evaluating the board again
+    fn node_static_eval(
+        &mut self,
+        board: &Board,
+        ply: u32,
+        stats: &mut Stats,
+        board_entry: Option<TranspositionEntry>,
+    ) -> i32 {
+        let static_eval = match board_entry.and_then(|entry| entry.static_eval()) {
+            Some(static_eval) => {
+                stats.stored_static_eval_reuses += 1;
+                static_eval
+            }
+            None => self.eval(board, ply, stats, None),
+        };
+        self.search_stack.frame_mut(ply).static_eval = Some(static_eval);
+        static_eval
+    }
+
+    /// Keeps searching captures past the nominal depth so that the eval isn't taken in the middle
+    /// of an exchange. The side to move may stand pat, i.e. stop capturing and take the eval,
+    /// which also gives the cutoffs.
+    #[allow(clippy::too_many_arguments)]
+    fn quiescence_search(
+        &mut self,
+        board: &Board,
+        mut alpha: i32,
+        beta: i32,
+        quiescence_depth: u32,
+        ply: u32,
+        stats: &mut Stats,
+    ) -> i32 {
+        self.search_stack.count(|counters| {
+            counters.quiescence_nodes += 1;
+            counters.seldepth = counters.seldepth.max(ply);
+        });
+        let stand_pat = self.eval(board, ply, stats, Some((alpha, beta)));
+        self.search_stack.frame_mut(ply).static_eval = Some(stand_pat);
+        let in_check = board.checkers().popcnt() > 0;
+        if board.status() != BoardStatus::Ongoing
+            || !in_check && quiescence::stand_pat(stand_pat, &mut alpha, beta)
+            || quiescence_depth >= MAX_QUIESCENCE_DEPTH
+        {
+            return stand_pat;
+        }
+
+        // In check every evasion is searched, and the side to move is mated unless one holds
+        let (moves, mut best_eval) = if in_check {
+            (quiescence::evasions(board), eval::mated_score(ply))
+        } else {
+            let mut moves = quiescence::ordered_captures(board);
+            if self.quiescence_checks && quiescence_depth == 0 {
+                moves.extend(quiescence::quiet_checks(board));
+            }
+            (moves, stand_pat)
+        };
+
+        for chess_move in moves {
+            if self.quiescence_see_pruning
+                && !in_check
+                && board.piece_on(chess_move.get_dest()).is_some()
+                && quiescence::static_exchange_evaluation(board, chess_move) < 0
+            {
+                stats.quiescence_captures_pruned += 1;
+                continue;
+            }
+            self.make_accumulator_move(ply, board, chess_move);
+            let eval = -self.quiescence_search(
+                &board.make_move_new(chess_move),
+                -beta,
+                -alpha,
+                quiescence_depth + 1,
+                ply + 1,
+                stats,
             );
+            best_eval = best_eval.max(eval);
+            alpha = alpha.max(eval);
+            if alpha >= beta {
+                break;
+            }
         }
+        best_eval
+    }
+
+    /// All of `legal_moves` in the order they should be searched
+    #[allow(clippy::too_many_arguments)]
+    fn ordered_board_list(
+        &self,
+        board: &Board,
+        original: bool,
+        ply: u32,
+        stats: &mut Stats,
+        legal_moves: MoveGen,
+        transposition_table: &TranspositionTable,
+    ) -> Vec<(ChessMove, Board, Option<TranspositionEntry>)> {
+        let mut boards = Self::create_board_list(
+            board,
+            stats,
+            legal_moves,
+            if module_enabled(self.modules, TRANSPOSITION_TABLE) {
+                Some(transposition_table)
+            } else {
+                None
+            },
+        );
 
-        if debug_data.is_some() {
-            let mut debug_data_unwrapped = debug_data.unwrap();
-            if let Some(Action::MakeMove(next_move)) = best_evaluation.next_action {
-                utils::vector_push_debug!(
-                    debug_data_unwrapped,
-                    best_evaluation.eval,
-                    next_move.to_string(),
-                );
-            }
-            debug_data = Some(debug_data_unwrapped);
+        // Quiet moves by history first, so that they stay in that order among the moves
+        // without transposition entries after the stable sort by eval
+        if module_enabled(self.modules, HISTORY_HEURISTIC) {
+            boards.sort_by_key(|(chess_move, _, _)| {
+                Reverse(
+                    self.history_table
+                        .score(board, *chess_move)
+                        .unwrap_or(i32::MAX),
+                )
+            });
         }
-        NodeData::new(best_evaluation, debug_data)
+        // Killers before the other moves without transposition entries
+        if module_enabled(self.modules, KILLER_MOVES) {
+            let killers = self.search_stack.frame(ply).killers;
+            boards.sort_by_key(|(chess_move, _, _)| !killers.contains(&Some(*chess_move)));
+        }
+        // Sort by eval
+        Self::sort_by_eval(&mut boards);
+        // The previous iteration already searched every root move, which beats any estimate
+        if original {
+            boards.sort_by_key(|(chess_move, _, _)| {
+                self.root_move_order
+                    .iter()
+                    .position(|root_move| root_move == chess_move)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        boards
     }
 
     fn create_board_list(
         board: &Board,
         stats: &mut Stats,
-        legal_moves: MoveGen,
-        transposition_table: Option<&HashMap<u64, TranspositionEntry>>,
+        legal_moves: impl Iterator<Item = ChessMove>,
+        transposition_table: Option<&TranspositionTable>,
     ) -> Vec<(ChessMove, Board, Option<TranspositionEntry>)> {
         legal_moves
             .map(|chess_move| {
@@ -306,84 +1369,541 @@ impl Algorithm {
             .collect::<Vec<(ChessMove, Board, Option<TranspositionEntry>)>>()
     }
 
-    fn sort_by_eval(maximise: bool, boards: &mut [(ChessMove, Board, Option<TranspositionEntry>)]) {
-        boards.sort_by(|board1, board2| {
-            let eval1 = if let Some(entry) = board1.2 {
-                entry.evaluation.eval.unwrap_or_default()
-            } else {
-                0.
-            };
-            let eval2 = if let Some(entry) = board2.2 {
-                entry.evaluation.eval.unwrap_or_default()
-            } else {
-                0.
-            };
-            let ordering = eval1.partial_cmp(&eval2).expect("Eval is a valid value");
+    /// The entries are from the point of view of the opponent, so the lowest evals go first
+    fn sort_by_eval(boards: &mut [(ChessMove, Board, Option<TranspositionEntry>)]) {
+        boards.sort_by_key(|(_, _, entry)| {
+            entry
+                .and_then(|entry| entry.evaluation().eval)
+                .unwrap_or_default()
+        });
+    }
+
+    /// Adds the seeded noise of `root_noise` to the eval of a root move. Mates are left exact.
+    fn add_root_noise(&mut self, evaluation: &mut Evaluation) {
+        if self.root_noise <= 0 {
+            return;
+        }
+        if let Some(eval) = evaluation
+            .eval
+            .as_mut()
+            .filter(|eval| !eval::is_mate_score(**eval))
+        {
+            *eval += self.noise_rng.gen_range(-self.root_noise..=self.root_noise);
+        }
+    }
+
+    /// Leaves out the root moves of the lines that MultiPV has already found
+    fn exclude_root_moves(&self, legal_moves: &mut MoveGen) {
+        for chess_move in &self.excluded_root_moves {
+            legal_moves.remove_move(*chess_move);
+        }
+    }
 
-            if maximise {
-                return ordering.reverse();
+    /// Keeps the order of the root moves for the next iteration, by `root_evals` but with the
+    /// best move first
+    fn remember_root_order(
+        &mut self,
+        mut root_evals: Vec<(ChessMove, i32)>,
+        best_action: Option<Action>,
+    ) {
+        root_evals.sort_by_key(|&(_, eval)| Reverse(eval));
+        // Evals after the best move are only bounds, which may tie with it, so put it first
+        if let Some(Action::MakeMove(best_move)) = best_action {
+            if let Some(i) = root_evals
+                .iter()
+                .position(|&(chess_move, _)| chess_move == best_move)
+            {
+                root_evals[..=i].rotate_right(1);
             }
-            ordering
-        });
+        }
+        self.root_eval_margin = match root_evals[..] {
+            [(_, best), (_, second), ..] => Some(best.saturating_sub(second)),
+            _ => None,
+        };
+        self.root_move_order = root_evals
+            .into_iter()
+            .map(|(chess_move, _)| chess_move)
+            .collect();
     }
 
-    fn next_action(
+    /// Searches the root moves split between `search_threads` threads. The first move is
+    /// searched alone so that the others have an eval to beat, then the threads take the rest
+    /// one at a time, narrowing the window by the evals of the moves that are done. Each thread
+    /// works on its own copy of the algorithm, while they all share the transposition table.
+    fn root_split_search(
         &mut self,
         board: &Board,
         depth: u32,
+        window: (i32, i32),
         deadline: Option<Instant>,
-        transposition_table: &mut HashMap<u64, TranspositionEntry>,
-    ) -> (Option<Action>, Vec<String>, Stats) {
-        let mut stats = Stats::default();
-        let out = self.node_eval_recursive(
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> NodeData {
+        let mut best_evaluation = Evaluation::empty();
+        let mut legal_moves = MoveGen::new_legal(board);
+        let num_legal_moves = legal_moves.len();
+        if num_legal_moves == 0 {
+            best_evaluation.eval = Some(eval::eval_no_legal_moves(board, 0));
+            return NodeData::new(best_evaluation, None);
+        }
+        self.exclude_root_moves(&mut legal_moves);
+        let num_legal_moves = legal_moves.len();
+        let boards =
+            self.ordered_board_list(board, true, 0, stats, legal_moves, transposition_table);
+        let alpha_beta = module_enabled(self.modules, ALPHA_BETA);
+
+        let first = self.search_root_move(
             board,
+            boards[0],
+            window,
             depth,
-            f32::MIN,
-            f32::MAX,
-            true,
+            num_legal_moves,
             deadline,
-            &mut stats,
-            0,
-            &mut HashMap::new(),
-            0.,
-            0.,
+            stats,
             transposition_table,
         );
+        let best_eval = AtomicI32::new(match first.0.eval {
+            Some(eval) if alpha_beta => eval,
+            _ => window.0,
+        });
+        let next_move = AtomicUsize::new(1);
+        let worker_outputs: Vec<_> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.search_threads.max(1))
+                .map(|_| {
+                    let mut worker = self.clone();
+                    // Each thread counts its own nodes into its stats
+                    worker.search_stack.take_counters();
+                    let (boards, best_eval, next_move) = (&boards, &best_eval, &next_move);
+                    scope.spawn(move || {
+                        let mut worker_stats = Stats::default();
+                        let mut results = Vec::new();
+                        loop {
+                            let i = next_move.fetch_add(1, Ordering::Relaxed);
+                            let window = if alpha_beta {
+                                root_splitting::window(window, best_eval)
+                            } else {
+                                window
+                            };
+                            if i >= boards.len() || window.0 >= window.1 {
+                                break;
+                            }
+                            let (evaluation, line) = worker.search_root_move(
+                                board,
+                                boards[i],
+                                window,
+                                depth,
+                                num_legal_moves,
+                                deadline,
+                                &mut worker_stats,
+                                transposition_table,
+                            );
+                            if let Some(eval) = evaluation.eval.filter(|_| alpha_beta) {
+                                root_splitting::record_eval(best_eval, eval);
+                            }
+                            results.push((i, evaluation, line));
+                        }
+                        worker
+                            .search_stack
+                            .take_counters()
+                            .fold_into(&mut worker_stats);
+                        (results, worker_stats)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("Root splitting thread panicked"))
+                .collect()
+        });
+
+        let mut results = vec![(0, first.0, first.1)];
+        for (worker_results, worker_stats) in worker_outputs {
+            results.extend(worker_results);
+            let max_depth = stats.max_depth.max(worker_stats.max_depth);
+            let seldepth = stats.seldepth.max(worker_stats.seldepth);
+            *stats += worker_stats;
+            stats.max_depth = max_depth;
+            stats.seldepth = seldepth;
+        }
+        // Pick the best move the same way regardless of which thread finished first
+        results.sort_by_key(|&(i, _, _)| i);
+        if deadline.is_some_and(utils::passed_deadline) {
+            stats.progress_on_next_layer = results.len() as f32 / num_legal_moves as f32;
+        }
+
+        let mut root_evals = Vec::new();
+        let mut best_line = Vec::new();
+        for (i, mut evaluation, line) in results {
+            self.add_root_noise(&mut evaluation);
+            let chess_move = boards[i].0;
+            if let Some(eval) = evaluation.eval {
+                root_evals.push((chess_move, eval));
+            }
+            best_evaluation.path_dependent |= evaluation.path_dependent;
+            if eval::new_eval_is_better(&best_evaluation, &evaluation) {
+                best_evaluation.eval = evaluation.eval;
+                best_evaluation.next_action = Some(Action::MakeMove(chess_move));
+                best_line = [chess_move].into_iter().chain(line).collect();
+            }
+        }
+        if let Some(eval) = best_evaluation.eval {
+            best_evaluation.bound = alpha_beta::bound(eval, window.0, window.1);
+        }
+        self.remember_root_order(root_evals, best_evaluation.next_action);
+        self.principal_variation.set_root_line(best_line);
+
+        if module_enabled(self.modules, TRANSPOSITION_TABLE)
+            && !best_evaluation.path_dependent
+            && self.excluded_root_moves.is_empty()
+        {
+            transposition_table::insert_in_transposition_table(
+                transposition_table,
+                board,
+                depth,
+                stats,
+                best_evaluation,
+                None,
+            );
+        }
+        let debug_data = module_enabled(self.modules, ANALYZE).then(|| {
+            let mut debug_data = Vec::new();
+            if let Some(Action::MakeMove(next_move)) = best_evaluation.next_action {
+                utils::vector_push_debug!(debug_data, best_evaluation.eval, next_move.to_string());
+            }
+            debug_data
+        });
+        NodeData::new(best_evaluation, debug_data)
+    }
+
+    /// Keeps the PSQT sums of TAPERED_INCREMENTAL_PESTO_PSQT and the hidden layer of NNUE up
+    /// to date in the child that `chess_move` leads to
+    fn make_accumulator_move(&mut self, ply: u32, board: &Board, chess_move: ChessMove) {
+        if module_enabled(self.modules, TAPERED_INCREMENTAL_PESTO_PSQT) {
+            self.search_stack
+                .make_psqt_move(ply, board, chess_move, &self.psqt_tables);
+        }
+        if let Some(network) = self
+            .nnue
+            .as_ref()
+            .filter(|_| module_enabled(self.modules, NNUE))
+        {
+            self.search_stack
+                .make_nnue_move(ply, board, chess_move, network);
+        }
+    }
+
+    /// Searches a single root move for `root_split_search`. Returns its evaluation and the line
+    /// that follows it.
+    #[allow(clippy::too_many_arguments)]
+    fn search_root_move(
+        &mut self,
+        board: &Board,
+        (chess_move, new_board, transposition_entry): (
+            ChessMove,
+            Board,
+            Option<TranspositionEntry>,
+        ),
+        window: (i32, i32),
+        depth: u32,
+        num_legal_moves: usize,
+        deadline: Option<Instant>,
+        stats: &mut Stats,
+        transposition_table: &TranspositionTable,
+    ) -> (Evaluation, Vec<ChessMove>) {
+        self.search_stack
+            .count(|counters| counters.nodes_visited += 1);
+        let extend_by = if module_enabled(self.modules, SEARCH_EXTENSIONS) {
+            self.extension_policy
+                .calculate(0, num_legal_moves, board, chess_move, &new_board, None)
+        } else {
+            0
+        };
+        let child_depth = depth - 1 + extend_by;
+        if let Some(entry) = transposition_entry.filter(|entry| entry.depth() >= child_depth) {
+            let mut evaluation = entry.evaluation().negated();
+            evaluation.eval = eval::mate_score_from_node(evaluation.eval, 1);
+            if transposition_table::settles(&evaluation, window.0, window.1) {
+                stats.transposition_cutoff_hits += 1;
+                return (evaluation, transposition_table.line(&new_board, depth));
+            }
+        }
+        self.position_history.push(&new_board);
+        self.principal_variation.clear(1);
+        self.make_accumulator_move(0, board, chess_move);
+        let child = NodeArgs {
+            board: new_board,
+            depth: child_depth,
+            alpha: -window.1,
+            beta: -window.0,
+            original: false,
+            node_type: NodeType::Pv.child(true),
+            ply: 1,
+            last_move: Some(LastMove {
+                chess_move,
+                captured: board.piece_on(chess_move.get_dest()),
+            }),
+            num_extensions: extend_by,
+        };
+        let evaluation = self
+            .search_node(child, deadline, stats, transposition_table)
+            .evaluation
+            .negated();
+        self.position_history.pop();
+        (evaluation, self.principal_variation.line(1))
+    }
+
+    fn next_action(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        window: (i32, i32),
+        deadline: Option<Instant>,
+        transposition_table: &TranspositionTable,
+    ) -> (
+        Option<Action>,
+        Vec<String>,
+        Stats,
+        Option<i32>,
+        Vec<ChessMove>,
+        Bound,
+    ) {
+        let mut stats = Stats::default();
+        let game_length = self.position_history.len();
+        let out =
+            if module_enabled(self.modules, ROOT_SPLITTING) && depth >= root_splitting::MIN_DEPTH {
+                self.root_split_search(
+                    board,
+                    depth,
+                    window,
+                    deadline,
+                    &mut stats,
+                    transposition_table,
+                )
+            } else {
+                let root = NodeArgs {
+                    board: *board,
+                    depth,
+                    alpha: window.0,
+                    beta: window.1,
+                    original: true,
+                    node_type: NodeType::Pv,
+                    ply: 0,
+                    last_move: None,
+                    num_extensions: 0,
+                };
+                self.search_node(root, deadline, &mut stats, transposition_table)
+            };
+        utils::search_assert!(
+            self.position_history.len() == game_length,
+            "boards pushed in the search were not all popped"
+        );
+        self.search_stack.take_counters().fold_into(&mut stats);
         let analyzer_data = out.debug_data.unwrap_or_default();
-        (out.evaluation.next_action, analyzer_data, stats)
+        (
+            out.evaluation.next_action,
+            analyzer_data,
+            stats,
+            out.evaluation.eval,
+            self.principal_variation.root_line(),
+            out.evaluation.bound,
+        )
     }
 
     pub(crate) fn next_action_iterative_deepening(
         &mut self,
         board: &Board,
         deadline: Instant,
-    ) -> (Action, Vec<String>, Stats) {
-        self.board_played_times.insert(
-            *board,
-            *self.board_played_times.get(board).unwrap_or(&0) + 1,
-        );
+    ) -> (Action, Vec<String>, Stats, Vec<ScoredLine>) {
+        self.position_history.push(board);
 
-        let mut transposition_table = HashMap::new();
+        if module_enabled(self.modules, HISTORY_HEURISTIC) {
+            self.history_table.decay();
+        }
+        self.root_move_order.clear();
+        self.reset_search_stack(board);
+        // Taken out of the algorithm so that the search can borrow both
+        let mut transposition_table =
+            mem::replace(&mut self.transposition_table, TranspositionTable::new(0));
+        if self.transposition_clear_policy == ClearPolicy::EveryMove {
+            transposition_table.clear();
+        }
+        transposition_table.new_search();
         // Guarantee that at least the first layer gets done.
         const START_DEPTH: u32 = 1;
+        let iteration_start = Instant::now();
         let mut deepest_complete_output =
-            self.next_action(board, START_DEPTH, None, &mut transposition_table);
+            self.next_action(board, START_DEPTH, FULL_WINDOW, None, &transposition_table);
         let mut deepest_complete_depth = START_DEPTH;
+        let mut previous_iteration_time = Instant::now() - iteration_start;
+        let predict = module_enabled(self.modules, ITERATION_PREDICTION);
+        // An obvious move doesn't need all of the time, which is left to later moves
+        let easy = module_enabled(self.modules, EASY_MOVE)
+            && match deepest_complete_output.0 {
+                Some(Action::MakeMove(best_move)) => easy_move::is_easy(
+                    self.last_position.as_ref(),
+                    board,
+                    best_move,
+                    self.root_eval_margin,
+                ),
+                _ => false,
+            };
+        let deadline = if easy {
+            easy_move::deadline(iteration_start, deadline)
+        } else {
+            deadline
+        };
+        // A fixed depth search never looks at the clock
+        let deadline = self.fixed_depth.is_none().then_some(deadline);
+        let passed_deadline = || deadline.is_some_and(utils::passed_deadline);
 
-        for depth in (deepest_complete_depth + 1)..=10 {
-            let latest_output =
-                self.next_action(board, depth, Some(deadline), &mut transposition_table);
-            if utils::passed_deadline(deadline) {
+        // The search doesn't go further than MAX_PLY from the root anyway
+        let max_depth = self.fixed_depth.or(self.max_depth).unwrap_or(MAX_PLY);
+        let mut iterations = vec![(deepest_complete_output.0, deepest_complete_output.3)];
+        let mut stopped_before_max_depth = false;
+        for depth in (deepest_complete_depth + 1)..=max_depth {
+            // An iteration cancelled by the deadline is wasted, leave the time to later moves
+            if predict
+                && deadline.is_some_and(|deadline| {
+                    !iteration_prediction::can_complete(
+                        previous_iteration_time,
+                        self.branching_factor,
+                        deadline.saturating_duration_since(Instant::now()),
+                    )
+                })
+            {
+                deepest_complete_output.2.skipped_iterations = 1;
+                stopped_before_max_depth = true;
+                break;
+            }
+            let iteration_start = Instant::now();
+            let aspiration = module_enabled(self.modules, ASPIRATION_WINDOWS)
+                && module_enabled(self.modules, ALPHA_BETA);
+            let mut window = if aspiration {
+                aspiration_windows::initial(deepest_complete_output.3)
+            } else {
+                FULL_WINDOW
+            };
+            let (mut fail_high_researches, mut fail_low_researches) = (0, 0);
+            // The last finished search of this depth that failed high
+            let mut fail_high_output = None;
+            let mut latest_output = loop {
+                let output = self.next_action(board, depth, window, deadline, &transposition_table);
+                match aspiration_windows::widen(window, output.5) {
+                    Some(wider) if aspiration && !passed_deadline() => {
+                        if output.5 == Bound::Lower {
+                            fail_high_researches += 1;
+                            fail_high_output = Some(output);
+                        } else {
+                            fail_low_researches += 1;
+                        }
+                        window = wider;
+                    }
+                    _ => break output,
+                }
+            };
+            latest_output.2.root_fail_high_researches = fail_high_researches;
+            latest_output.2.root_fail_low_researches = fail_low_researches;
+            let iteration_time = Instant::now() - iteration_start;
+            if passed_deadline() {
+                // The move that failed high is proven better than the previous depth's eval,
+                // even though the search with the wider window didn't finish to tell how much
+                if let Some(fail_high_output) = fail_high_output {
+                    deepest_complete_output.0 = fail_high_output.0;
+                    deepest_complete_output.3 = fail_high_output.3;
+                    deepest_complete_output.4 = fail_high_output.4;
+                    deepest_complete_output.2.root_fail_high_researches = fail_high_researches;
+                }
+                if predict {
+                    self.branching_factor = iteration_prediction::update_branching_factor(
+                        self.branching_factor,
+                        previous_iteration_time,
+                        iteration_time,
+                        latest_output.2.progress_on_next_layer,
+                    );
+                }
                 // The cancelled layer is the one with this data
                 deepest_complete_output.2.progress_on_next_layer =
                     latest_output.2.progress_on_next_layer;
+                stopped_before_max_depth = true;
+                break;
+            }
+            if predict {
+                self.branching_factor = iteration_prediction::update_branching_factor(
+                    self.branching_factor,
+                    previous_iteration_time,
+                    iteration_time,
+                    1.,
+                );
+            }
+            previous_iteration_time = iteration_time;
+            iterations.push((latest_output.0, latest_output.3));
+            deepest_complete_output = latest_output;
+            deepest_complete_depth = depth;
+            // Leave the rest of the time to later moves
+            if module_enabled(self.modules, EARLY_TERMINATION)
+                && self.fixed_depth.is_none()
+                && early_termination::is_stable(&iterations)
+            {
+                deepest_complete_output.2.early_terminations = 1;
+                stopped_before_max_depth = true;
                 break;
-            } else {
-                deepest_complete_output = latest_output;
-                deepest_complete_depth = depth;
             }
         }
         deepest_complete_output.2.depth = deepest_complete_depth;
+        deepest_complete_output.2.easy_moves = easy as u32;
+        if !stopped_before_max_depth {
+            deepest_complete_output.2.max_depth_cutoffs = 1;
+        }
+        // Reported from white's point of view, like the evals of the games
+        let side_to_move = board.side_to_move();
+        let mut lines = vec![ScoredLine {
+            eval: deepest_complete_output
+                .3
+                .map(|eval| eval::from_perspective(side_to_move, eval)),
+            line: deepest_complete_output.4,
+        }];
+        let num_legal_moves = MoveGen::new_legal(board).len();
+        while lines.len() < self.multi_pv.min(num_legal_moves) {
+            let Some(&first_move) = lines.last().unwrap().line.first() else {
+                break;
+            };
+            self.excluded_root_moves.push(first_move);
+            let output = self.next_action(
+                board,
+                deepest_complete_depth,
+                FULL_WINDOW,
+                None,
+                &transposition_table,
+            );
+            lines.push(ScoredLine {
+                eval: output
+                    .3
+                    .map(|eval| eval::from_perspective(side_to_move, eval)),
+                line: output.4,
+            });
+        }
+        self.excluded_root_moves.clear();
+
+        if module_enabled(self.modules, TRANSPOSITION_TABLE) {
+            deepest_complete_output.2.transposition_table_occupancy =
+                transposition_table.occupancy();
+            deepest_complete_output.2.hashfull = transposition_table.hashfull();
+        }
+        self.transposition_table = transposition_table;
+
+        if module_enabled(self.modules, ANALYZE) {
+            let eval_breakdown = self.eval_breakdown(board, 0);
+            deepest_complete_output
+                .1
+                .push(format!("eval_breakdown = {}", eval_breakdown));
+            // Of white, like the evals of the lines
+            if let Some(eval) = lines[0].eval {
+                deepest_complete_output.1.push(format!(
+                    "win_probability = {:.3}",
+                    self.win_probability(eval)
+                ));
+            }
+        }
 
         let mut action = match deepest_complete_output.0 {
             Some(action) => action,
@@ -400,86 +1920,185 @@ impl Algorithm {
 
         if let Action::MakeMove(chess_move) = action {
             let new_board = board.make_move_new(chess_move);
-            let old_value = *self.board_played_times.get(&new_board).unwrap_or(&0);
-            if old_value >= 3 {
+            if self.position_history.occurrences(&new_board) >= 3 {
                 // We should declare draw by three-fold repetition. This is not checked
                 // unless we do this.
                 action = Action::DeclareDraw;
             }
-            self.board_played_times.insert(new_board, old_value + 1);
+            self.position_history.push(&new_board);
+            self.last_position = Some(new_board);
         }
 
-        (action, deepest_complete_output.1, deepest_complete_output.2)
+        (
+            action,
+            deepest_complete_output.1,
+            deepest_complete_output.2,
+            lines,
+        )
+    }
+
+    /// Whether the board is drawn by the way it was reached. Either it is the third time the
+    /// board is played, counting both the game and the moves predicted in the search, or the
+    /// fifty-move rule applies.
+    fn is_path_draw(&self, board: &Board) -> bool {
+        self.position_history.occurrences(board) >= 2
+            || self.position_history.fifty_move_draw(board)
     }
 
+    /// Static eval of `board` from the point of view of the side to move. With a `window`, the
+    /// eval may be cut short if it is far outside of it, see `lazy_eval_margin`.
     pub(crate) fn eval(
         &mut self,
         board: &Board,
-        board_played_times_prediction: &HashMap<u64, u32>,
-        mg_incremental_psqt_eval: f32,
-        eg_incremental_psqt_eval: f32,
-    ) -> f32 {
+        ply: u32,
+        stats: &mut Stats,
+        window: Option<(i32, i32)>,
+    ) -> i32 {
+        self.eval_terms(board, ply, stats, window, None)
+    }
+
+    /// Starts a new search stack with the accumulators of its root set up for `board`
+    fn reset_search_stack(&mut self, board: &Board) {
+        self.search_stack = SearchStack::default();
+        self.search_stack.counting = self.collect_stats;
+        if module_enabled(self.modules, TAPERED_INCREMENTAL_PESTO_PSQT) {
+            self.search_stack.frame_mut(0).psqt =
+                PsqtAccumulator::from_board(board, &self.psqt_tables);
+        }
+        if let Some(network) = self
+            .nnue
+            .as_ref()
+            .filter(|_| module_enabled(self.modules, NNUE))
+        {
+            self.search_stack.frame_mut(0).nnue = network.accumulator(board);
+        }
+    }
+
+    /// Expected score, from 0 to 1, of the side whose point of view `eval` is from
+    pub(crate) fn win_probability(&self, eval: i32) -> f32 {
+        eval::win_probability(eval as f32, self.win_probability_scale)
+    }
+
+    /// Static eval of `board` outside of a search, from the point of view of the side to move
+    pub(crate) fn static_eval(&mut self, board: &Board) -> i32 {
+        self.reset_search_stack(board);
+        self.eval(board, 0, &mut Stats::default(), None)
+    }
+
+    /// Static eval of `board` like `eval`, along with every term it is made of
+    pub(crate) fn eval_breakdown(&mut self, board: &Board, ply: u32) -> EvalBreakdown {
+        let mut breakdown = EvalBreakdown::default();
+        let total = self.eval_terms(
+            board,
+            ply,
+            &mut Stats::default(),
+            None,
+            Some(&mut breakdown),
+        );
+        EvalBreakdown { total, ..breakdown }
+    }
+
+    /// `eval`, writing the terms to `breakdown` when there is one
+    fn eval_terms(
+        &mut self,
+        board: &Board,
+        ply: u32,
+        stats: &mut Stats,
+        window: Option<(i32, i32)>,
+        mut breakdown: Option<&mut EvalBreakdown>,
+    ) -> i32 {
         let board_status = board.status();
         if board_status == BoardStatus::Stalemate {
-            return 0.;
+            return 0;
         }
         if board_status == BoardStatus::Checkmate {
-            return if board.side_to_move() == Color::White {
-                f32::MIN
-            } else {
-                f32::MAX
-            };
+            return eval::mated_score(ply);
+        }
+        if self.is_path_draw(board) {
+            return 0;
+        }
+        // Only time a sample of the evals, measuring every one would cost more than some modules.
+        // Sampled by the evals themselves, as leaves don't count the evals of quiescence search.
+        self.search_stack.count(|counters| counters.evals += 1);
+        let sample_timing = self.search_stack.counting
+            && self
+                .search_stack
+                .counters()
+                .evals
+                .is_multiple_of(TIMING_SAMPLE_INTERVAL);
+
+        // Endings with known outcomes are evaluated by what is known about them instead
+        if module_enabled(self.modules, ENDGAMES)
+            && board.combined().popcnt() <= endgames::MAX_PIECES
+        {
+            let start = sample_timing.then(Instant::now);
+            let endgame = endgames::endgame_eval(board);
+            utils::record_module_time(stats, ENDGAMES, start);
+            if let Some(evaluation) = endgame {
+                if let Some(breakdown) = breakdown.as_deref_mut() {
+                    breakdown.known_ending = Some(evaluation);
+                }
+                return eval::from_perspective(board.side_to_move(), self.scaled(evaluation));
+            }
         }
-        let board_played_times = *self.board_played_times.get(board).unwrap_or(&0)
-            + *board_played_times_prediction
-                .get(&board.get_hash())
-                .unwrap_or(&0);
-        if board_played_times >= 2 {
-            // This is third time this is played. Draw by three-fold repetition
-            return 0.;
+
+        // The network takes the place of every handcrafted term
+        if let Some(network) = self
+            .nnue
+            .as_ref()
+            .filter(|_| module_enabled(self.modules, NNUE))
+        {
+            let start = sample_timing.then(Instant::now);
+            let accumulator = self.search_stack.frame(ply).nnue;
+            utils::search_assert!(
+                accumulator == network.accumulator(board),
+                "incremental NNUE accumulator differs from the board's"
+            );
+            let evaluation = network.evaluate(&accumulator);
+            utils::record_module_time(stats, NNUE, start);
+            if let Some(breakdown) = breakdown.as_deref_mut() {
+                breakdown.nnue = Some(evaluation);
+            }
+            return eval::from_perspective(board.side_to_move(), self.scaled(evaluation));
         }
+
         let material_each_side: (u32, u32) = utils::material_each_side(board);
+        // How far from the endgame the position is, for tapered terms
+        let phase = utils::game_phase(board);
 
         // Negative when black has advantage
         let diff_material: i32 = material_each_side.0 as i32 - material_each_side.1 as i32;
-
-        let mut controlled_squares = 0;
-        if module_enabled(self.modules, SQUARE_CONTROL_METRIC) {
-            controlled_squares = if board.side_to_move() == Color::Black {
-                -1i32
-            } else {
-                1i32
-            } * MoveGen::new_legal(board).count() as i32;
-        }
+        let params = &self.eval_params;
 
         // Compares piece position with an 8x8 table containing certain values. The value corresponding to the position of the piece gets added as evaluation.
-        let mut naive_psqt: f32 = 0.;
+        let mut naive_psqt: i32 = 0;
+        let start = sample_timing.then(Instant::now);
         if module_enabled(self.modules, NAIVE_PSQT) {
             fn naive_psqt_calc(
-                naive_psqt_table: [f32; 64],
+                naive_psqt_table: [i32; 64],
                 piece_bitboard: &BitBoard,
                 color_bitboard: &BitBoard,
-            ) -> f32 {
+            ) -> i32 {
                 // Essentially, gets the dot product between a "vector" of the bitboard (containing 64 0s and 1s) and the table with NAIVE_PSQT bonus constants.
-                let mut bonus: f32 = 0.;
+                let mut bonus: i32 = 0;
                 // Gets the bitboard with all piece NAIVE_PSQTs, and runs bitwise and for the board having one's own colors.
                 for (i, table_entry) in naive_psqt_table.iter().enumerate() {
                     //The naive_psqt table and bitboard are flipped vertically, hence .reverse_colors(). Reverse colors is for some reason faster than replacing i with 56-i+2*(i%8).
                     bonus += ((piece_bitboard & color_bitboard)
                         .reverse_colors()
                         .to_size(i as u8)
-                        & 1) as f32
+                        & 1) as i32
                         * table_entry;
                 }
                 bonus
             }
 
             macro_rules! in_hash_map {
-                ($board: tt, $piece: tt, $table: tt, $hashmap: tt) => {
+                ($board: tt, $piece: tt, $hashmap: tt) => {
                     in_hash_map(
                         $board.pieces(Piece::$piece),
                         $board.color_combined($board.side_to_move()),
-                        $table,
+                        self.psqt_tables.naive[Piece::$piece.to_index()],
                         &mut self.$hashmap,
                     )
                 };
@@ -490,9 +2109,9 @@ impl Algorithm {
             fn in_hash_map(
                 piece_bitboard: &BitBoard,
                 color_bitboard: &BitBoard,
-                naive_psqt_table: [f32; 64],
-                naive_psqt_hash_map: &mut HashMap<BitBoard, f32>,
-            ) -> f32 {
+                naive_psqt_table: [i32; 64],
+                naive_psqt_hash_map: &mut HashMap<BitBoard, i32>,
+            ) -> i32 {
                 *naive_psqt_hash_map
                     .entry(piece_bitboard & color_bitboard)
                     .or_insert_with(|| {
@@ -500,115 +2119,304 @@ impl Algorithm {
                     })
             }
 
-            naive_psqt += in_hash_map!(board, Pawn, NAIVE_PSQT_TABLE_PAWN, naive_psqt_pawn_hash);
-            naive_psqt += in_hash_map!(board, Rook, NAIVE_PSQT_TABLE_ROOK, naive_psqt_rook_hash);
-            naive_psqt += in_hash_map!(board, King, NAIVE_PSQT_TABLE_KING, naive_psqt_king_hash);
-            naive_psqt += in_hash_map!(board, Queen, NAIVE_PSQT_TABLE_QUEEN, naive_psqt_queen_hash);
-            naive_psqt += in_hash_map!(
-                board,
-                Bishop,
-                NAIVE_PSQT_TABLE_BISHOP,
-                naive_psqt_bishop_hash
-            );
-            naive_psqt += in_hash_map!(
-                board,
-                Knight,
-                NAIVE_PSQT_TABLE_KNIGHT,
-                naive_psqt_knight_hash
-            );
+            naive_psqt += in_hash_map!(board, Pawn, naive_psqt_pawn_hash);
+            naive_psqt += in_hash_map!(board, Rook, naive_psqt_rook_hash);
+            naive_psqt += in_hash_map!(board, King, naive_psqt_king_hash);
+            naive_psqt += in_hash_map!(board, Queen, naive_psqt_queen_hash);
+            naive_psqt += in_hash_map!(board, Bishop, naive_psqt_bishop_hash);
+            naive_psqt += in_hash_map!(board, Knight, naive_psqt_knight_hash);
         }
+        utils::record_module_time(stats, NAIVE_PSQT, start);
 
-        let mut mg_tapered_pesto: f32 = 0.;
-        let mut eg_tapered_pesto: f32 = 0.;
-        let mut tapered_pesto: f32 = 0.;
+        let mut mg_tapered_pesto: i32 = 0;
+        let mut eg_tapered_pesto: i32 = 0;
+        let mut tapered_pesto: i32 = 0;
+        let start = sample_timing.then(Instant::now);
         if module_enabled(self.modules, TAPERED_EVERY_PESTO_PSQT) {
             for i in 0..5+1 {
-                mg_tapered_pesto += Self::calc_tapered_psqt_eval(board, i, true);
-                eg_tapered_pesto += Self::calc_tapered_psqt_eval(board, i, false);
+                mg_tapered_pesto += Self::calc_tapered_psqt_eval(board, &self.psqt_tables, i, true);
+                eg_tapered_pesto +=
+                    Self::calc_tapered_psqt_eval(board, &self.psqt_tables, i, false);
+            }
+            tapered_pesto = utils::taper((mg_tapered_pesto, eg_tapered_pesto), phase);
+        }
+        utils::record_module_time(stats, TAPERED_EVERY_PESTO_PSQT, start);
+
+        let mut incremental_psqt_eval: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, TAPERED_INCREMENTAL_PESTO_PSQT) {
+            let psqt = self.search_stack.frame(ply).psqt;
+            utils::search_assert!(
+                psqt == PsqtAccumulator::from_board(board, &self.psqt_tables),
+                "incremental PSQT {:?} differs from the board's {:?}",
+                psqt,
+                PsqtAccumulator::from_board(board, &self.psqt_tables)
+            );
+            incremental_psqt_eval = psqt.tapered(phase);
+        }
+        utils::record_module_time(stats, TAPERED_INCREMENTAL_PESTO_PSQT, start);
+
+        let material = diff_material * params.material_weight;
+        let psqt = (naive_psqt + tapered_pesto + incremental_psqt_eval) * params.psqt_scale / 100;
+
+        // Far outside of the window the slower terms below can't bring the eval back into it
+        if let (Some(margin), Some((alpha, beta))) = (self.lazy_eval_margin, window) {
+            let lazy_eval =
+                eval::from_perspective(board.side_to_move(), self.scaled(material + psqt));
+            if lazy_eval.saturating_add(margin) <= alpha || lazy_eval.saturating_sub(margin) >= beta
+            {
+                stats.lazy_evals += 1;
+                return lazy_eval;
+            }
+        }
+
+        let mut material_imbalance: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, MATERIAL_IMBALANCE) {
+            material_imbalance = material_imbalance::material_imbalance_score(board, params);
+        }
+        utils::record_module_time(stats, MATERIAL_IMBALANCE, start);
+
+        // Built once for every term that needs them
+        let attack_maps = (module_enabled(self.modules, SQUARE_CONTROL_METRIC)
+            || module_enabled(self.modules, THREATS)
+            || module_enabled(self.modules, KING_DANGER))
+        .then(|| AttackMaps::new(board));
+
+        let mut controlled_squares: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, SQUARE_CONTROL_METRIC) {
+            if let Some(attack_maps) = &attack_maps {
+                controlled_squares =
+                    square_control::square_control_score(board, attack_maps, params);
             }
-            tapered_pesto = ((material_each_side.0 + material_each_side.1
-                - 2 * piece_value(Piece::King)) as f32
-                * mg_tapered_pesto
-                + (78
-                    - (material_each_side.0 + material_each_side.1 - 2 * piece_value(Piece::King)))
-                    as f32
-                    * eg_tapered_pesto)
-                / 78.;
         }
+        utils::record_module_time(stats, SQUARE_CONTROL_METRIC, start);
 
-        let mut pawn_structure: f32 = 0.;
+        let mut pawn_structure: i32 = 0;
+        let start = sample_timing.then(Instant::now);
         if module_enabled(self.modules, PAWN_STRUCTURE) {
-            fn pawn_structure_calc(
-                all_pawn_bitboard: &BitBoard,
-                color_bitboard: &BitBoard,
-                all_king_bitboard: &BitBoard,
-            ) -> f32 {
-                let mut bonus: f32 = 0.;
-                let pawn_bitboard: usize = (all_pawn_bitboard & color_bitboard).to_size(0);
-                let king_bitboard: usize = (all_king_bitboard & color_bitboard).to_size(0);
-                //pawn chain, awarding 0.5 eval for each pawn protected by another pawn. Constants should in theory cover a (literal) edge case... I hope.
-                bonus += 0.5
-                    * ((pawn_bitboard & 0xFEFEFEFEFEFEFEFE & (pawn_bitboard << 9)).count_ones()
-                        + (pawn_bitboard & 0x7F7F7F7F7F7F7F7F & (pawn_bitboard << 7)).count_ones())
-                        as f32;
-
-                //stacked pawns. -0.5 points per rank containing >1 pawns. By taking the pawn bitboard and operating bitwise AND for another bitboard (integer) where the leftmost rank is filled. This returns all pawns in that rank. By bitshifting we can choose rank. Additionally by counting we get number of pawns. We then remove 1 as we only want to know if there are >1 pawn. If there is, subtract 0.5 points per extra pawn.
-                for i in 0..7 {
-                    //constant 0x8080808080808080: entire first rank.
-                    bonus -= 0.5
-                        * ((pawn_bitboard & (0x8080808080808080 >> i)).count_ones() as f32 - 1.)
-                            .max(0.);
-                }
+            let [(white_mg, white_eg), (black_mg, black_eg)] =
+                self.pawn_hash.probe(board, params).structure;
+            pawn_structure = utils::taper((white_mg - black_mg, white_eg - black_eg), phase);
+        }
+        utils::record_module_time(stats, PAWN_STRUCTURE, start);
 
-                //king safety. Outer 3 pawns get +1 eval bonus per pawn if king is behind them. King bitboard required is either ..X..... or ......X.
-                bonus += ((king_bitboard & 0x40).count_ones()
-                    * (pawn_bitboard & 0x80E000).count_ones()
-                    + (king_bitboard & 0x4).count_ones() * (pawn_bitboard & 0x1070000).count_ones())
-                    as f32;
-                bonus
+        let mut rook_files: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, ROOK_FILES) {
+            rook_files = rook_files::rook_file_bonus(board, params);
+        }
+        utils::record_module_time(stats, ROOK_FILES, start);
+
+        let mut passed_pawns: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, PASSED_PAWNS) {
+            let entry = self.pawn_hash.probe(board, params);
+            passed_pawns = passed_pawns::passed_pawn_bonus(
+                board,
+                entry.passed,
+                entry.candidates,
+                phase,
+                params,
+            );
+        }
+        utils::record_module_time(stats, PASSED_PAWNS, start);
+
+        let mut pawn_majority: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, PAWN_MAJORITY) {
+            let passed = self.pawn_hash.probe(board, params).passed;
+            pawn_majority = pawn_majority::pawn_majority_score(board, passed, phase, params);
+        }
+        utils::record_module_time(stats, PAWN_MAJORITY, start);
+
+        let mut rook_coordination: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, ROOK_COORDINATION) {
+            let passed = self.pawn_hash.probe(board, params).passed;
+            rook_coordination = rook_coordination::rook_coordination_bonus(board, passed, params);
+        }
+        utils::record_module_time(stats, ROOK_COORDINATION, start);
+
+        let mut rook_on_seventh: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, ROOK_ON_SEVENTH) {
+            rook_on_seventh = rook_on_seventh::rook_on_seventh_bonus(board, phase, params);
+        }
+        utils::record_module_time(stats, ROOK_ON_SEVENTH, start);
+
+        let mut king_tropism: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, KING_TROPISM) {
+            king_tropism = king_tropism::king_tropism_bonus(board, phase, params);
+        }
+        utils::record_module_time(stats, KING_TROPISM, start);
+
+        let mut king_activity: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, KING_ACTIVITY) {
+            let passed = self.pawn_hash.probe(board, params).passed;
+            king_activity = king_activity::king_activity_bonus(board, passed, phase, params);
+        }
+        utils::record_module_time(stats, KING_ACTIVITY, start);
+
+        let mut king_danger: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, KING_DANGER) {
+            if let Some(attack_maps) = &attack_maps {
+                king_danger = king_danger::king_danger_score(board, attack_maps, phase, params);
             }
+        }
+        utils::record_module_time(stats, KING_DANGER, start);
 
-            //Because pawn moves (according to chessprogramming.org) are rarely performed, hashing them is useful.
-            let pawn_bitboard: BitBoard =
-                board.pieces(Piece::Pawn) & board.color_combined(board.side_to_move());
-            self.pawn_hash.entry(pawn_bitboard).or_insert_with(|| {
-                pawn_structure_calc(
-                    board.pieces(Piece::Pawn),
-                    board.color_combined(board.side_to_move()),
-                    board.pieces(Piece::King),
-                )
-            });
-            pawn_structure = *self.pawn_hash.get(&pawn_bitboard).unwrap();
+        let mut pawn_shield: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, PAWN_SHIELD) {
+            pawn_shield = pawn_shield::pawn_shield_score(board, phase, params);
         }
+        utils::record_module_time(stats, PAWN_SHIELD, start);
 
-        let mut incremental_psqt_eval: f32 = 0.;
-        if module_enabled(self.modules, TAPERED_INCREMENTAL_PESTO_PSQT) {
-            incremental_psqt_eval = (material_each_side.0 + material_each_side.1
-                - 2 * piece_value(Piece::King)) as f32
-                * mg_incremental_psqt_eval
-                + (78 - material_each_side.0 + material_each_side.1 - 2 * piece_value(Piece::King))
-                    as f32
-                    * eg_incremental_psqt_eval
+        let mut trapped_pieces: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, TRAPPED_PIECES) {
+            trapped_pieces = trapped_pieces::trapped_piece_score(board, params);
+        }
+        utils::record_module_time(stats, TRAPPED_PIECES, start);
+
+        let mut bishop_color: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, BISHOP_COLOR) {
+            let entry = self.pawn_hash.probe(board, params);
+            bishop_color = bishop_color::bishop_color_score(
+                board,
+                entry.dark_pawns,
+                entry.light_pawns,
+                phase,
+                params,
+            );
+        }
+        utils::record_module_time(stats, BISHOP_COLOR, start);
+
+        let mut threats: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, THREATS) {
+            if let Some(attack_maps) = &attack_maps {
+                threats = threats::threat_bonus(board, attack_maps, params);
+            }
+        }
+        utils::record_module_time(stats, THREATS, start);
+
+        let mut tempo: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, TEMPO) {
+            tempo = tempo::tempo_bonus(board.side_to_move(), phase, params);
         }
+        utils::record_module_time(stats, TEMPO, start);
 
-        let evaluation: f32 = controlled_squares as f32 / 20.
-            + diff_material as f32
-            + naive_psqt
+        let mut early_queen: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, EARLY_QUEEN) {
+            early_queen = early_queen::early_queen_score(board, phase, params);
+        }
+        utils::record_module_time(stats, EARLY_QUEEN, start);
+
+        // In centipawns, positive when white is better until it is turned to the side to move
+        let evaluation: i32 = controlled_squares
+            + material
+            + material_imbalance
+            + psqt
             + pawn_structure
-            + tapered_pesto
-            + incremental_psqt_eval;
-        evaluation
+            + rook_files
+            + passed_pawns
+            + pawn_majority
+            + rook_coordination
+            + rook_on_seventh
+            + king_tropism
+            + king_activity
+            + king_danger
+            + pawn_shield
+            + trapped_pieces
+            + bishop_color
+            + threats
+            + tempo
+            + early_queen;
+
+        let start = sample_timing.then(Instant::now);
+        let drawishness_scale =
+            module_enabled(self.modules, ENDGAMES).then(|| endgames::drawishness_scale(board));
+        let evaluation = match drawishness_scale {
+            Some(scale) => evaluation * scale / endgames::NORMAL_SCALE,
+            None => evaluation,
+        };
+        utils::record_module_time(stats, ENDGAMES, start);
+
+        let start = sample_timing.then(Instant::now);
+        let halfmove_scale = module_enabled(self.modules, HALFMOVE_SCALING).then(|| {
+            let halfmove_clock = self.position_history.halfmove_clock(board);
+            halfmove_scaling::halfmove_scale(halfmove_clock, params)
+        });
+        let evaluation = match halfmove_scale {
+            Some(scale) => evaluation * scale / 100,
+            None => evaluation,
+        };
+        utils::record_module_time(stats, HALFMOVE_SCALING, start);
+
+        let mut stalemate_trap: i32 = 0;
+        let start = sample_timing.then(Instant::now);
+        if module_enabled(self.modules, STALEMATE_TRAP) {
+            stalemate_trap = stalemate_trap::stalemate_trap_score(board, evaluation, params);
+        }
+        utils::record_module_time(stats, STALEMATE_TRAP, start);
+        let evaluation = evaluation + stalemate_trap;
+
+        if let Some(breakdown) = breakdown {
+            *breakdown = EvalBreakdown {
+                material,
+                material_imbalance,
+                psqt,
+                square_control: controlled_squares,
+                pawn_structure,
+                rook_files,
+                passed_pawns,
+                pawn_majority,
+                rook_coordination,
+                rook_on_seventh,
+                king_tropism,
+                king_activity,
+                king_danger,
+                pawn_shield,
+                trapped_pieces,
+                bishop_color,
+                threats,
+                tempo,
+                early_queen,
+                drawishness_scale,
+                halfmove_scale,
+                stalemate_trap,
+                ..EvalBreakdown::default()
+            };
+        }
+
+        eval::from_perspective(board.side_to_move(), self.scaled(evaluation))
+    }
+
+    /// Applies the eval scale and clamp to an eval from white's point of view
+    fn scaled(&self, evaluation: i32) -> i32 {
+        ((evaluation as f32 * self.eval_scale).round() as i32)
+            .clamp(-self.eval_clamp, self.eval_clamp)
     }
 
-    fn calc_tapered_psqt_eval(board: &Board, piece: u8, mg_eg: bool) -> f32 {
+    fn calc_tapered_psqt_eval(board: &Board, tables: &PsqtTables, piece: u8, mg_eg: bool) -> i32 {
         fn tapered_psqt_calc(
             piece_bitboard: &BitBoard,
             color_bitboard: &BitBoard,
+            tables: &PsqtTables,
             piece_index: usize,
             mg_eg: bool,
-        ) -> f32 {
+        ) -> i32 {
             // Essentially, gets the dot product between a "vector" of the bitboard (containing 64 0s and 1s) and the table with NAIVE_PSQT bonus constants.
-            let mut bonus: f32 = 0.;
+            let mut bonus: i32 = 0;
 
             if mg_eg {
                 // Gets the bitboard with all piece positions, and runs bitwise and for the board having one's own colors.
@@ -621,8 +2429,8 @@ impl Algorithm {
                     bonus += ((piece_bitboard & color_bitboard)
                         .reverse_colors()
                         .to_size(i as u8)
-                        & 1) as f32
-                        * TAPERED_MG_PESTO[piece_index][i];
+                        & 1) as i32
+                        * tables.mg[piece_index][i];
                 }
                 bonus
             } else {
@@ -630,8 +2438,8 @@ impl Algorithm {
                     bonus += ((piece_bitboard & color_bitboard)
                         .reverse_colors()
                         .to_size(i as u8)
-                        & 1) as f32
-                        * TAPERED_EG_PESTO[piece_index][i];
+                        & 1) as i32
+                        * tables.eg[piece_index][i];
                 }
                 bonus
             }
@@ -642,6 +2450,7 @@ impl Algorithm {
                 tapered_psqt_calc(
                     $board.pieces(Piece::$piece),
                     $board.color_combined($board.side_to_move()),
+                    tables,
                     $index,
                     $mg_eg,
                 )
@@ -659,8 +2468,24 @@ impl Algorithm {
     }
 
     pub(crate) fn reset(&mut self) {
-        self.board_played_times = HashMap::new();
-        self.pawn_hash = HashMap::new();
+        self.position_history = RepetitionStack::default();
+        self.last_position = None;
+        self.noise_rng = StdRng::seed_from_u64(self.noise_seed);
+        self.pawn_hash = PawnHashTable::default();
+        self.clear_naive_psqt_hashes();
+        if self.transposition_clear_policy >= ClearPolicy::EveryGame {
+            self.transposition_table.clear();
+        }
+    }
+
+    /// Forgets what was learned from the last game pair, before the first game of the next one
+    pub(crate) fn new_game_pair(&mut self) {
+        if self.transposition_clear_policy >= ClearPolicy::EveryGamePair {
+            self.transposition_table.clear();
+        }
+    }
+
+    fn clear_naive_psqt_hashes(&mut self) {
         self.naive_psqt_pawn_hash = HashMap::new();
         self.naive_psqt_king_hash = HashMap::new();
         self.naive_psqt_queen_hash = HashMap::new();
@@ -669,3 +2494,32 @@ impl Algorithm {
         self.naive_psqt_knight_hash = HashMap::new();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::Board;
+    use tokio::time::Duration;
+
+    use super::Algorithm;
+    use crate::algorithms::eval;
+    use crate::common::constants::modules::QUIESCENCE;
+    use crate::common::constants::MATE_SCORE;
+    use crate::common::utils::Stats;
+
+    #[test]
+    fn quiescence_searches_quiet_evasions_in_check() {
+        // The queen checks from f5 and c8 in turn. Black is ahead by the eval, but every
+        // evasion is quiet and loses the rook.
+        let board = Board::from_str("b1r5/6pk/7p/5Q2/8/8/8/6K1 b - - 0 1").unwrap();
+        let mut algorithm = Algorithm::new(QUIESCENCE, Duration::from_secs(1));
+        let mut stats = Stats::default();
+        let stand_pat = algorithm.eval(&board, 0, &mut stats, None);
+        let eval = algorithm.quiescence_search(&board, -MATE_SCORE, MATE_SCORE, 0, 0, &mut stats);
+        // Searching only the captures would find none and return being mated
+        assert!(!eval::is_mate_score(eval), "eval {}", eval);
+        // Standing pat would return the eval of the board itself
+        assert!(eval < stand_pat, "eval {} stand pat {}", eval, stand_pat);
+    }
+}
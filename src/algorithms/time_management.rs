@@ -0,0 +1,28 @@
+use chess::{Board, MoveGen};
+use tokio::time::Duration;
+
+/// The remaining time is planned as if it was split evenly over this many moves
+const EXPECTED_MOVES_LEFT: u32 = 30;
+/// Positions with this many legal moves get the even split, more moves get more time and fewer
+/// get less
+const TYPICAL_LEGAL_MOVES: f32 = 30.;
+/// The even split is scaled by at most this much up or down
+const MAX_COMPLEXITY_SCALE: f32 = 2.;
+/// No single move gets more than this fraction of the remaining time
+const MAX_FRACTION_OF_REMAINING: f32 = 0.25;
+
+/// How long to think about a move on a clock with `remaining` time left, which gets
+/// `increment` added after every move. Complex positions with many legal moves get more time
+/// and forced ones with a single legal move none at all, so that only the first depth is
+/// searched.
+pub(crate) fn allocate(board: &Board, remaining: Duration, increment: Duration) -> Duration {
+    let num_legal_moves = MoveGen::new_legal(board).len();
+    if num_legal_moves <= 1 {
+        return Duration::ZERO;
+    }
+    let complexity = (num_legal_moves as f32 / TYPICAL_LEGAL_MOVES)
+        .clamp(1. / MAX_COMPLEXITY_SCALE, MAX_COMPLEXITY_SCALE);
+    (remaining / EXPECTED_MOVES_LEFT + increment)
+        .mul_f32(complexity)
+        .min(remaining.mul_f32(MAX_FRACTION_OF_REMAINING))
+}
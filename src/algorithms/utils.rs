@@ -1,34 +1,99 @@
-use chess::Action;
+use chess::{Action, ChessMove, Piece};
 
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub(crate) struct Evaluation {
     // pub(super) debug_data: Option<Vec<String>>,
-    pub(crate) eval: Option<f32>,
+    /// In centipawns, positive when the side to move in the evaluated position is better
+    pub(crate) eval: Option<i32>,
     pub(crate) next_action: Option<Action>,
-    pub(super) incremental_psqt_eval: Option<f32>,
+    /// The eval depends on the path to this position, e.g. a draw by repetition of a position
+    /// earlier on the path. Such evals must not be reused through the transposition table.
+    pub(crate) path_dependent: bool,
+    /// Whether the eval is exact or, as the search fell outside of the window, only a bound
+    pub(crate) bound: Bound,
+}
+
+/// Whether a score is the exact score of the position or only a bound on it
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[repr(u8)]
+pub(crate) enum Bound {
+    Exact = 0,
+    /// The position is at least this good, as the search failed high
+    Lower = 1,
+    /// The position is at most this good, as the search failed low
+    Upper = 2,
+}
+
+impl Bound {
+    /// The bound of a score from the other side's point of view
+    pub(crate) fn negated(self) -> Bound {
+        match self {
+            Bound::Exact => Bound::Exact,
+            Bound::Lower => Bound::Upper,
+            Bound::Upper => Bound::Lower,
+        }
+    }
+}
+
+/// The expected type of a node in the search, from how the node was reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeType {
+    /// On the principal variation, all moves are searched with an open window
+    Pv,
+    /// Expected to fail high, one good enough move causes a cutoff
+    Cut,
+    /// Expected to fail low, all moves have to be searched
+    All,
+}
+
+/// The move that led to a node, for heuristics that respond to the opponent's last move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LastMove {
+    pub(crate) chess_move: ChessMove,
+    /// The piece that the move captured, if any
+    pub(crate) captured: Option<Piece>,
+}
+
+impl NodeType {
+    /// The expected type of a child, `first` being whether it's the first move searched
+    pub(crate) fn child(self, first: bool) -> NodeType {
+        match self {
+            NodeType::Pv if first => NodeType::Pv,
+            NodeType::Pv | NodeType::All => NodeType::Cut,
+            NodeType::Cut => NodeType::All,
+        }
+    }
 }
 
 impl Evaluation {
     pub(crate) fn new(
-        eval: Option<f32>,
+        eval: Option<i32>,
         next_action: Option<Action>,
         // debug_data: Option<Vec<String>>,
-        incremental_psqt_eval: Option<f32>,
     ) -> Evaluation {
         Evaluation {
             eval,
             next_action,
             // debug_data,
-            incremental_psqt_eval,
+            path_dependent: false,
+            bound: Bound::Exact,
         }
     }
 
+    /// The evaluation from the point of view of the other side, i.e. of the parent node
+    pub(crate) fn negated(mut self) -> Evaluation {
+        self.eval = self.eval.map(|eval| -eval);
+        self.bound = self.bound.negated();
+        self
+    }
+
     pub fn empty() -> Evaluation {
         Evaluation {
             eval: None,
             next_action: None,
             // debug_data: None,
-            incremental_psqt_eval: None,
+            path_dependent: false,
+            bound: Bound::Exact,
         }
     }
 }
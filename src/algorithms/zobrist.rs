@@ -0,0 +1,384 @@
+use std::sync::OnceLock;
+
+use chess::{
+    get_adjacent_files, get_rank, BitBoard, Board, BoardBuilder, CastleRights, ChessMove, Color,
+    File, Piece, Rank, Square, ALL_CASTLE_RIGHTS, ALL_COLORS, ALL_FILES, ALL_PIECES, ALL_SQUARES,
+    EMPTY,
+};
+
+/// Kings are keyed relative to a king on this square, as a board always has exactly one king of
+/// each color. Moving a king only needs the keys of two squares, in which the reference cancels.
+const KING_REFERENCE: Square = Square::D4;
+
+/// What each part of a board adds to `Board::get_hash`. The chess crate doesn't export its keys,
+/// so they are taken from the hashes of boards that differ in that one part.
+struct ZobristKeys {
+    /// By color, piece and square index
+    pieces: [[[u64; 64]; 6]; 2],
+    /// By color and castle rights index, relative to no castle rights
+    castle_rights: [[u64; 4]; 2],
+    /// By the side to move and the file of the pawn that can be captured
+    en_passant: [[u64; 8]; 2],
+    side_to_move: u64,
+}
+
+static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+
+fn keys() -> &'static ZobristKeys {
+    KEYS.get_or_init(ZobristKeys::recover)
+}
+
+impl ZobristKeys {
+    fn recover() -> ZobristKeys {
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castle_rights: [[0; 4]; 2],
+            en_passant: [[0; 8]; 2],
+            side_to_move: 0,
+        };
+        let no_rights = [CastleRights::NoRights; 2];
+
+        for color in ALL_COLORS {
+            for square in ALL_SQUARES {
+                // The other king is kept in a far corner, where the piece can't touch it
+                let corner = far_corner(square);
+                let other_corner = Square::make_square(corner.get_rank(), far_file(corner));
+                for piece in ALL_PIECES {
+                    let key = if piece == Piece::King {
+                        let kings =
+                            |king| [(king, Piece::King, color), (corner, Piece::King, !color)];
+                        hash(&kings(square), !color, no_rights)
+                            ^ hash(&kings(KING_REFERENCE), !color, no_rights)
+                    } else if piece == Piece::Pawn && back_rank(square) {
+                        continue;
+                    } else {
+                        // The other side is to move, so that it may be in check from the piece
+                        let kings = [
+                            (corner, Piece::King, color),
+                            (other_corner, Piece::King, !color),
+                        ];
+                        let mut pieces = kings.to_vec();
+                        pieces.push((square, piece, color));
+                        hash(&pieces, !color, no_rights) ^ hash(&kings, !color, no_rights)
+                    };
+                    keys.pieces[color.to_index()][piece.to_index()][square.to_index()] = key;
+                }
+            }
+        }
+
+        let home_pieces = [
+            (Square::E1, Piece::King, Color::White),
+            (Square::A1, Piece::Rook, Color::White),
+            (Square::H1, Piece::Rook, Color::White),
+            (Square::E8, Piece::King, Color::Black),
+            (Square::A8, Piece::Rook, Color::Black),
+            (Square::H8, Piece::Rook, Color::Black),
+        ];
+        for color in ALL_COLORS {
+            for rights in ALL_CASTLE_RIGHTS {
+                let mut castle_rights = no_rights;
+                castle_rights[color.to_index()] = rights;
+                keys.castle_rights[color.to_index()][rights.to_index()] =
+                    hash(&home_pieces, Color::White, castle_rights)
+                        ^ hash(&home_pieces, Color::White, no_rights);
+            }
+        }
+
+        let kings = [
+            (Square::A1, Piece::King, Color::White),
+            (Square::H8, Piece::King, Color::Black),
+        ];
+        keys.side_to_move =
+            hash(&kings, Color::Black, no_rights) ^ hash(&kings, Color::White, no_rights);
+
+        // A double push next to an enemy pawn, so that the move sets en passant the way the
+        // chess crate does, compared to the same board without it
+        for color in ALL_COLORS {
+            for file in ALL_FILES {
+                let start = Square::make_square(color.to_second_rank(), file);
+                let dest = Square::make_square(color.to_fourth_rank(), file);
+                let capturer_file = if file == File::A {
+                    File::B
+                } else {
+                    file.left()
+                };
+                let capturer = Square::make_square(color.to_fourth_rank(), capturer_file);
+                let kings = [
+                    (
+                        Square::make_square(color.to_my_backrank(), far_file(dest)),
+                        Piece::King,
+                        color,
+                    ),
+                    (
+                        Square::make_square((!color).to_my_backrank(), far_file(dest)),
+                        Piece::King,
+                        !color,
+                    ),
+                ];
+                let mut before = kings.to_vec();
+                before.extend([(start, Piece::Pawn, color), (capturer, Piece::Pawn, !color)]);
+                let mut after = kings.to_vec();
+                after.extend([(dest, Piece::Pawn, color), (capturer, Piece::Pawn, !color)]);
+                let pushed = board(&before, color, no_rights)
+                    .make_move_new(ChessMove::new(start, dest, None));
+                keys.en_passant[(!color).to_index()][file.to_index()] =
+                    pushed.get_hash() ^ hash(&after, !color, no_rights);
+            }
+        }
+        keys
+    }
+
+    fn piece(&self, color: Color, piece: Piece, square: Square) -> u64 {
+        self.pieces[color.to_index()][piece.to_index()][square.to_index()]
+    }
+
+    fn castle_rights(&self, color: Color, rights: CastleRights) -> u64 {
+        self.castle_rights[color.to_index()][rights.to_index()]
+    }
+
+    fn en_passant(&self, board: &Board) -> u64 {
+        board.en_passant().map_or(0, |square| {
+            self.en_passant[board.side_to_move().to_index()][square.get_file().to_index()]
+        })
+    }
+}
+
+fn board(
+    pieces: &[(Square, Piece, Color)],
+    side_to_move: Color,
+    castle_rights: [CastleRights; 2],
+) -> Board {
+    let mut builder = BoardBuilder::new();
+    for &(square, piece, color) in pieces {
+        builder.piece(square, piece, color);
+    }
+    builder
+        .side_to_move(side_to_move)
+        .castle_rights(Color::White, castle_rights[0])
+        .castle_rights(Color::Black, castle_rights[1]);
+    Board::try_from(&builder).expect("boards that the keys are recovered from are legal")
+}
+
+fn hash(
+    pieces: &[(Square, Piece, Color)],
+    side_to_move: Color,
+    castle_rights: [CastleRights; 2],
+) -> u64 {
+    board(pieces, side_to_move, castle_rights).get_hash()
+}
+
+/// The corner at least four files and ranks away from `square`
+fn far_corner(square: Square) -> Square {
+    let rank = if square.get_rank().to_index() < 4 {
+        Rank::Eighth
+    } else {
+        Rank::First
+    };
+    Square::make_square(rank, far_file(square))
+}
+
+/// The edge file at least four files away from `square`
+fn far_file(square: Square) -> File {
+    if square.get_file().to_index() < 4 {
+        File::H
+    } else {
+        File::A
+    }
+}
+
+fn back_rank(square: Square) -> bool {
+    matches!(square.get_rank(), Rank::First | Rank::Eighth)
+}
+
+/// A key of a board that is updated move by move instead of hashing the whole board again. It is
+/// always equal to `Board::get_hash` of the board that the moves lead to, so it can stand in for
+/// it in the repetition stack, the pawn hash and the transposition table.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ZobristKey(u64);
+
+#[allow(dead_code)]
+impl ZobristKey {
+    pub(crate) fn new(board: &Board) -> ZobristKey {
+        ZobristKey(board.get_hash())
+    }
+
+    pub(crate) fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Updates the key of `board` to that of the board after the legal move `chess_move`
+    pub(crate) fn make_move(&mut self, board: &Board, chess_move: ChessMove) {
+        let keys = keys();
+        let color = board.side_to_move();
+        let (source, dest) = (chess_move.get_source(), chess_move.get_dest());
+        let Some(moved) = board.piece_on(source) else {
+            return;
+        };
+
+        let mut key = keys.piece(color, moved, source)
+            ^ keys.piece(color, chess_move.get_promotion().unwrap_or(moved), dest);
+        if let Some(captured) = board.piece_on(dest) {
+            key ^= keys.piece(!color, captured, dest);
+        } else if moved == Piece::Pawn && source.get_file() != dest.get_file() {
+            // En passant, where the captured pawn is beside the source square
+            let captured = Square::make_square(source.get_rank(), dest.get_file());
+            key ^= keys.piece(!color, Piece::Pawn, captured);
+        }
+        if moved == Piece::King
+            && source
+                .get_file()
+                .to_index()
+                .abs_diff(dest.get_file().to_index())
+                == 2
+        {
+            let rank = source.get_rank();
+            let (rook_source, rook_dest) = if dest.get_file() == File::G {
+                (File::H, File::F)
+            } else {
+                (File::A, File::D)
+            };
+            key ^= keys.piece(color, Piece::Rook, Square::make_square(rank, rook_source))
+                ^ keys.piece(color, Piece::Rook, Square::make_square(rank, rook_dest));
+        }
+
+        // Castle rights are lost by moving the king or a rook, or by a rook being captured
+        for rights_color in ALL_COLORS {
+            let rights = board.castle_rights(rights_color);
+            let remaining = rights
+                .remove(CastleRights::square_to_castle_rights(rights_color, source))
+                .remove(CastleRights::square_to_castle_rights(rights_color, dest));
+            key ^= keys.castle_rights(rights_color, rights)
+                ^ keys.castle_rights(rights_color, remaining);
+        }
+
+        key ^= keys.en_passant(board) ^ keys.side_to_move;
+        // Like the chess crate, en passant is only kept when an enemy pawn could capture
+        let double_push = moved == Piece::Pawn
+            && source
+                .get_rank()
+                .to_index()
+                .abs_diff(dest.get_rank().to_index())
+                == 2;
+        let capturers: BitBoard = get_adjacent_files(dest.get_file())
+            & get_rank(dest.get_rank())
+            & board.pieces(Piece::Pawn)
+            & board.color_combined(!color);
+        if double_push && capturers != EMPTY {
+            key ^= keys.en_passant[(!color).to_index()][dest.get_file().to_index()];
+        }
+        self.0 ^= key;
+    }
+
+    /// Updates the key of `board` to that of `board.null_move()`
+    pub(crate) fn make_null_move(&mut self, board: &Board) {
+        let keys = keys();
+        self.0 ^= keys.en_passant(board) ^ keys.side_to_move;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::{Board, ChessMove, MoveGen};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::ZobristKey;
+
+    /// Plays every legal move of `board` and checks the updated key against the hash
+    fn check_every_move(board: &Board) {
+        for chess_move in MoveGen::new_legal(board) {
+            let mut key = ZobristKey::new(board);
+            key.make_move(board, chess_move);
+            assert_eq!(
+                key.get(),
+                board.make_move_new(chess_move).get_hash(),
+                "{} after {} from {}",
+                key.get(),
+                chess_move,
+                board
+            );
+        }
+    }
+
+    fn check_line(fen: &str, moves: &[&str]) {
+        let mut board = Board::from_str(fen).unwrap();
+        let mut key = ZobristKey::new(&board);
+        for chess_move in moves {
+            let chess_move = ChessMove::from_san(&board, chess_move).unwrap();
+            key.make_move(&board, chess_move);
+            board = board.make_move_new(chess_move);
+            assert_eq!(
+                key.get(),
+                board.get_hash(),
+                "after {} in {}",
+                chess_move,
+                board
+            );
+        }
+    }
+
+    #[test]
+    fn matches_random_games() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let mut board = Board::default();
+            let mut key = ZobristKey::new(&board);
+            for _ in 0..200 {
+                check_every_move(&board);
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                if moves.is_empty() {
+                    break;
+                }
+                let chess_move = moves[rng.gen_range(0..moves.len())];
+                key.make_move(&board, chess_move);
+                board = board.make_move_new(chess_move);
+                assert_eq!(key.get(), board.get_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn matches_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        check_line(fen, &["O-O", "O-O-O"]);
+        check_line(fen, &["O-O-O", "O-O"]);
+        // Moving or losing a rook only takes away castling on its side
+        check_line(fen, &["Rxa8", "Kd7", "Rh2", "Ke6"]);
+        check_line(fen, &["Kf1", "Rh7"]);
+    }
+
+    #[test]
+    fn matches_en_passant() {
+        // Capturable, captured, and pushed past with no pawn beside it
+        check_line(
+            "4k3/8/8/8/5p2/8/4P1P1/4K3 w - - 0 1",
+            &["e4", "fxe3", "g4", "Kd7"],
+        );
+        // Passing on the capture drops it again
+        check_line("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1", &["d5", "Kd2", "Kd8"]);
+    }
+
+    #[test]
+    fn matches_promotions() {
+        check_line(
+            "1r6/P3k3/8/8/8/8/p3K3/1R6 w - - 0 1",
+            &["axb8=Q", "axb1=N", "Qxb1", "Kd6"],
+        );
+    }
+
+    #[test]
+    fn matches_null_moves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            let mut key = ZobristKey::new(&board);
+            key.make_null_move(&board);
+            assert_eq!(key.get(), board.null_move().unwrap().get_hash());
+        }
+    }
+}
@@ -1,237 +1,355 @@
+use chess::Piece;
+
 pub(crate) mod modules {
-    pub(crate) const ANALYZE: u32 = 1;
-    pub(crate) const ALPHA_BETA: u32 = 1 << 1;
-    pub(crate) const TRANSPOSITION_TABLE: u32 = 1 << 2;
-    pub(crate) const SEARCH_EXTENSIONS: u32 = 1 << 3;
-    pub(crate) const SQUARE_CONTROL_METRIC: u32 = 1 << 4;
-    pub(crate) const SKIP_BAD_MOVES: u32 = 1 << 5;
-    pub(crate) const NAIVE_PSQT: u32 = 1 << 6;
-    pub(crate) const PAWN_STRUCTURE: u32 = 1 << 7;
-    pub(crate) const TAPERED_EVERY_PESTO_PSQT: u32 = 1 << 8;
-    pub(crate) const TAPERED_INCREMENTAL_PESTO_PSQT: u32 = 1 << 9;
+    pub(crate) const ANALYZE: u64 = 1;
+    pub(crate) const ALPHA_BETA: u64 = 1 << 1;
+    pub(crate) const TRANSPOSITION_TABLE: u64 = 1 << 2;
+    pub(crate) const SEARCH_EXTENSIONS: u64 = 1 << 3;
+    pub(crate) const SQUARE_CONTROL_METRIC: u64 = 1 << 4;
+    pub(crate) const SKIP_BAD_MOVES: u64 = 1 << 5;
+    pub(crate) const NAIVE_PSQT: u64 = 1 << 6;
+    pub(crate) const PAWN_STRUCTURE: u64 = 1 << 7;
+    pub(crate) const TAPERED_EVERY_PESTO_PSQT: u64 = 1 << 8;
+    pub(crate) const TAPERED_INCREMENTAL_PESTO_PSQT: u64 = 1 << 9;
+    pub(crate) const QUIESCENCE: u64 = 1 << 10;
+    pub(crate) const HISTORY_HEURISTIC: u64 = 1 << 11;
+    pub(crate) const LATE_MOVE_PRUNING: u64 = 1 << 12;
+    pub(crate) const PRINCIPAL_VARIATION_SEARCH: u64 = 1 << 13;
+    pub(crate) const ASPIRATION_WINDOWS: u64 = 1 << 14;
+    pub(crate) const INTERNAL_ITERATIVE_DEEPENING: u64 = 1 << 15;
+    pub(crate) const MULTI_CUT: u64 = 1 << 16;
+    pub(crate) const PROBCUT: u64 = 1 << 17;
+    pub(crate) const EARLY_TERMINATION: u64 = 1 << 18;
+    pub(crate) const ITERATION_PREDICTION: u64 = 1 << 19;
+    pub(crate) const ROOT_SPLITTING: u64 = 1 << 20;
+    pub(crate) const KILLER_MOVES: u64 = 1 << 21;
+    pub(crate) const EASY_MOVE: u64 = 1 << 22;
+    pub(crate) const ROOK_FILES: u64 = 1 << 23;
+    pub(crate) const PASSED_PAWNS: u64 = 1 << 24;
+    pub(crate) const KING_TROPISM: u64 = 1 << 25;
+    pub(crate) const TEMPO: u64 = 1 << 26;
+    pub(crate) const ROOK_COORDINATION: u64 = 1 << 27;
+    pub(crate) const PAWN_SHIELD: u64 = 1 << 28;
+    pub(crate) const TRAPPED_PIECES: u64 = 1 << 29;
+    pub(crate) const THREATS: u64 = 1 << 30;
+    pub(crate) const ENDGAMES: u64 = 1 << 31;
+    pub(crate) const NNUE: u64 = 1 << 32;
+    pub(crate) const KING_ACTIVITY: u64 = 1 << 33;
+    pub(crate) const BISHOP_COLOR: u64 = 1 << 34;
+    pub(crate) const ROOK_ON_SEVENTH: u64 = 1 << 35;
+    pub(crate) const EARLY_QUEEN: u64 = 1 << 36;
+    pub(crate) const KING_DANGER: u64 = 1 << 37;
+    pub(crate) const PAWN_MAJORITY: u64 = 1 << 38;
+    pub(crate) const MATERIAL_IMBALANCE: u64 = 1 << 39;
+    pub(crate) const STALEMATE_TRAP: u64 = 1 << 40;
+    pub(crate) const HALFMOVE_SCALING: u64 = 1 << 41;
+
+    /// Every module that adds a term to the static eval or evaluates instead of the terms
+    pub(crate) const ALL_EVAL_MODULES: u64 = SQUARE_CONTROL_METRIC
+        | NAIVE_PSQT
+        | PAWN_STRUCTURE
+        | TAPERED_EVERY_PESTO_PSQT
+        | TAPERED_INCREMENTAL_PESTO_PSQT
+        | ROOK_FILES
+        | PASSED_PAWNS
+        | KING_TROPISM
+        | TEMPO
+        | ROOK_COORDINATION
+        | PAWN_SHIELD
+        | TRAPPED_PIECES
+        | THREATS
+        | ENDGAMES
+        | NNUE
+        | KING_ACTIVITY
+        | BISHOP_COLOR
+        | ROOK_ON_SEVENTH
+        | EARLY_QUEEN
+        | KING_DANGER
+        | PAWN_MAJORITY
+        | MATERIAL_IMBALANCE
+        | STALEMATE_TRAP
+        | HALFMOVE_SCALING;
 }
 
-pub(crate) const NUMBER_OF_MODULES: usize = 10;
+pub(crate) const NUMBER_OF_MODULES: usize = 42;
+
+/// Bump this whenever an eval term is added or changed. Stored data containing evaluations is
+/// stamped with it so that data from an older eval is never silently mixed with the current one.
+pub(crate) const EVAL_VERSION: u32 = 30;
+
+/// Checkmates are scored as this minus the number of plies from the root to the mate, so that
+/// faster mates are preferred
+pub(crate) const MATE_SCORE: i32 = 1_000_000;
+/// Mates further away than this are not told apart
+pub(crate) const MAX_MATE_PLIES: u32 = 1000;
+
+/// Plies without a capture or pawn move after which the game is drawn by the fifty-move rule
+pub(crate) const FIFTY_MOVE_PLIES: u32 = 100;
+
+/// Game phase of the starting position, where tapered eval terms are fully middlegame. Phases
+/// above it, after promotions, count as this.
+pub(crate) const MAX_PHASE: i32 = 24;
+/// How much each piece adds to the game phase. Pawns and kings don't count, so that the phase
+/// only tells how far the pieces have been traded off and not who is ahead.
+pub(crate) const PHASE_WEIGHTS: [(Piece, i32); 4] = [
+    (Piece::Knight, 1),
+    (Piece::Bishop, 1),
+    (Piece::Rook, 2),
+    (Piece::Queen, 4),
+];
+
+/// Evals are in centipawns by default
+pub(crate) const DEFAULT_EVAL_SCALE: f32 = 1.;
+/// Large enough to separate any realistic material difference, small enough to keep terms with
+/// large magnitudes from drowning out everything else
+pub(crate) const DEFAULT_EVAL_CLAMP: i32 = 2000;
+/// Centipawns of advantage at which the side ahead is expected to score ten times as much as
+/// the other, as on the Elo scale
+pub(crate) const DEFAULT_WIN_PROBABILITY_SCALE: f32 = 400.;
+
+/// Timing every eval or transposition table access would cost more than what is being timed,
+/// so only every n:th one is timed and the time is scaled up
+pub(crate) const TIMING_SAMPLE_INTERVAL: u32 = 64;
+
+/// Every dark square, a1 being one of them
+pub(crate) const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
 
 //NAIVE_PSQT TABLES
 pub(crate) mod naive_psqt_tables {
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_PAWN: [f32; 64] = [
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        1., 1., 1., 1., 1., 1., 1., 1.,
-        0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5,
-        0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2, 0.2,
-        0., 0.1, 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0., 0.1, 0.1, 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.
+    pub(crate) const NAIVE_PSQT_TABLE_PAWN: [i32; 64] = [
+           0,    0,    0,    0,    0,    0,    0,    0,
+         100,  100,  100,  100,  100,  100,  100,  100,
+          50,   50,   50,   50,   50,   50,   50,   50,
+          20,   20,   20,   20,   20,   20,   20,   20,
+           0,   10,   10,   10,   10,   10,    0,    0,
+           0,    0,    0,   10,   10,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0
     ];
 
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_KNIGHT: [f32; 64] = [
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0.2, 0.2, 0., 0., 0.,
-        0., 0., 0.2, 0.2, 0.2, 0.2, 0., 0.,
-        0., 0., 0.2, 0.2, 0.2, 0.2, 0., 0.,
-        0., 0., 0.1, 0.2, 0.2, 0.1, 0., 0.,
-        0., 0., 0., 0.1, 0.1, 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.
+    pub(crate) const NAIVE_PSQT_TABLE_KNIGHT: [i32; 64] = [
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,   20,   20,    0,    0,    0,
+           0,    0,   20,   20,   20,   20,    0,    0,
+           0,    0,   20,   20,   20,   20,    0,    0,
+           0,    0,   10,   20,   20,   10,    0,    0,
+           0,    0,    0,   10,   10,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0
     ];
 
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_ROOK: [f32; 64] = [
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.
+    pub(crate) const NAIVE_PSQT_TABLE_ROOK: [i32; 64] = [
+           0,    0,   10,   10,   10,   10,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,   10,   10,   10,   10,    0,    0
     ];
 
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_BISHOP: [f32; 64] = [
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0.05, 0.1, 0., 0., 0.1, 0.05, 0.,
-        0.05, 0.1, 0.05, 0., 0., 0.05, 0.1, 0.05,
-        0., 0.05, 0., 0., 0., 0., 0.05, 0.
+    pub(crate) const NAIVE_PSQT_TABLE_BISHOP: [i32; 64] = [
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    5,   10,    0,    0,   10,    5,    0,
+           5,   10,    5,    0,    0,    5,   10,    5,
+           0,    5,    0,    0,    0,    0,    5,    0
     ];
 
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_QUEEN: [f32; 64] = [
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0.1, 0.1, 0.1, 0.1, 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.
+    pub(crate) const NAIVE_PSQT_TABLE_QUEEN: [i32; 64] = [
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,   10,   10,   10,   10,    0,    0,
+           0,    0,   10,   10,   10,   10,    0,    0,
+           0,    0,   10,   10,   10,   10,    0,    0,
+           0,    0,   10,   10,   10,   10,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0
     ];
 
     #[rustfmt::skip]
-    pub(crate) const NAIVE_PSQT_TABLE_KING: [f32; 64] = [
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0., 0., 0., 0., 0., 0., 0., 0.,
-        0.1, 0.3, 0.3, 0., 0., 0., 0.3, 0.1
+    pub(crate) const NAIVE_PSQT_TABLE_KING: [i32; 64] = [
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+           0,    0,    0,    0,    0,    0,    0,    0,
+          10,   30,   30,    0,    0,    0,   30,   10
     ];
 }
 
 //Tapered PeSTO eval tables. MG = (opening +) middle-game. EG = endgame.
 pub(crate) mod tapered_pesto_psqt_tables {
     #[rustfmt::skip]
-    pub(crate) const TAPERED_MG_PESTO: [[f32; 64]; 6] = [
+    pub(crate) const TAPERED_MG_PESTO: [[i32; 64]; 6] = [
         //Pawn
         [
-        0.,    0.,    0.,    0.,    0.,   0.,   0.,    0., 
-        0.98,  1.34,  0.61,  0.95,  0.68, 1.26, 0.34, -0.11,
-       -0.06,  0.07,  0.26,  0.31,  0.65, 0.56, 0.25, -0.2,
-       -0.14,  0.13,  0.06,  0.21,  0.23, 0.12, 0.17, -0.23,
-       -0.27, -0.02, -0.05,  0.12,  0.17, 0.06, 0.10, -0.25,
-       -0.26, -0.04, -0.04, -0.10,  0.03, 0.03, 0.33, -0.12,
-       -0.35, -0.01, -0.20, -0.23, -0.15, 0.24, 0.38, -0.22,
-        0.,    0.,    0.,    0.,    0.,   0.,   0.,    0.,
+           0,    0,    0,    0,    0,    0,    0,    0,
+          98,  134,   61,   95,   68,  126,   34,  -11,
+          -6,    7,   26,   31,   65,   56,   25,  -20,
+         -14,   13,    6,   21,   23,   12,   17,  -23,
+         -27,   -2,   -5,   12,   17,    6,   10,  -25,
+         -26,   -4,   -4,  -10,    3,    3,   33,  -12,
+         -35,   -1,  -20,  -23,  -15,   24,   38,  -22,
+           0,    0,    0,    0,    0,    0,    0,    0,
         ],
 
         //Knight
         [
-           -1.67, -0.89, -0.34, -0.49,  0.61, -0.97, -0.15,  -1.07,
-           -0.73, -0.41,  0.72,  0.36,  0.23,  0.62,  0.07,  -0.17,
-           -0.47,  0.60,  0.37,  0.65,  0.84,  1.29,  0.73,   0.44,
-           -0.09,  0.17,  0.19,  0.53,  0.37,  0.69,  0.18,   0.22,
-           -0.13,  0.04,  0.16,  0.13,  0.28,  0.19,  0.21,  -0.08,
-           -0.23, -0.09,  0.12,  0.10,  0.19,  0.17,  0.25,  -0.16,
-           -0.29, -0.53, -0.12, -0.03, -0.01,  0.18, -0.14,  -0.19,
-           -1.05, -0.21, -0.58, -0.33, -0.17, -0.28, -0.19,  -0.23,
+           -167,  -89,  -34,  -49,   61,  -97,  -15, -107,
+            -73,  -41,   72,   36,   23,   62,    7,  -17,
+            -47,   60,   37,   65,   84,  129,   73,   44,
+             -9,   17,   19,   53,   37,   69,   18,   22,
+            -13,    4,   16,   13,   28,   19,   21,   -8,
+            -23,   -9,   12,   10,   19,   17,   25,  -16,
+            -29,  -53,  -12,   -3,   -1,   18,  -14,  -19,
+           -105,  -21,  -58,  -33,  -17,  -28,  -19,  -23,
         ],
 
         //Bishop
         [
-            -0.29,  0.04, -0.82, -0.37, -0.25, -0.42,  0.07, -0.08,
-            -0.26,  0.16, -0.18, -0.13,  0.30,  0.59,  0.18, -0.47,
-            -0.16,  0.37,  0.43,  0.40,  0.35,  0.50,  0.37, -0.02,
-            -0.04,  0.05,  0.19,  0.50,  0.37,  0.37,  0.07, -0.02,
-            -0.06,  0.13,  0.13,  0.26,  0.34,  0.12,  0.10,  0.04,
-             0.,    0.15,  0.15,  0.15,  0.14,  0.27,  0.18,  0.10,
-             0.04,  0.15,  0.16,   0.0,  0.07,  0.21,  0.33,  0.01,
-            -0.33, -0.03, -0.14, -0.21, -0.13, -0.12, -0.39, -0.21,
+             -29,    4,  -82,  -37,  -25,  -42,    7,   -8,
+             -26,   16,  -18,  -13,   30,   59,   18,  -47,
+             -16,   37,   43,   40,   35,   50,   37,   -2,
+              -4,    5,   19,   50,   37,   37,    7,   -2,
+              -6,   13,   13,   26,   34,   12,   10,    4,
+                0,   15,   15,   15,   14,   27,   18,   10,
+                4,   15,   16,    0,    7,   21,   33,    1,
+             -33,   -3,  -14,  -21,  -13,  -12,  -39,  -21,
         ],
 
         //Rook
         [
-            0.32,  0.42,  0.32,  0.51, 0.63, 0.09,  0.31,  0.43,
-            0.27,  0.32,  0.58,  0.62, 0.80, 0.67,  0.26,  0.44,
-           -0.05,  0.19,  0.26,  0.36, 0.17, 0.45,  0.61,  0.16,
-           -0.24, -0.11,  0.07,  0.26, 0.24, 0.35, -0.08, -0.20,
-           -0.36, -0.26, -0.12, -0.01, 0.09,-0.07,  0.06, -0.23,
-           -0.45, -0.25, -0.16, -0.17, 0.03, 0.0,  -0.05, -0.33,
-           -0.44, -0.16, -0.20, -0.09,-0.01, 0.11, -0.06, -0.71,
-           -0.19, -0.13,  0.01,  0.17, 0.16, 0.07, -0.37, -0.26,
+              32,   42,   32,   51,   63,    9,   31,   43,
+              27,   32,   58,   62,   80,   67,   26,   44,
+             -5,   19,   26,   36,   17,   45,   61,   16,
+            -24,  -11,    7,   26,   24,   35,   -8,  -20,
+            -36,  -26,  -12,   -1,    9,   -7,    6,  -23,
+            -45,  -25,  -16,  -17,    3,    0,   -5,  -33,
+            -44,  -16,  -20,   -9,   -1,   11,   -6,  -71,
+            -19,  -13,    1,   17,   16,    7,  -37,  -26,
         ],
 
         //Queen
         [
-            -0.28,   0.0,  0.29,  0.12,  0.59,  0.44,  0.43,  0.45,
-            -0.24, -0.39, -0.05,  0.01, -0.16,  0.57,  0.28,  0.54,
-            -0.13, -0.17,  0.07,  0.08,  0.29,  0.56,  0.47,  0.57,
-            -0.27, -0.27, -0.16, -0.16, -0.01,  0.17, -0.02,  0.01,
-            -0.09, -0.26, -0.09, -0.10, -0.02, -0.04,  0.03, -0.03,
-            -0.14,  0.02, -0.11, -0.02, -0.05,  0.02,  0.14,  0.05,
-            -0.35, -0.08,  0.11,  0.02,  0.08,  0.15, -0.03,  0.01,
-            -0.01, -0.18, -0.09,  0.10, -0.15, -0.25, -0.31, -0.50,
+             -28,    0,   29,   12,   59,   44,   43,   45,
+             -24,  -39,   -5,    1,  -16,   57,   28,   54,
+             -13,  -17,    7,    8,   29,   56,   47,   57,
+             -27,  -27,  -16,  -16,   -1,   17,   -2,    1,
+              -9,  -26,   -9,  -10,   -2,   -4,    3,   -3,
+             -14,    2,  -11,   -2,   -5,    2,   14,    5,
+             -35,   -8,   11,    2,    8,   15,   -3,    1,
+              -1,  -18,   -9,   10,  -15,  -25,  -31,  -50,
         ],
 
         //King
         [
-           -0.65,  0.23,  0.16, -0.15, -0.56, -0.34,  0.02,  0.13,
-            0.29, -0.01, -0.20, -0.07, -0.08, -0.04, -0.38, -0.29,
-           -0.09,  0.24,  0.02, -0.16, -0.20,  0.06,  0.22, -0.22,
-           -0.17, -0.20, -0.12, -0.27, -0.30, -0.25, -0.14, -0.36,
-           -0.49, -0.01, -0.27, -0.39, -0.46, -0.44, -0.33, -0.51,
-           -0.14, -0.14, -0.22, -0.46, -0.44, -0.30, -0.15, -0.27,
-            0.01,  0.07, -0.08, -0.64, -0.43, -0.16,  0.09,  0.08,
-           -0.15,  0.36,  0.12, -0.54,  0.08, -0.28,  0.24,  0.14,
+            -65,   23,   16,  -15,  -56,  -34,    2,   13,
+              29,   -1,  -20,   -7,   -8,   -4,  -38,  -29,
+             -9,   24,    2,  -16,  -20,    6,   22,  -22,
+            -17,  -20,  -12,  -27,  -30,  -25,  -14,  -36,
+            -49,   -1,  -27,  -39,  -46,  -44,  -33,  -51,
+            -14,  -14,  -22,  -46,  -44,  -30,  -15,  -27,
+               1,    7,   -8,  -64,  -43,  -16,    9,    8,
+            -15,   36,   12,  -54,    8,  -28,   24,   14,
         ],
     ];
 
     #[rustfmt::skip]
-    pub(crate) const TAPERED_EG_PESTO: [[f32; 64]; 6] = [
+    pub(crate) const TAPERED_EG_PESTO: [[i32; 64]; 6] = [
         //Pawn
         [
-            0., 0., 0., 0., 0., 0., 0., 0.,
-            1.78, 1.73, 1.58, 1.34, 1.47, 1.32, 1.65, 1.87,
-            0.94, 1., 0.85, 0.67, 0.56, 0.53, 0.82, 0.84,
-            0.32, 0.24, 0.13, 0.05, -0.02, 0.04, 0.17, 0.17,
-            0.13, 0.09, -0.03, -0.07, -0.07, -0.08, 0.03, -0.01,
-            0.04, 0.07, -0.06, 0.01, 0., -0.05, -0.01, -0.08,
-            0.13, 0.08, 0.08, 0.10, 0.13, 0., 0.02, -0.07,
-            0., 0., 0., 0., 0., 0., 0., 0.,
+               0,    0,    0,    0,    0,    0,    0,    0,
+             178,  173,  158,  134,  147,  132,  165,  187,
+              94,  100,   85,   67,   56,   53,   82,   84,
+              32,   24,   13,    5,   -2,    4,   17,   17,
+              13,    9,   -3,   -7,   -7,   -8,    3,   -1,
+               4,    7,   -6,    1,    0,   -5,   -1,   -8,
+              13,    8,    8,   10,   13,    0,    2,   -7,
+               0,    0,    0,    0,    0,    0,    0,    0,
         ],
         //Knight
         [
-            -0.58, -0.38, -0.13, -0.28, -0.31, -0.27, -0.63, -0.99,
-            -0.25, -0.08, -0.25, -0.02, -0.09, -0.25, -0.24, -0.52,
-            -0.24, -0.20, 0.10, 0.09, -0.01, -0.09, -0.19, -0.41,
-            -0.17, 0.03, 0.22, 0.22, 0.22, 0.11, 0.08, -0.18,
-            -0.18, -0.06, 0.16, 0.25, 0.16, 0.17, 0.04, -0.18,
-            -0.23, -0.03, -0.01, 0.15, 0.10, -0.03, -0.20, -0.22,
-            -0.42, -0.20, -0.10, -0.05, -0.02, -0.20, -0.23, -0.44,
-            -0.29, -0.51, -0.23, -0.15, -0.22, -0.18, -0.50, -0.64,
+             -58,  -38,  -13,  -28,  -31,  -27,  -63,  -99,
+             -25,   -8,  -25,   -2,   -9,  -25,  -24,  -52,
+             -24,  -20,   10,    9,   -1,   -9,  -19,  -41,
+             -17,    3,   22,   22,   22,   11,    8,  -18,
+             -18,   -6,   16,   25,   16,   17,    4,  -18,
+             -23,   -3,   -1,   15,   10,   -3,  -20,  -22,
+             -42,  -20,  -10,   -5,   -2,  -20,  -23,  -44,
+             -29,  -51,  -23,  -15,  -22,  -18,  -50,  -64,
         ],
         //Bishop
         [
-            -0.14, -0.21, -0.11, -0.08, -0.07, -0.09, -0.17, -0.24,
-            -0.08, -0.04, 0.07, -0.12, -0.03, -0.13, -0.04, -0.14,
-            0.02, -0.08, 0.0, -0.01, -0.02, 0.06, 0.0, 0.04,
-            -0.03, 0.09, 0.12, 0.09, 0.14, 0.10, 0.03, 0.02,
-            -0.06, 0.03, 0.13, 0.19, 0.07, 0.10, -0.03, -0.09,
-            -0.12, -0.03, 0.08, 0.10, 0.13, 0.03, -0.07, -0.015,
-            -0.14, -0.18, -0.07, -0.01, 0.04, -0.09, -0.15, -0.027,
-            -0.23, -0.09, -0.23, -0.05, -0.09, -0.16, -0.05, -0.017,
+             -14,  -21,  -11,   -8,   -7,   -9,  -17,  -24,
+              -8,   -4,    7,  -12,   -3,  -13,   -4,  -14,
+               2,   -8,    0,   -1,   -2,    6,    0,    4,
+              -3,    9,   12,    9,   14,   10,    3,    2,
+              -6,    3,   13,   19,    7,   10,   -3,   -9,
+             -12,   -3,    8,   10,   13,    3,   -7,   -2,
+             -14,  -18,   -7,   -1,    4,   -9,  -15,   -3,
+             -23,   -9,  -23,   -5,   -9,  -16,   -5,   -2,
         ],
         //Rook
         [
-            0.13, 0.10, 0.18, 0.15, 0.12, 0.12, 0.08, 0.05,
-            0.11, 0.13, 0.13, 0.11, -0.03, 0.03, 0.08, 0.03,
-            0.07, 0.07, 0.07, 0.05, 0.04, -0.03, -0.05, -0.03,
-            0.04, 0.03, 0.13, 0.01, 0.02, 0.01, -0.01, 0.02,
-            0.03, 0.05, 0.08, 0.04, -0.05, -0.06, -0.08, -0.11,
-            -0.04, 0.0, -0.05, -0.01, -0.07, -0.12, -0.08, -0.16,
-            -0.06, -0.06, 0.0, 0.02, -0.09, -0.09, -0.11, -0.03,
-            -0.09, 0.02, 0.03, -0.01, -0.05, -0.13, 0.04, -0.20,
+              13,   10,   18,   15,   12,   12,    8,    5,
+              11,   13,   13,   11,   -3,    3,    8,    3,
+               7,    7,    7,    5,    4,   -3,   -5,   -3,
+               4,    3,   13,    1,    2,    1,   -1,    2,
+               3,    5,    8,    4,   -5,   -6,   -8,  -11,
+              -4,    0,   -5,   -1,   -7,  -12,   -8,  -16,
+              -6,   -6,    0,    2,   -9,   -9,  -11,   -3,
+              -9,    2,    3,   -1,   -5,  -13,    4,  -20,
         ],
         //Queen
         [
-            -0.09, 0.22, 0.22, 0.27, 0.27, 0.19, 0.10, 0.20,
-            -0.17, 0.20, 0.32, 0.41, 0.58, 0.25, 0.30, 0.,
-            -0.20, 0.06, 0.09, 0.49, 0.47, 0.35, 0.19, 0.09,
-            0.03, 0.22, 0.24, 0.45, 0.57, 0.40, 0.57, 0.36,
-            -0.18, 0.28, 0.19, 0.47, 0.31, 0.34, 0.39, 0.23,
-            -0.16, -0.27, 0.15, 0.06, 0.09, 0.17, 0.10, 0.05,
-            -0.22, -0.23, -0.30, -0.16, -0.16, -0.23, -0.36, -0.32,
-            -0.33, -0.28, -0.22, -0.43, -0.05, -0.32, -0.20, -0.41,
+              -9,   22,   22,   27,   27,   19,   10,   20,
+             -17,   20,   32,   41,   58,   25,   30,    0,
+             -20,    6,    9,   49,   47,   35,   19,    9,
+               3,   22,   24,   45,   57,   40,   57,   36,
+             -18,   28,   19,   47,   31,   34,   39,   23,
+             -16,  -27,   15,    6,    9,   17,   10,    5,
+             -22,  -23,  -30,  -16,  -16,  -23,  -36,  -32,
+             -33,  -28,  -22,  -43,   -5,  -32,  -20,  -41,
         ],
         //King
         [
-            -0.74, -0.35, -0.18, -0.18, -0.11, 0.15, 0.04, -0.17,
-            -0.12, 0.17, 0.14, 0.17, 0.17, 0.38, 0.23, 0.11,
-            0.10, 0.17, 0.23, 0.15, 0.20, 0.45, 0.44, 0.13,
-            -0.08, 0.22, 0.24, 0.27, 0.26, 0.33, 0.26, 0.03,
-            -0.18, -0.04, 0.21, 0.24, 0.27, 0.23, 0.09, -0.11,
-            -0.19, -0.03, 0.11, 0.21, 0.23, 0.16, 0.07, -0.09,
-            -0.27, -0.11, 0.04, 0.13, 0.14, 0.04, -0.05, -0.17,
-            -0.53, -0.34, -0.21, -0.11, -0.28, -0.14, -0.24, -0.43,
+             -74,  -35,  -18,  -18,  -11,   15,    4,  -17,
+             -12,   17,   14,   17,   17,   38,   23,   11,
+              10,   17,   23,   15,   20,   45,   44,   13,
+              -8,   22,   24,   27,   26,   33,   26,    3,
+             -18,   -4,   21,   24,   27,   23,    9,  -11,
+             -19,   -3,   11,   21,   23,   16,    7,   -9,
+             -27,  -11,    4,   13,   14,    4,   -5,  -17,
+             -53,  -34,  -21,  -11,  -28,  -14,  -24,  -43,
         ],
     ];
 }
+
+//FILE MASKS
+pub(crate) mod file_masks {
+    /// Every square of the a-file, the other files are this shifted by their index
+    pub(crate) const FILE_A: u64 = 0x0101_0101_0101_0101;
+    /// Every square of each file, from the a-file to the h-file
+    pub(crate) const FILES: [u64; 8] = [
+        FILE_A,
+        FILE_A << 1,
+        FILE_A << 2,
+        FILE_A << 3,
+        FILE_A << 4,
+        FILE_A << 5,
+        FILE_A << 6,
+        FILE_A << 7,
+    ];
+}
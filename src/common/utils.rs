@@ -1,9 +1,14 @@
-use std::ops::{AddAssign, Div};
+use std::ops::{AddAssign, Deref, DerefMut, Div};
 use tokio::time::{Duration, Instant};
 
-use chess::{Board, ChessMove, Color, Game, MoveGen, Piece};
+use chess::{BitBoard, Board, ChessMove, Color, File, Game, MoveGen, Piece, Square};
 use rand::Rng;
 
+use crate::common::constants::file_masks::FILES;
+use crate::common::constants::{
+    MAX_PHASE, NUMBER_OF_MODULES, PHASE_WEIGHTS, TIMING_SAMPLE_INTERVAL,
+};
+
 pub(crate) fn random_starting_position(num_random_moves: u32) -> Game {
     let mut game = Game::new();
     for _ in 0..num_random_moves {
@@ -35,6 +40,118 @@ pub(crate) fn material_each_side(board: &Board) -> (u32, u32) {
     output
 }
 
+/// The squares of the files on either side of `file`, 0 being the a-file
+pub(crate) fn adjacent_files(file: usize) -> u64 {
+    let left = if file > 0 { FILES[file - 1] } else { 0 };
+    let right = if file < 7 { FILES[file + 1] } else { 0 };
+    left | right
+}
+
+/// Number of king moves between the squares
+pub(crate) fn distance(from: Square, to: Square) -> i32 {
+    let ranks = from
+        .get_rank()
+        .to_index()
+        .abs_diff(to.get_rank().to_index());
+    let files = from
+        .get_file()
+        .to_index()
+        .abs_diff(to.get_file().to_index());
+    ranks.max(files) as i32
+}
+
+/// The rank of `square` counted from the back rank of `color`
+pub(crate) fn relative_rank(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.get_rank().to_index(),
+        Color::Black => 7 - square.get_rank().to_index(),
+    }
+}
+
+/// Calls `change` for every piece that `chess_move` on `board` removes from or adds to a
+/// square, with `added` telling which. For accumulators that are updated move by move.
+pub(crate) fn for_each_piece_change(
+    board: &Board,
+    chess_move: ChessMove,
+    mut change: impl FnMut(Piece, Color, Square, bool),
+) {
+    let color = board.side_to_move();
+    let (source, dest) = (chess_move.get_source(), chess_move.get_dest());
+    let Some(piece) = board.piece_on(source) else {
+        return;
+    };
+    change(piece, color, source, false);
+    change(
+        chess_move.get_promotion().unwrap_or(piece),
+        color,
+        dest,
+        true,
+    );
+
+    if let Some(captured) = board.piece_on(dest) {
+        change(captured, !color, dest, false);
+    } else if piece == Piece::Pawn && source.get_file() != dest.get_file() {
+        // En passant, the captured pawn is next to the source square
+        let captured = Square::make_square(source.get_rank(), dest.get_file());
+        change(Piece::Pawn, !color, captured, false);
+    }
+
+    // Castling is a king move of two squares, the rook jumps over the king
+    if piece == Piece::King
+        && source
+            .get_file()
+            .to_index()
+            .abs_diff(dest.get_file().to_index())
+            == 2
+    {
+        let (rook_source, rook_dest) = if dest.get_file() == File::G {
+            (File::H, File::F)
+        } else {
+            (File::A, File::D)
+        };
+        let rank = source.get_rank();
+        change(
+            Piece::Rook,
+            color,
+            Square::make_square(rank, rook_source),
+            false,
+        );
+        change(
+            Piece::Rook,
+            color,
+            Square::make_square(rank, rook_dest),
+            true,
+        );
+    }
+}
+
+/// How far from the endgame `board` is, from `MAX_PHASE` at the start of the game down to 0 when
+/// only kings and pawns are left
+pub(crate) fn game_phase(board: &Board) -> i32 {
+    PHASE_WEIGHTS
+        .iter()
+        .map(|&(piece, weight)| board.pieces(piece).popcnt() as i32 * weight)
+        .sum::<i32>()
+        .min(MAX_PHASE)
+}
+
+/// Blends a middlegame and an endgame score by `phase`, see `game_phase`
+pub(crate) fn taper((mg, eg): (i32, i32), phase: i32) -> i32 {
+    let phase = phase.clamp(0, MAX_PHASE);
+    (phase * mg + (MAX_PHASE - phase) * eg) / MAX_PHASE
+}
+
+/// Whether the move between two positions, given by their pawns and number of pieces, was a
+/// capture or pawn move. Positions before such a move can never be reached again.
+pub(crate) fn is_irreversible(
+    pawns_before: BitBoard,
+    pieces_before: u32,
+    pawns_after: BitBoard,
+    pieces_after: u32,
+) -> bool {
+    pawns_before != pawns_after || pieces_before != pieces_after
+}
+
 pub(crate) fn piece_value(piece: Piece) -> u32 {
     match piece {
         Piece::Pawn => 1,
@@ -64,6 +181,14 @@ pub(crate) fn to_pgn(game: &Game) -> String {
     output
 }
 
+/// Moves separated by spaces, without move numbers
+pub(crate) fn line_to_string(line: &[ChessMove]) -> String {
+    line.iter()
+        .map(ChessMove::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 /// Pushes the debug string representation into this vector. Used for printing debug information
 macro_rules! vector_push_debug {
     ($vec:expr, $var:expr $(,)?) => {
@@ -76,34 +201,164 @@ macro_rules! vector_push_debug {
 
 pub(crate) use vector_push_debug;
 
+/// Asserts a search invariant when the `search-assertions` feature is enabled. The condition is
+/// still type checked without the feature but compiled away.
+macro_rules! search_assert {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "search-assertions") {
+            assert!($($arg)+);
+        }
+    };
+}
+
+pub(crate) use search_assert;
+
+/// One value per module, indexed by the bit of the module. Arrays only derive Default up to 32
+/// elements, which the modules have outgrown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PerModule<T>(pub(crate) [T; NUMBER_OF_MODULES]);
+
+impl<T: Default + Copy> Default for PerModule<T> {
+    fn default() -> Self {
+        PerModule([T::default(); NUMBER_OF_MODULES])
+    }
+}
+
+impl<T> Deref for PerModule<T> {
+    type Target = [T; NUMBER_OF_MODULES];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for PerModule<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub(crate) struct Stats {
     pub(crate) alpha_beta_breaks: u32,
     pub(crate) depth: u32,
+    /// Deepest ply from the root that the search reached, extensions and quiescence included
+    pub(crate) seldepth: u32,
     pub(crate) max_depth: u32,
+    /// Searches ended by the max depth of the algorithm rather than by the deadline
+    pub(crate) max_depth_cutoffs: u32,
+    /// Searches stopped before the deadline because the best move was stable
+    pub(crate) early_terminations: u32,
+    /// Searches that didn't start another depth as it was predicted not to finish in time
+    pub(crate) skipped_iterations: u32,
+    /// Searches given less time as the best move was a recapture or far better than the rest
+    pub(crate) easy_moves: u32,
     pub(crate) leaves_visited: u32,
+    /// Boards evaluated, in quiescence search too, apart from the ones decided by the rules
+    pub(crate) evals: u32,
+    /// Evals cut short for being far outside of the window, see `lazy_eval_margin`
+    pub(crate) lazy_evals: u32,
     pub(crate) nodes_visited: u32,
+    /// Nodes searched past the nominal depth, leaves included
+    pub(crate) quiescence_nodes: u32,
+    /// Captures skipped in quiescence search for losing material
+    pub(crate) quiescence_captures_pruned: u32,
+    /// Moves that LATE_MOVE_PRUNING would prune, the ones searched to verify it included
+    pub(crate) late_move_prunes: u32,
+    /// Moves searched again with the full window after their null window search
+    pub(crate) principal_variation_researches: u32,
+    /// Searches of the root repeated with a wider aspiration window after failing high
+    pub(crate) root_fail_high_researches: u32,
+    /// Searches of the root repeated with a wider aspiration window after failing low
+    pub(crate) root_fail_low_researches: u32,
+    /// Expected cut-nodes pruned because enough moves failed high at reduced depth, counting
+    /// the ones searched in full anyway to verify the cutoff
+    pub(crate) multi_cut_prunes: u32,
+    /// Nodes pruned because a shallow search got past the raised bound, counting the ones
+    /// searched in full anyway to verify the cutoff
+    pub(crate) probcut_prunes: u32,
+    /// Nodes where SKIP_BAD_MOVES would have skipped moves, but they were searched anyway
+    pub(crate) skipped_tail_searches: u32,
+    /// Cutoffs by the transposition table move, before the other moves were generated
+    pub(crate) transposition_move_cutoffs: u32,
     pub(crate) num_plies: u32,
     pub(crate) time_spent: Duration,
     pub(crate) progress_on_next_layer: f32,
     pub(crate) transposition_table_entries: u32,
     pub(crate) transposition_table_accesses: u32,
+    /// Lookups in the transposition table, whether they found an entry or not
+    pub(crate) transposition_table_probes: u32,
+    /// Moves whose value was taken from a transposition entry searched at least as deep as the
+    /// move would have been
+    pub(crate) transposition_cutoff_hits: u32,
+    /// Transposition entries found for a node that only ordered its moves by their best move
+    pub(crate) transposition_ordering_hits: u32,
+    /// Static evals taken from transposition entries instead of evaluating the board again
+    pub(crate) stored_static_eval_reuses: u32,
+    /// Stored best moves that were illegal on the board they were probed for, as the board
+    /// shared its hash with the board of the entry
+    pub(crate) transposition_collisions: u32,
+    /// Fraction of the transposition table in use after the search
+    pub(crate) transposition_table_occupancy: f32,
+    /// Sampled permille of the transposition table filled by the search, see
+    /// `TranspositionTable::hashfull`
+    pub(crate) hashfull: u32,
+    /// Estimated from a sample of the accesses
     pub(crate) time_for_transposition_access: Duration,
+    /// Estimated time spent in each eval module, indexed by the bit of the module. Only a sample
+    /// of evals are timed and the result is scaled up.
+    pub(crate) eval_module_time: PerModule<Duration>,
+    /// Verification re-searches done by each pruning/reduction module, indexed by the bit of the
+    /// module
+    pub(crate) verification_searches: PerModule<u32>,
+    /// How many of the verification re-searches that showed the pruning/reduction was wrong
+    pub(crate) verification_fails: PerModule<u32>,
 }
 
 impl AddAssign for Stats {
     fn add_assign(&mut self, rhs: Self) {
         self.nodes_visited += rhs.nodes_visited;
         self.depth += rhs.depth;
+        self.seldepth += rhs.seldepth;
         self.max_depth += rhs.max_depth;
+        self.max_depth_cutoffs += rhs.max_depth_cutoffs;
+        self.early_terminations += rhs.early_terminations;
+        self.skipped_iterations += rhs.skipped_iterations;
+        self.easy_moves += rhs.easy_moves;
         self.leaves_visited += rhs.leaves_visited;
+        self.evals += rhs.evals;
+        self.lazy_evals += rhs.lazy_evals;
+        self.quiescence_nodes += rhs.quiescence_nodes;
+        self.quiescence_captures_pruned += rhs.quiescence_captures_pruned;
+        self.late_move_prunes += rhs.late_move_prunes;
+        self.principal_variation_researches += rhs.principal_variation_researches;
+        self.root_fail_high_researches += rhs.root_fail_high_researches;
+        self.root_fail_low_researches += rhs.root_fail_low_researches;
+        self.multi_cut_prunes += rhs.multi_cut_prunes;
+        self.probcut_prunes += rhs.probcut_prunes;
+        self.skipped_tail_searches += rhs.skipped_tail_searches;
+        self.transposition_move_cutoffs += rhs.transposition_move_cutoffs;
         self.alpha_beta_breaks += rhs.alpha_beta_breaks;
         self.num_plies += rhs.num_plies;
         self.time_spent += rhs.time_spent;
         self.progress_on_next_layer += rhs.progress_on_next_layer;
         self.transposition_table_entries += rhs.transposition_table_entries;
         self.transposition_table_accesses += rhs.transposition_table_accesses;
+        self.transposition_table_probes += rhs.transposition_table_probes;
+        self.transposition_cutoff_hits += rhs.transposition_cutoff_hits;
+        self.transposition_ordering_hits += rhs.transposition_ordering_hits;
+        self.stored_static_eval_reuses += rhs.stored_static_eval_reuses;
+        self.transposition_collisions += rhs.transposition_collisions;
+        self.transposition_table_occupancy += rhs.transposition_table_occupancy;
+        self.hashfull += rhs.hashfull;
         self.time_for_transposition_access += rhs.time_for_transposition_access;
+        for (time, rhs_time) in self.eval_module_time.iter_mut().zip(*rhs.eval_module_time) {
+            *time += rhs_time;
+        }
+        for i in 0..NUMBER_OF_MODULES {
+            self.verification_searches[i] += rhs.verification_searches[i];
+            self.verification_fails[i] += rhs.verification_fails[i];
+        }
     }
 }
 
@@ -114,15 +369,46 @@ impl Div<u32> for Stats {
         StatsAverage {
             alpha_beta_breaks: self.alpha_beta_breaks as f32 / rhs as f32,
             depth: self.depth as f32 / rhs as f32,
+            seldepth: self.seldepth as f32 / rhs as f32,
             max_depth: self.max_depth as f32 / rhs as f32,
+            max_depth_cutoffs: self.max_depth_cutoffs as f32 / rhs as f32,
+            early_terminations: self.early_terminations as f32 / rhs as f32,
+            skipped_iterations: self.skipped_iterations as f32 / rhs as f32,
+            easy_moves: self.easy_moves as f32 / rhs as f32,
             leaves_visited: self.leaves_visited as f32 / rhs as f32,
+            evals: self.evals as f32 / rhs as f32,
+            lazy_evals: self.lazy_evals as f32 / rhs as f32,
             nodes_visited: self.nodes_visited as f32 / rhs as f32,
+            quiescence_nodes: self.quiescence_nodes as f32 / rhs as f32,
+            quiescence_captures_pruned: self.quiescence_captures_pruned as f32 / rhs as f32,
+            late_move_prunes: self.late_move_prunes as f32 / rhs as f32,
+            principal_variation_researches: self.principal_variation_researches as f32 / rhs as f32,
+            root_fail_high_researches: self.root_fail_high_researches as f32 / rhs as f32,
+            root_fail_low_researches: self.root_fail_low_researches as f32 / rhs as f32,
+            multi_cut_prunes: self.multi_cut_prunes as f32 / rhs as f32,
+            probcut_prunes: self.probcut_prunes as f32 / rhs as f32,
+            skipped_tail_searches: self.skipped_tail_searches as f32 / rhs as f32,
+            transposition_move_cutoffs: self.transposition_move_cutoffs as f32 / rhs as f32,
             num_plies: self.num_plies as f32 / rhs as f32,
             time_spent: self.time_spent / rhs,
             progress_on_next_layer: self.progress_on_next_layer / rhs as f32,
             transposition_table_entries: self.transposition_table_entries as f32 / rhs as f32,
             transposition_table_accesses: self.transposition_table_accesses as f32 / rhs as f32,
+            transposition_table_probes: self.transposition_table_probes as f32 / rhs as f32,
+            transposition_cutoff_hits: self.transposition_cutoff_hits as f32 / rhs as f32,
+            transposition_ordering_hits: self.transposition_ordering_hits as f32 / rhs as f32,
+            stored_static_eval_reuses: self.stored_static_eval_reuses as f32 / rhs as f32,
+            transposition_collisions: self.transposition_collisions as f32 / rhs as f32,
+            transposition_table_occupancy: self.transposition_table_occupancy / rhs as f32,
+            hashfull: self.hashfull as f32 / rhs as f32,
             time_for_transposition_access: self.time_for_transposition_access / rhs,
+            eval_module_time: PerModule(self.eval_module_time.map(|time| time / rhs)),
+            verification_searches: PerModule(
+                self.verification_searches.map(|n| n as f32 / rhs as f32),
+            ),
+            verification_fail_rate: PerModule(std::array::from_fn(|i| {
+                self.verification_fails[i] as f32 / self.verification_searches[i].max(1) as f32
+            })),
         }
     }
 }
@@ -132,15 +418,43 @@ impl Div<u32> for Stats {
 pub(crate) struct StatsAverage {
     pub(crate) alpha_beta_breaks: f32,
     pub(crate) depth: f32,
+    pub(crate) seldepth: f32,
     pub(crate) max_depth: f32,
+    pub(crate) max_depth_cutoffs: f32,
+    pub(crate) early_terminations: f32,
+    pub(crate) skipped_iterations: f32,
+    pub(crate) easy_moves: f32,
     pub(crate) leaves_visited: f32,
+    pub(crate) evals: f32,
+    pub(crate) lazy_evals: f32,
     pub(crate) nodes_visited: f32,
+    pub(crate) quiescence_nodes: f32,
+    pub(crate) quiescence_captures_pruned: f32,
+    pub(crate) late_move_prunes: f32,
+    pub(crate) principal_variation_researches: f32,
+    pub(crate) root_fail_high_researches: f32,
+    pub(crate) root_fail_low_researches: f32,
+    pub(crate) multi_cut_prunes: f32,
+    pub(crate) probcut_prunes: f32,
+    pub(crate) skipped_tail_searches: f32,
+    pub(crate) transposition_move_cutoffs: f32,
     pub(crate) num_plies: f32,
     pub(crate) time_spent: Duration,
     pub(crate) progress_on_next_layer: f32,
     pub(crate) transposition_table_entries: f32,
     pub(crate) transposition_table_accesses: f32,
+    pub(crate) transposition_table_probes: f32,
+    pub(crate) transposition_cutoff_hits: f32,
+    pub(crate) transposition_ordering_hits: f32,
+    pub(crate) stored_static_eval_reuses: f32,
+    pub(crate) transposition_collisions: f32,
+    pub(crate) transposition_table_occupancy: f32,
+    pub(crate) hashfull: f32,
     pub(crate) time_for_transposition_access: Duration,
+    pub(crate) eval_module_time: PerModule<Duration>,
+    pub(crate) verification_searches: PerModule<f32>,
+    /// Fraction of the verification re-searches that failed, not averaged per ply
+    pub(crate) verification_fail_rate: PerModule<f32>,
 }
 
 pub(crate) fn passed_deadline(deadline: Instant) -> bool {
@@ -148,6 +462,25 @@ pub(crate) fn passed_deadline(deadline: Instant) -> bool {
     !time_since_deadline.is_zero()
 }
 
-pub(crate) fn module_enabled(modules: u32, module_to_test: u32) -> bool {
+/// Adds the time since `start` to the eval module's total. `start` is `None` when this eval is
+/// not part of the timed sample.
+pub(crate) fn record_module_time(stats: &mut Stats, module: u64, start: Option<Instant>) {
+    if let Some(start) = start {
+        stats.eval_module_time[module.trailing_zeros() as usize] +=
+            (Instant::now() - start) * TIMING_SAMPLE_INTERVAL;
+    }
+}
+
+/// Counts a verification re-search of a position that `module` pruned or reduced. `failed` is
+/// true when the re-search disagreed with the reduced search.
+pub(crate) fn record_verification(stats: &mut Stats, module: u64, failed: bool) {
+    let index = module.trailing_zeros() as usize;
+    stats.verification_searches[index] += 1;
+    if failed {
+        stats.verification_fails[index] += 1;
+    }
+}
+
+pub(crate) fn module_enabled(modules: u64, module_to_test: u64) -> bool {
     modules & module_to_test != 0
 }
\ No newline at end of file
@@ -1,6 +1,78 @@
+use std::hash::Hasher;
+use std::io::{Error, ErrorKind};
 use std::{fs::OpenOptions, io::prelude::*};
 
-use crate::common::constants::NUMBER_OF_MODULES;
+use crate::common::constants::{EVAL_VERSION, NUMBER_OF_MODULES};
+
+/// First line of every stored file containing evaluations. The modules and `weights`, the hash
+/// of the eval weights, PSQT tables and network, are part of it since they decide the evals.
+pub(crate) fn eval_version_header(modules: u64, weights: u64) -> String {
+    format!(
+        "eval-version {} modules {} weights {:016x}",
+        EVAL_VERSION, modules, weights
+    )
+}
+
+/// Refuses stored data that was produced by another eval version, module set or weights
+pub(crate) fn check_eval_version(header: &str, modules: u64, weights: u64) -> std::io::Result<()> {
+    let expected = eval_version_header(modules, weights);
+    if header.trim_end() != expected {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Stored data has header \"{}\" but the current eval is \"{}\"",
+                header.trim_end(),
+                expected
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// First line of stored eval weights and PSQT tables. They are tuned for the terms of one eval
+/// version. It starts with `#` so that the weights files read it as a comment.
+pub(crate) fn weights_version_header() -> String {
+    format!("# eval-version {}", EVAL_VERSION)
+}
+
+/// Refuses weights that were written for another eval version, given the first line of the file
+pub(crate) fn check_weights_version(header: &str) -> std::io::Result<()> {
+    let expected = weights_version_header();
+    if header.trim_end() != expected {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Weights have header \"{}\" but the current eval is \"{}\"",
+                header.trim_end(),
+                expected
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// FNV-1a. Unlike the hashers of std it gives the same hash in every build, so the hash can be
+/// stored.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
 
 pub(crate) fn write_result(buf: &[u8], file: &str) -> std::io::Result<()> {
     let mut file = OpenOptions::new()
@@ -12,11 +84,11 @@ pub(crate) fn write_result(buf: &[u8], file: &str) -> std::io::Result<()> {
     Ok(())
 }
 
-pub(crate) fn modules_to_string(modules: u32) -> String {
+pub(crate) fn modules_to_string(modules: u64) -> String {
     let mut start: bool = true;
     let mut output: String = "".to_string();
-    //For loop ranges is all available modules index. +1 because we need inclusive.
-    for i in 0..NUMBER_OF_MODULES + 1 {
+    // Every module index
+    for i in 0..NUMBER_OF_MODULES {
         if (modules & (1 << i)).count_ones() == 1 {
             let module_string = match i {
                 0 => "ANALYZE",
@@ -29,6 +101,38 @@ pub(crate) fn modules_to_string(modules: u32) -> String {
                 7 => "PAWN_STRUCTURE",
                 8 => "TAPERED_EVERY_PESTO_PSQT",
                 9 => "TAPERED_INCREMENTAL_PESTO_PSQT",
+                10 => "QUIESCENCE",
+                11 => "HISTORY_HEURISTIC",
+                12 => "LATE_MOVE_PRUNING",
+                13 => "PRINCIPAL_VARIATION_SEARCH",
+                14 => "ASPIRATION_WINDOWS",
+                15 => "INTERNAL_ITERATIVE_DEEPENING",
+                16 => "MULTI_CUT",
+                17 => "PROBCUT",
+                18 => "EARLY_TERMINATION",
+                19 => "ITERATION_PREDICTION",
+                20 => "ROOT_SPLITTING",
+                21 => "KILLER_MOVES",
+                22 => "EASY_MOVE",
+                23 => "ROOK_FILES",
+                24 => "PASSED_PAWNS",
+                25 => "KING_TROPISM",
+                26 => "TEMPO",
+                27 => "ROOK_COORDINATION",
+                28 => "PAWN_SHIELD",
+                29 => "TRAPPED_PIECES",
+                30 => "THREATS",
+                31 => "ENDGAMES",
+                32 => "NNUE",
+                33 => "KING_ACTIVITY",
+                34 => "BISHOP_COLOR",
+                35 => "ROOK_ON_SEVENTH",
+                36 => "EARLY_QUEEN",
+                37 => "KING_DANGER",
+                38 => "PAWN_MAJORITY",
+                39 => "MATERIAL_IMBALANCE",
+                40 => "STALEMATE_TRAP",
+                41 => "HALFMOVE_SCALING",
                 _ => "INVALID MODULE DETECTED",
             };
             if !start {
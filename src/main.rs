@@ -2,19 +2,34 @@ use std::fs::remove_file;
 use std::mem;
 use std::time::Duration;
 
+use crate::algorithms::eval_profile::{EvalProfile, ALL_EVAL_PROFILES};
 use crate::algorithms::the_algorithm::Algorithm;
 #[allow(unused_imports)]
 use crate::common::constants::{
     modules::{
-        ALPHA_BETA, ANALYZE, NAIVE_PSQT, PAWN_STRUCTURE, SEARCH_EXTENSIONS, SKIP_BAD_MOVES,
-        SQUARE_CONTROL_METRIC, TAPERED_EVERY_PESTO_PSQT, TAPERED_INCREMENTAL_PESTO_PSQT,
-        TRANSPOSITION_TABLE,
+        ALPHA_BETA, ANALYZE, ASPIRATION_WINDOWS, BISHOP_COLOR, EARLY_QUEEN, EARLY_TERMINATION,
+        EASY_MOVE, ENDGAMES, HALFMOVE_SCALING, HISTORY_HEURISTIC, INTERNAL_ITERATIVE_DEEPENING,
+        ITERATION_PREDICTION, KILLER_MOVES, KING_ACTIVITY, KING_DANGER, KING_TROPISM,
+        LATE_MOVE_PRUNING, MATERIAL_IMBALANCE, MULTI_CUT, NAIVE_PSQT, NNUE, PASSED_PAWNS,
+        PAWN_MAJORITY, PAWN_SHIELD, PAWN_STRUCTURE, PRINCIPAL_VARIATION_SEARCH, PROBCUT,
+        QUIESCENCE, ROOK_COORDINATION, ROOK_FILES, ROOK_ON_SEVENTH, ROOT_SPLITTING,
+        SEARCH_EXTENSIONS, SKIP_BAD_MOVES, SQUARE_CONTROL_METRIC, STALEMATE_TRAP,
+        TAPERED_EVERY_PESTO_PSQT, TAPERED_INCREMENTAL_PESTO_PSQT, TEMPO, THREATS,
+        TRANSPOSITION_TABLE, TRAPPED_PIECES,
     },
     NUMBER_OF_MODULES,
 };
 use crate::io::write_result;
+use crate::modules::history_heuristic::HistoryDecay;
+use crate::modules::transposition_table::{ClearPolicy, ALL_REPLACEMENT_SCHEMES};
 
-use self::pitter::logic::{Competition, CompetitionResults};
+use self::pitter::logic::{
+    Competition, CompetitionResults, TimeControl, Verbosity, RANDOM_OPENING_PLIES,
+};
+use self::pitter::psqt_fit::{self, PsqtFitSettings};
+use self::pitter::spsa::{self, SpsaParameter, SpsaSettings};
+use self::pitter::summary::{self, ConfigurationRecord};
+use self::pitter::{benchmark, pgn_archive, symmetry, win_probability};
 
 mod algorithms;
 mod common;
@@ -22,14 +37,233 @@ mod io;
 mod modules;
 mod pitter;
 
+//How much competitions print, unless overridden with --verbosity quiet|results|pairs|games.
+const VERBOSITY: Verbosity = Verbosity::ResultsOnly;
 //If we should test all possible pairs of combinations.
 const TEST_ALL_PAIRS: bool = false;
+//If we should measure how pairs of modules interact when added on top of INTERACTION_BASELINE.
+const TEST_MODULE_INTERACTIONS: bool = false;
+const INTERACTION_BASELINE: u64 = ALPHA_BETA;
+//Transposition table file that algo1 starts from and that its table is saved to after the
+//competition, to keep building on the same table across runs.
+const TRANSPOSITION_TABLE_FILE: Option<&str> = None;
+//Megabytes of the transposition table of each algorithm. Tables cleared before every move make
+//larger tables cost time at short time controls.
+const TRANSPOSITION_TABLE_MB: usize = 1;
+//When the transposition tables of algo1 and algo2 are cleared, e.g. ClearPolicy::EveryGamePair
+//against ClearPolicy::EveryMove to see if keeping entries for the second game of a pair helps.
+//Loading TRANSPOSITION_TABLE_FILE keeps algo1's table for good.
+const CLEAR_POLICY1: ClearPolicy = ClearPolicy::EveryMove;
+const CLEAR_POLICY2: ClearPolicy = ClearPolicy::EveryMove;
+//What happens to the history tables of algo1 and algo2 between moves, e.g. HistoryDecay::Keep
+//against HistoryDecay::Halve to see if old history still helps ordering moves.
+const HISTORY_DECAY1: HistoryDecay = HistoryDecay::Halve;
+const HISTORY_DECAY2: HistoryDecay = HistoryDecay::Halve;
+//Play games on a clock, e.g. Some(TimeControl { base: Duration::from_millis(100), increment:
+//Duration::from_millis(1) }), instead of with a fixed time per move.
+const TIME_CONTROL: Option<TimeControl> = None;
+//Search every move of algo1 and algo2 to exactly this depth, ignoring the clock, e.g. Some(4).
+//Unlike the time per move this gives the same games on any hardware.
+const FIXED_DEPTH1: Option<u32> = None;
+const FIXED_DEPTH2: Option<u32> = None;
+//Eval weights files of algo1 and algo2, as written by EvalParams' Display. Weights that a file
+//leaves out keep their defaults, so two files can pit single weights against each other.
+const EVAL_PARAMS_FILE1: Option<&str> = None;
+const EVAL_PARAMS_FILE2: Option<&str> = None;
+//PSQT files of algo1 and algo2, as written by PsqtTables' Display. Tables that a file leaves out
+//keep the compiled-in ones.
+const PSQT_FILE1: Option<&str> = None;
+const PSQT_FILE2: Option<&str> = None;
+//Weights file of the network that both algorithms evaluate with when NNUE is enabled.
+const NNUE_FILE: Option<&str> = None;
+//Every competition is appended here so that the best configurations can be summarized. Files
+//written by another eval version have to be moved away first.
+const RESULTS_FILE: &str = "./results.txt";
+//Only print the best configurations found in RESULTS_FILE instead of running a competition.
+const SUMMARIZE_RESULTS: bool = false;
+//Every played game is appended here.
+const PGN_ARCHIVE_FILE: Option<&str> = None;
+//Syzygy tablebases that games cut off at the ply cap are adjudicated with, before falling back
+//to material. Probing isn't implemented yet, so setting this has no effect.
+const TABLEBASE_PATH: Option<&str> = None;
+//Only write the positions where these modules lost quickly in PGN_ARCHIVE_FILE to SHORT_LOSSES_FILE.
+const MINE_SHORT_LOSSES_FOR: Option<u64> = None;
+const SHORT_LOSSES_FILE: &str = "./short_losses.epd";
+//Only check that collecting stats takes a negligible part of the search time.
+const BENCHMARK_STATS: bool = false;
+//Only measure the transposition table hit rate of each replacement scheme, in games played with
+//the table kept between moves.
+const BENCHMARK_TT_REPLACEMENT: bool = false;
+//Only check that the eval of SYMMETRY_POSITIONS random positions is unchanged by flipping the
+//colors or mirroring the files, with every combination of eval modules.
+const CHECK_EVAL_SYMMETRY: bool = false;
+const SYMMETRY_POSITIONS: u32 = 20;
+//Fit the tapered PSQT of PSQT_FIT_MODULES to self-play games and write it to PSQT_FIT_FILE,
+//where PSQT_FILE1 or PSQT_FILE2 can load it, instead of running a competition.
+const FIT_PSQT: bool = false;
+const PSQT_FIT_MODULES: u64 = ALPHA_BETA | TAPERED_INCREMENTAL_PESTO_PSQT;
+const PSQT_FIT_FILE: &str = "./fitted_psqt.txt";
+//Find the win_probability_scale of CALIBRATION_MODULES from self-play games instead of running a
+//competition. Calibrate each module set whose evals are compared, e.g. NNUE and handcrafted.
+const CALIBRATE_WIN_PROBABILITY: bool = false;
+const CALIBRATION_MODULES: u64 = ALPHA_BETA | TAPERED_INCREMENTAL_PESTO_PSQT;
+//Play every pair of ALL_EVAL_PROFILES against each other on top of PROFILE_SEARCH_MODULES,
+//instead of running a competition. Profiles read their files from ./profiles/ and are skipped
+//while those are missing.
+const COMPARE_EVAL_PROFILES: bool = false;
+const PROFILE_SEARCH_MODULES: u64 = ALPHA_BETA | QUIESCENCE | TRANSPOSITION_TABLE;
+//Tune SPSA_PARAMETERS of SPSA_MODULES by self-play instead of running a competition.
+const SPSA_TUNE: bool = false;
+const SPSA_MODULES: u64 = ALPHA_BETA | PROBCUT | SKIP_BAD_MOVES | SEARCH_EXTENSIONS;
+const SPSA_PARAMETERS: [SpsaParameter; 4] = [
+    SpsaParameter {
+        name: "probcut_margin",
+        step: 20.,
+        min: 0.,
+        max: 500.,
+    },
+    SpsaParameter {
+        name: "skip_fraction",
+        step: 0.05,
+        min: 0.,
+        max: 1.,
+    },
+    SpsaParameter {
+        name: "max_extensions_middlegame",
+        step: 1.,
+        min: 0.,
+        max: 10.,
+    },
+    SpsaParameter {
+        name: "max_extensions_endgame",
+        step: 1.,
+        min: 0.,
+        max: 10.,
+    },
+];
+
+/// Reads the verbosity from the command line, falling back to VERBOSITY
+fn verbosity_from_args() -> Verbosity {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|arg| arg == "--verbosity") {
+        Some(i) => args
+            .get(i + 1)
+            .map(|level| {
+                level
+                    .parse()
+                    .unwrap_or_else(|error: String| panic!("{}", error))
+            })
+            .expect("--verbosity needs a level"),
+        None => VERBOSITY,
+    }
+}
 
 #[tokio::main]
 async fn main() {
+    let verbosity = verbosity_from_args();
+    if BENCHMARK_STATS {
+        let overhead = benchmark::stats_overhead(ALPHA_BETA, 4, 200);
+        println!(
+            "Stats collection takes {:.3}% of search time",
+            overhead * 100.
+        );
+        assert!(
+            overhead < 0.01,
+            "Stats collection takes over 1% of search time"
+        );
+        return;
+    }
+    if BENCHMARK_TT_REPLACEMENT {
+        for scheme in ALL_REPLACEMENT_SCHEMES {
+            let hit_rate = benchmark::transposition_hit_rate(
+                ALPHA_BETA | TRANSPOSITION_TABLE,
+                Duration::from_micros(2000),
+                scheme,
+                20,
+                40,
+            );
+            println!(
+                "{:?} replacement: {:.2}% of probes hit",
+                scheme,
+                hit_rate * 100.
+            );
+        }
+        return;
+    }
+    if CHECK_EVAL_SYMMETRY {
+        let failures = symmetry::check_eval_symmetry(SYMMETRY_POSITIONS);
+        for failure in &failures {
+            println!("{}", failure);
+        }
+        assert!(failures.is_empty(), "The eval is not symmetric");
+        return;
+    }
+    if SUMMARIZE_RESULTS {
+        let records = summary::read_records(RESULTS_FILE).expect("Results file is readable");
+        summary::print_recommendations(&records);
+        return;
+    }
+    if let (Some(modules), Some(path)) = (MINE_SHORT_LOSSES_FOR, PGN_ARCHIVE_FILE) {
+        let games = pgn_archive::read_archive(path).expect("PGN archive is readable");
+        let epd = pgn_archive::mine_short_losses(&games, modules, RANDOM_OPENING_PLIES as usize);
+        remove_file(SHORT_LOSSES_FILE).unwrap_or_default();
+        let _ = write_result(epd.as_bytes(), SHORT_LOSSES_FILE);
+        return;
+    }
+
+    if FIT_PSQT {
+        let base = Algorithm::new(PSQT_FIT_MODULES, Duration::from_micros(2000));
+        let tables = psqt_fit::generate(&base, PsqtFitSettings::default(), verbosity);
+        remove_file(PSQT_FIT_FILE).unwrap_or_default();
+        let _ = write_result(tables.to_string().as_bytes(), PSQT_FIT_FILE);
+        return;
+    }
+
+    if CALIBRATE_WIN_PROBABILITY {
+        let base = Algorithm::new(CALIBRATION_MODULES, Duration::from_micros(2000));
+        let scale = win_probability::calibrate_scale(&base, 200, verbosity);
+        println!("win_probability_scale: {}", scale);
+        return;
+    }
+
+    if SPSA_TUNE {
+        let base = Algorithm::new(SPSA_MODULES, Duration::from_micros(2000));
+        let tuned = spsa::tune(&base, &SPSA_PARAMETERS, SpsaSettings::default(), verbosity)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+        for parameter in SPSA_PARAMETERS {
+            println!("{}: {:?}", parameter.name, tuned.parameter(parameter.name));
+        }
+        return;
+    }
+
+    if COMPARE_EVAL_PROFILES {
+        remove_file("./profiles.txt").unwrap_or_default();
+        compare_eval_profiles(
+            PROFILE_SEARCH_MODULES,
+            Duration::from_micros(2000),
+            400,
+            verbosity,
+        )
+        .await;
+        return;
+    }
+
+    if TEST_MODULE_INTERACTIONS {
+        remove_file("./interactions.txt").unwrap_or_default();
+        test_module_interactions(
+            INTERACTION_BASELINE,
+            Duration::from_micros(2000),
+            400,
+            verbosity,
+        )
+        .await;
+        return;
+    }
+
     remove_file("./output.txt").unwrap_or_default();
     if !TEST_ALL_PAIRS {
-        //ALPHA_BETA | ANALYZE | SEARCH_EXTENSIONS | SKIP_BAD_MOVES | SQUARE_CONTROL_METRIC | TRANSPOSITION_TABLE | NAIVE_PSQT | PAWN_STRUCTURE | TAPERED_EVERY_PESTO_PSQT | TAPERED_INCREMENTAL_PESTO_PSQT
+        //ALPHA_BETA | ANALYZE | SEARCH_EXTENSIONS | SKIP_BAD_MOVES | SQUARE_CONTROL_METRIC | TRANSPOSITION_TABLE | NAIVE_PSQT | PAWN_STRUCTURE | TAPERED_EVERY_PESTO_PSQT | TAPERED_INCREMENTAL_PESTO_PSQT | QUIESCENCE | HISTORY_HEURISTIC | LATE_MOVE_PRUNING | PRINCIPAL_VARIATION_SEARCH | ASPIRATION_WINDOWS | INTERNAL_ITERATIVE_DEEPENING | MULTI_CUT | PROBCUT | EARLY_TERMINATION | ITERATION_PREDICTION | ROOT_SPLITTING | KILLER_MOVES | EASY_MOVE | ROOK_FILES | PASSED_PAWNS | KING_TROPISM | TEMPO | ROOK_COORDINATION | PAWN_SHIELD | TRAPPED_PIECES | THREATS | ENDGAMES | NNUE | KING_ACTIVITY | BISHOP_COLOR | ROOK_ON_SEVENTH | EARLY_QUEEN | KING_DANGER | PAWN_MAJORITY | MATERIAL_IMBALANCE | STALEMATE_TRAP | HALFMOVE_SCALING
         //Put 0 for no modules.
         //Setup modules
         let modules1 = ALPHA_BETA | TAPERED_EVERY_PESTO_PSQT;
@@ -45,13 +279,18 @@ async fn main() {
             time_per_move1,
             time_per_move2,
             game_pairs,
+            verbosity,
         )
         .await;
     
-        println!("Algo 1: {}", io::modules_to_string(modules1));
-        println!("Algo 2: {}", io::modules_to_string(modules2));
-        println!("Game pairs: {}", game_pairs);
-        dbg!(result);
+        if verbosity >= Verbosity::ResultsOnly {
+            println!("Algo 1: {}", io::modules_to_string(modules1));
+            println!("Algo 2: {}", io::modules_to_string(modules2));
+            println!("Game pairs: {}", game_pairs);
+            dbg!(result);
+        }
+        let record = ConfigurationRecord::new(modules1, modules2, &result);
+        let _ = summary::write_record(record, RESULTS_FILE);
     } else {
         println!(
             "Running {} possibilites",
@@ -95,10 +334,13 @@ async fn main() {
                     time_per_move1,
                     time_per_move2,
                     game_pairs,
+                    verbosity,
                 )
                 .await;
 
                 dp[i][j] = Some(result);
+                let record = ConfigurationRecord::new(modules1, modules2, &result);
+                let _ = summary::write_record(record, RESULTS_FILE);
                 let output: String =
                     format!("{}\t", result.algo1_wins as i64 - result.algo2_wins as i64);
                 let buf = output.as_bytes();
@@ -109,17 +351,188 @@ async fn main() {
     }
 }
 
+/// Plays every module and every pair of modules added to `baseline` against the baseline itself.
+/// The interaction of a pair is the Elo it gains beyond the sum of what each module gains alone:
+/// positive means the modules help each other, negative that they get in each other's way.
+/// Writes the labeled matrix of interactions to ./interactions.txt.
+async fn test_module_interactions(
+    baseline: u64,
+    time_per_move: Duration,
+    game_pairs: u32,
+    verbosity: Verbosity,
+) {
+    let candidates: Vec<u64> = (0..NUMBER_OF_MODULES)
+        .map(|i| 1 << i)
+        .filter(|&module| module != ANALYZE && baseline & module == 0)
+        .collect();
+    println!(
+        "Running {} competitions",
+        candidates.len() * (candidates.len() + 1) / 2
+    );
+
+    let elo_gain = |modules: u64| async move {
+        let result = do_competition(
+            baseline | modules,
+            baseline,
+            time_per_move,
+            time_per_move,
+            game_pairs,
+            verbosity,
+        )
+        .await;
+        let record = ConfigurationRecord::new(baseline | modules, baseline, &result);
+        let _ = summary::write_record(record, RESULTS_FILE);
+        result.elo_difference()
+    };
+
+    let mut single_gain = Vec::new();
+    for &module in &candidates {
+        single_gain.push(elo_gain(module).await);
+    }
+
+    let mut interactions = vec![vec![0.; candidates.len()]; candidates.len()];
+    for i in 0..candidates.len() {
+        for j in (i + 1)..candidates.len() {
+            let pair_gain = elo_gain(candidates[i] | candidates[j]).await;
+            interactions[i][j] = pair_gain - single_gain[i] - single_gain[j];
+            interactions[j][i] = interactions[i][j];
+        }
+    }
+
+    let names: Vec<String> = candidates
+        .iter()
+        .map(|&module| io::modules_to_string(module))
+        .collect();
+    let mut output = format!(
+        "Interactions on top of {}\t{}\n",
+        io::modules_to_string(baseline),
+        names.join("\t")
+    );
+    for (name, row) in names.iter().zip(&interactions) {
+        let row: Vec<String> = row.iter().map(|elo| format!("{:.1}", elo)).collect();
+        output += &format!("{}\t{}\n", name, row.join("\t"));
+    }
+    print!("{}", output);
+    let _ = write_result(output.as_bytes(), "./interactions.txt");
+}
+
+/// Plays every eval profile against every other one, all with the search of `search_modules`.
+/// Writes the labeled matrix of the Elo of each row profile against each column profile to
+/// ./profiles.txt.
+async fn compare_eval_profiles(
+    search_modules: u64,
+    time_per_move: Duration,
+    game_pairs: u32,
+    verbosity: Verbosity,
+) {
+    // Profiles whose files haven't been made yet are left out of the comparison
+    let algorithms: Vec<(EvalProfile, Algorithm)> = ALL_EVAL_PROFILES
+        .into_iter()
+        .filter_map(|profile| {
+            match Algorithm::with_eval_profile(search_modules, time_per_move, profile) {
+                Ok(algorithm) => Some((profile, algorithm)),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    println!("Skipping eval profile {}: {}", profile, error);
+                    None
+                }
+                Err(error) => panic!("Could not load eval profile {}: {}", profile, error),
+            }
+        })
+        .collect();
+    let mut elo = vec![vec![0.; algorithms.len()]; algorithms.len()];
+    for i in 0..algorithms.len() {
+        for j in (i + 1)..algorithms.len() {
+            let ((profile1, algo1), (profile2, algo2)) = (&algorithms[i], &algorithms[j]);
+            let (modules1, modules2) = (algo1.modules, algo2.modules);
+            let mut competition = Competition::new(algo1.clone(), algo2.clone());
+            competition.pgn_archive = PGN_ARCHIVE_FILE.map(str::to_string);
+            competition.tablebase_path = TABLEBASE_PATH.map(str::to_string);
+            competition.verbosity = verbosity;
+            competition.time_control = TIME_CONTROL;
+            let result = competition.start_competition(game_pairs).await;
+            let mut record = ConfigurationRecord::new(modules1, modules2, &result);
+            record.profile = Some(*profile1);
+            record.opponent_profile = Some(*profile2);
+            let _ = summary::write_record(record, RESULTS_FILE);
+            elo[i][j] = result.elo_difference();
+            elo[j][i] = -elo[i][j];
+        }
+    }
+
+    let names: Vec<String> = algorithms
+        .iter()
+        .map(|(profile, _)| profile.to_string())
+        .collect();
+    let mut output = format!("Eval profiles\t{}\n", names.join("\t"));
+    for (name, row) in names.iter().zip(&elo) {
+        let row: Vec<String> = row.iter().map(|elo| format!("{:.1}", elo)).collect();
+        output += &format!("{}\t{}\n", name, row.join("\t"));
+    }
+    print!("{}", output);
+    let _ = write_result(output.as_bytes(), "./profiles.txt");
+}
+
 async fn do_competition(
-    modules1: u32,
-    modules2: u32,
+    modules1: u64,
+    modules2: u64,
     time_per_move1: Duration,
     time_per_move2: Duration,
     game_pairs: u32,
+    verbosity: Verbosity,
 ) -> CompetitionResults {
-    let competition = Competition::new(
-        Algorithm::new(modules1, time_per_move1),
-        Algorithm::new(modules2, time_per_move2),
-    );
+    let mut algo1 = Algorithm::new(modules1, time_per_move1);
+    algo1.resize_transposition_table(TRANSPOSITION_TABLE_MB);
+    algo1.transposition_clear_policy = CLEAR_POLICY1;
+    algo1.history_table.decay = HISTORY_DECAY1;
+    algo1.fixed_depth = FIXED_DEPTH1;
+    let mut algo2 = Algorithm::new(modules2, time_per_move2);
+    algo2.resize_transposition_table(TRANSPOSITION_TABLE_MB);
+    algo2.transposition_clear_policy = CLEAR_POLICY2;
+    algo2.history_table.decay = HISTORY_DECAY2;
+    algo2.fixed_depth = FIXED_DEPTH2;
+    for (algo, file) in [
+        (&mut algo1, EVAL_PARAMS_FILE1),
+        (&mut algo2, EVAL_PARAMS_FILE2),
+    ] {
+        if let Some(path) = file {
+            if let Err(error) = algo.load_eval_params(path) {
+                panic!("Could not load eval weights {}: {}", path, error);
+            }
+        }
+    }
+    for (algo, file) in [(&mut algo1, PSQT_FILE1), (&mut algo2, PSQT_FILE2)] {
+        if let Some(path) = file {
+            if let Err(error) = algo.load_psqt_tables(path) {
+                panic!("Could not load PSQTs {}: {}", path, error);
+            }
+        }
+    }
+    if let Some(path) = NNUE_FILE {
+        for algo in [&mut algo1, &mut algo2] {
+            if let Err(error) = algo.load_nnue(path) {
+                panic!("Could not load network {}: {}", path, error);
+            }
+        }
+    }
+    // After the weights, which the table has to have been searched with
+    if let Some(path) = TRANSPOSITION_TABLE_FILE {
+        match algo1.load_tt(path) {
+            Ok(()) => println!(
+                "Loaded {} transposition entries",
+                algo1.transposition_table.occupied()
+            ),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                algo1.transposition_clear_policy = ClearPolicy::Never
+            }
+            Err(error) => panic!("Could not load transposition table {}: {}", path, error),
+        }
+    }
+    let mut competition = Competition::new(algo1, algo2);
+    competition.pgn_archive = PGN_ARCHIVE_FILE.map(str::to_string);
+    competition.tablebase_path = TABLEBASE_PATH.map(str::to_string);
+    competition.transposition_table_file = TRANSPOSITION_TABLE_FILE.map(str::to_string);
+    competition.verbosity = verbosity;
+    competition.time_control = TIME_CONTROL;
 
     // competition.analyze_algorithm_choices(|(game_info, _), _| {
     //     game_info.outcome == GameOutcome::InconclusiveTooLong
@@ -1,17 +1,22 @@
-use crate::algorithms::utils::Evaluation;
+use crate::algorithms::utils::{Bound, Evaluation};
 
-pub(crate) fn calc_new(
-    mut alpha: f32,
-    mut beta: f32,
-    maximise: bool,
-    evaluation: Evaluation,
-) -> (f32, f32) {
-    if let Some(eval) = evaluation.eval {
-        if maximise {
-            alpha = alpha.max(eval);
-        } else {
-            beta = beta.min(eval);
-        }
+/// Raises alpha to the eval of a searched move, if it is better than the best move so far
+pub(crate) fn calc_new(alpha: i32, evaluation: Evaluation) -> i32 {
+    match evaluation.eval {
+        Some(eval) => alpha.max(eval),
+        None => alpha,
+    }
+}
+
+/// Whether a fail-soft `eval` searched with the window `alpha`..`beta` is exact. Evals outside
+/// of the window are only bounds, as the moves past a cutoff weren't searched or the moves were
+/// only searched far enough to show they are worse than alpha.
+pub(crate) fn bound(eval: i32, alpha: i32, beta: i32) -> Bound {
+    if eval >= beta {
+        Bound::Lower
+    } else if eval <= alpha {
+        Bound::Upper
+    } else {
+        Bound::Exact
     }
-    (alpha, beta)
 }
@@ -5,8 +5,7 @@ use crate::common::utils;
 
 #[must_use]
 pub(crate) fn get_debug_data(
-    modules: u32,
-    maximise: bool,
+    modules: u64,
     best_evaluation: &Evaluation,
     chess_move: &ChessMove,
     evaluation: &Evaluation,
@@ -17,7 +16,6 @@ pub(crate) fn get_debug_data(
     utils::vector_push_debug!(
         vec,
         modules,
-        maximise,
         best_evaluation.eval,
         new_best_move,
         new_best_eval,
@@ -0,0 +1,45 @@
+use crate::algorithms::eval;
+use crate::algorithms::utils::Bound;
+
+/// The window that searches without aspiration windows use. Symmetric, so that it can be
+/// negated for the children.
+pub(crate) const FULL_WINDOW: (i32, i32) = (-i32::MAX, i32::MAX);
+/// Half the width of the first window around the previous iteration's eval, in centipawns
+const INITIAL_DELTA: i32 = 25;
+/// Windows wider than this are widened to the full window on the failing side instead
+const MAX_DELTA: i32 = 400;
+
+/// A narrow window around the eval of the previous iteration, as the eval rarely changes much
+/// between depths
+pub(crate) fn initial(previous_eval: Option<i32>) -> (i32, i32) {
+    match previous_eval {
+        Some(eval) if !eval::is_mate_score(eval) => (eval - INITIAL_DELTA, eval + INITIAL_DELTA),
+        _ => FULL_WINDOW,
+    }
+}
+
+/// The window to search again with if the root eval with `bound` fell outside of `window`,
+/// widened on the side that it failed on. None if the search doesn't need to be repeated.
+pub(crate) fn widen(window: (i32, i32), bound: Bound) -> Option<(i32, i32)> {
+    let (alpha, beta) = window;
+    let delta = beta.saturating_sub(alpha);
+    match bound {
+        Bound::Upper if alpha != -i32::MAX => Some((
+            if delta >= MAX_DELTA {
+                -i32::MAX
+            } else {
+                alpha - delta
+            },
+            beta,
+        )),
+        Bound::Lower if beta != i32::MAX => Some((
+            alpha,
+            if delta >= MAX_DELTA {
+                i32::MAX
+            } else {
+                beta + delta
+            },
+        )),
+        _ => None,
+    }
+}
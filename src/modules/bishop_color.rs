@@ -0,0 +1,60 @@
+use chess::{get_pawn_attacks, BitBoard, Board, Color, Piece, EMPTY};
+
+use crate::algorithms::attack_maps;
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::DARK_SQUARES;
+use crate::common::utils::taper;
+
+/// Penalty per own pawn on the squares of a bishop's color, in the middlegame and endgame. Such
+/// pawns block the bishop and leave the squares of the other color to the enemy.
+pub(crate) const DEFAULT_BAD_BISHOP_PENALTY: (i32, i32) = (3, 6);
+/// Middlegame penalty per square next to the king of the color of a traded bishop that no own
+/// pawn covers, while the enemy still has a bishop of that color
+pub(crate) const DEFAULT_COLOR_WEAKNESS_PENALTY: i32 = 8;
+
+/// The penalties of black's bad bishops and weak color complexes minus those of white's,
+/// tapered by `phase`. `dark_pawns` and `light_pawns` are the pawns of each color on dark and
+/// light squares by color index, e.g. from the pawn hash.
+pub(crate) fn bishop_color_score(
+    board: &Board,
+    dark_pawns: [BitBoard; 2],
+    light_pawns: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    side_penalty(board, Color::Black, dark_pawns, light_pawns, phase, params)
+        - side_penalty(board, Color::White, dark_pawns, light_pawns, phase, params)
+}
+
+fn side_penalty(
+    board: &Board,
+    color: Color,
+    dark_pawns: [BitBoard; 2],
+    light_pawns: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    let bishops = |color: Color| board.pieces(Piece::Bishop) & board.color_combined(color);
+    let pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+    let pawn_cover = pawns.fold(EMPTY, |cover, pawn| {
+        cover | get_pawn_attacks(pawn, color, !EMPTY)
+    });
+    let king_zone = attack_maps::king_zone(board, color);
+
+    let mut penalty = 0;
+    for (squares, own_pawns) in [
+        (BitBoard::new(DARK_SQUARES), dark_pawns[color.to_index()]),
+        (BitBoard::new(!DARK_SQUARES), light_pawns[color.to_index()]),
+    ] {
+        let blocking_pawns = own_pawns.popcnt() as i32;
+        let (mg, eg) = params.bad_bishop_penalty;
+        let own_bishops = (bishops(color) & squares).popcnt() as i32;
+        penalty += own_bishops * taper((mg * blocking_pawns, eg * blocking_pawns), phase);
+
+        if own_bishops == 0 && bishops(!color) & squares != EMPTY {
+            let weak_squares = (king_zone & squares & !pawn_cover).popcnt() as i32;
+            penalty += taper((params.color_weakness_penalty * weak_squares, 0), phase);
+        }
+    }
+    penalty
+}
@@ -0,0 +1,48 @@
+use chess::{Board, Color, Piece, Square};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::taper;
+
+/// Middlegame penalty per minor piece still on its starting square while the queen has left
+/// hers, in centipawns. The queen gets chased around by the minor pieces developing with tempo.
+pub(crate) const DEFAULT_EARLY_QUEEN_PENALTY: i32 = 8;
+
+/// Starting squares of the queen and of the minor pieces of each color
+const QUEEN_START: [Square; 2] = [Square::D1, Square::D8];
+const MINOR_STARTS: [[(Square, Piece); 4]; 2] = [
+    [
+        (Square::B1, Piece::Knight),
+        (Square::C1, Piece::Bishop),
+        (Square::F1, Piece::Bishop),
+        (Square::G1, Piece::Knight),
+    ],
+    [
+        (Square::B8, Piece::Knight),
+        (Square::C8, Piece::Bishop),
+        (Square::F8, Piece::Bishop),
+        (Square::G8, Piece::Knight),
+    ],
+];
+
+/// The penalty of black's queen having left early minus that of white's, tapered by `phase` so
+/// that it only counts while pieces are still being developed
+pub(crate) fn early_queen_score(board: &Board, phase: i32, params: &EvalParams) -> i32 {
+    let penalty =
+        side_penalty(board, Color::Black, params) - side_penalty(board, Color::White, params);
+    taper((penalty, 0), phase)
+}
+
+fn side_penalty(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let on_start = |square: Square, piece: Piece| {
+        board.piece_on(square) == Some(piece) && board.color_on(square) == Some(color)
+    };
+    let has_queen = (board.pieces(Piece::Queen) & board.color_combined(color)).popcnt() > 0;
+    if !has_queen || on_start(QUEEN_START[color.to_index()], Piece::Queen) {
+        return 0;
+    }
+    let undeveloped = MINOR_STARTS[color.to_index()]
+        .iter()
+        .filter(|&&(square, piece)| on_start(square, piece))
+        .count() as i32;
+    undeveloped * params.early_queen_penalty
+}
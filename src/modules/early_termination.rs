@@ -0,0 +1,24 @@
+use chess::Action;
+
+/// How many completed depths in a row have to agree on the best move
+const STABLE_DEPTHS: usize = 3;
+/// How much the eval may change over those depths, in centipawns
+const EVAL_MARGIN: i32 = 20;
+
+/// Whether the last completed depths all chose the same move with about the same eval, so that
+/// searching deeper is unlikely to change the move. `iterations` holds the action and eval of
+/// every completed depth, shallowest first.
+pub(crate) fn is_stable(iterations: &[(Option<Action>, Option<i32>)]) -> bool {
+    let Some(last) = iterations.len().checked_sub(STABLE_DEPTHS) else {
+        return false;
+    };
+    let (action, Some(eval)) = iterations[last] else {
+        return false;
+    };
+    iterations[last..]
+        .iter()
+        .all(|&(other_action, other_eval)| {
+            other_action == action
+                && other_eval.is_some_and(|other| (other - eval).abs() <= EVAL_MARGIN)
+        })
+}
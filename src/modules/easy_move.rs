@@ -0,0 +1,38 @@
+use chess::{BitBoard, Board, ChessMove};
+use tokio::time::Instant;
+
+/// How much better than every other root move the best one has to be, in centipawns
+const EVAL_MARGIN: i32 = 200;
+/// Fraction of its time an easy move gets, the rest is left to later moves
+const TIME_FRACTION: f32 = 0.2;
+
+/// Whether `best_move` is obvious enough to not spend the full time on. It is if it recaptures
+/// on the square where the opponent's last move captured, going from `previous`, the board after
+/// our previous move, to `board`, or if the first iteration found it `margin` better than every
+/// other move.
+pub(crate) fn is_easy(
+    previous: Option<&Board>,
+    board: &Board,
+    best_move: ChessMove,
+    margin: Option<i32>,
+) -> bool {
+    margin.is_some_and(|margin| margin >= EVAL_MARGIN)
+        || previous.is_some_and(|previous| is_recapture(previous, board, best_move))
+}
+
+/// Whether `chess_move` captures on a square where one of the pieces of the side to move on
+/// `board` was captured since `previous`
+fn is_recapture(previous: &Board, board: &Board, chess_move: ChessMove) -> bool {
+    let us = board.side_to_move();
+    let captured_on = previous.color_combined(us) & board.color_combined(!us);
+    board.piece_on(chess_move.get_dest()).is_some()
+        && (captured_on & BitBoard::from_square(chess_move.get_dest())).popcnt() > 0
+}
+
+/// The shortened deadline of an easy move searched from `start`
+pub(crate) fn deadline(start: Instant, deadline: Instant) -> Instant {
+    start
+        + deadline
+            .saturating_duration_since(start)
+            .mul_f32(TIME_FRACTION)
+}
@@ -0,0 +1,157 @@
+use chess::{BitBoard, Board, Color, File, Piece, Square, ALL_COLORS, ALL_SQUARES, EMPTY};
+
+use crate::algorithms::eval;
+use crate::common::constants::file_masks::FILES;
+use crate::common::utils::{distance, relative_rank};
+
+/// Base score of an ending known to be won, with the progress towards the win added on top.
+/// Worth more than any material left in these endings but still within the eval clamp.
+const KNOWN_WIN: i32 = 1000;
+/// Corners a bishop on dark squares and one on light squares can mate in
+const DARK_CORNERS: [usize; 2] = [0, 63];
+const LIGHT_CORNERS: [usize; 2] = [7, 56];
+/// Most pieces, kings included, of any recognized ending. Positions with more skip the
+/// recognizer.
+pub(crate) const MAX_PIECES: u32 = 6;
+/// Scale factors of the eval, out of `NORMAL_SCALE`, in endings that are hard to win even when
+/// one side is ahead
+pub(crate) const NORMAL_SCALE: i32 = 64;
+const OPPOSITE_BISHOPS_SCALE: i32 = 32;
+const ROOK_ENDING_SCALE: i32 = 40;
+
+/// The eval of an ending with exact or heuristic knowledge, from white's point of view. None if
+/// the position isn't one of those endings or the knowledge doesn't settle it.
+pub(crate) fn endgame_eval(board: &Board) -> Option<i32> {
+    for strong in ALL_COLORS {
+        // All of the recognized endings are against a bare king
+        if board.color_combined(!strong).popcnt() != 1 {
+            continue;
+        }
+        let count = |piece: Piece| (board.pieces(piece) & board.color_combined(strong)).popcnt();
+        let pieces = board.color_combined(strong).popcnt() - 1;
+        let eval = if pieces == 1 && count(Piece::Pawn) == 1 {
+            kpk(board, strong)
+        } else if pieces == 2 && count(Piece::Bishop) == 1 && count(Piece::Knight) == 1 {
+            Some(kbnk(board, strong))
+        } else if count(Piece::Bishop) == 1
+            && count(Piece::Pawn) > 0
+            && pieces == 1 + count(Piece::Pawn)
+        {
+            wrong_bishop(board, strong)
+        } else {
+            None
+        };
+        return eval.map(|eval| eval::from_perspective(strong, eval));
+    }
+    None
+}
+
+/// How much of the eval to keep, out of `NORMAL_SCALE`, for how drawish the material is.
+/// Keeps the search from trading into dead drawn endings while ahead in positional points.
+pub(crate) fn drawishness_scale(board: &Board) -> i32 {
+    let count =
+        |piece: Piece, color: Color| (board.pieces(piece) & board.color_combined(color)).popcnt();
+    let only = |piece: Piece| {
+        ALL_COLORS.iter().all(|&color| {
+            count(piece, color) == 1
+                && board.color_combined(color).popcnt() == 2 + count(Piece::Pawn, color)
+        })
+    };
+    let pawns = ALL_COLORS.map(|color| count(Piece::Pawn, color));
+    let (fewer, more) = (pawns[0].min(pawns[1]), pawns[0].max(pawns[1]));
+
+    let bishops = board.pieces(Piece::Bishop);
+    if only(Piece::Bishop) {
+        let [white, black] =
+            ALL_COLORS.map(|color| (bishops & board.color_combined(color)).to_square());
+        if is_dark(white) != is_dark(black) {
+            return OPPOSITE_BISHOPS_SCALE;
+        }
+    }
+    if only(Piece::Rook) && more - fewer <= 1 && more <= 2 {
+        return ROOK_ENDING_SCALE;
+    }
+    NORMAL_SCALE
+}
+
+/// King and pawn against king, by the rule of the square and the key squares of the pawn.
+/// From the point of view of `strong`.
+fn kpk(board: &Board, strong: Color) -> Option<i32> {
+    let pawn = (board.pieces(Piece::Pawn) & board.color_combined(strong)).to_square();
+    let strong_king = board.king_square(strong);
+    let weak_king = board.king_square(!strong);
+    let weak_to_move = board.side_to_move() != strong;
+    let rank = relative_rank(pawn, strong);
+    let file = pawn.get_file();
+    let win = KNOWN_WIN + 10 * rank as i32;
+
+    // An undefended pawn next to the defending king falls if it is the defender's move
+    if weak_to_move && distance(weak_king, pawn) == 1 && distance(strong_king, pawn) > 1 {
+        return None;
+    }
+
+    // The pawn runs in if the defending king is outside its square and nothing blocks it
+    let promotion = Square::make_square(strong.to_their_backrank(), file);
+    let pawn_moves = (7 - rank).min(5) as i32;
+    let defender_moves = distance(weak_king, promotion) - weak_to_move as i32;
+    let own_king_in_the_way =
+        strong_king.get_file() == file && relative_rank(strong_king, strong) > rank;
+    if defender_moves > pawn_moves && !own_king_in_the_way {
+        return Some(win);
+    }
+
+    // A rook pawn is a draw once the defending king reaches the queening corner
+    if (file == File::A || file == File::H) && distance(weak_king, promotion) <= 1 {
+        return Some(0);
+    }
+
+    // The attacking king on a key square wins regardless of who has the opposition
+    let key_ranks = if rank >= 4 {
+        rank + 1..=(rank + 2).min(7)
+    } else {
+        rank + 2..=rank + 2
+    };
+    let on_key_square = key_ranks.contains(&relative_rank(strong_king, strong))
+        && strong_king.get_file().to_index().abs_diff(file.to_index()) <= 1;
+    if on_key_square && file != File::A && file != File::H {
+        return Some(win);
+    }
+    None
+}
+
+/// King, bishop and knight against king is won, but only by driving the defending king to a
+/// corner of the bishop's color. From the point of view of `strong`.
+fn kbnk(board: &Board, strong: Color) -> i32 {
+    let bishop = (board.pieces(Piece::Bishop) & board.color_combined(strong)).to_square();
+    let weak_king = board.king_square(!strong);
+    let corners = if is_dark(bishop) {
+        DARK_CORNERS
+    } else {
+        LIGHT_CORNERS
+    };
+    let corner_distance = corners
+        .iter()
+        .map(|&corner| distance(weak_king, ALL_SQUARES[corner]))
+        .min()
+        .unwrap_or(0);
+    let king_distance = distance(board.king_square(strong), weak_king);
+    KNOWN_WIN + 20 * (7 - corner_distance) + 10 * (7 - king_distance)
+}
+
+/// A bishop and pawns on a single rook file can't win if the bishop doesn't cover the queening
+/// square and the defending king gets there. From the point of view of `strong`.
+fn wrong_bishop(board: &Board, strong: Color) -> Option<i32> {
+    let pawns = board.pieces(Piece::Pawn) & board.color_combined(strong);
+    let file = [File::A, File::H]
+        .into_iter()
+        .find(|file| pawns & !BitBoard::new(FILES[file.to_index()]) == EMPTY)?;
+    let bishop = (board.pieces(Piece::Bishop) & board.color_combined(strong)).to_square();
+    let promotion = Square::make_square(strong.to_their_backrank(), file);
+    let drawn = is_dark(bishop) != is_dark(promotion)
+        && distance(board.king_square(!strong), promotion) <= 1;
+    drawn.then_some(0)
+}
+
+fn is_dark(square: Square) -> bool {
+    (square.get_rank().to_index() + square.get_file().to_index()).is_multiple_of(2)
+}
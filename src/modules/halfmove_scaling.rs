@@ -0,0 +1,19 @@
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::FIFTY_MOVE_PLIES;
+
+/// Plies without a capture or pawn move before the eval starts to shrink
+pub(crate) const DEFAULT_HALFMOVE_SCALE_START: i32 = 20;
+
+/// Percent of the eval that is kept with `halfmove_clock` plies since the last capture or pawn
+/// move. Falls linearly from 100 after `halfmove_scale_start` plies down to 0 at the fifty-move
+/// draw, so that the side ahead makes progress while there is still time to convert.
+pub(crate) fn halfmove_scale(halfmove_clock: u32, params: &EvalParams) -> i32 {
+    let fifty_moves = FIFTY_MOVE_PLIES as i32;
+    let start = params.halfmove_scale_start.clamp(0, fifty_moves - 1);
+    let clock = halfmove_clock as i32;
+    if clock <= start {
+        100
+    } else {
+        100 * (fifty_moves - clock).max(0) / (fifty_moves - start)
+    }
+}
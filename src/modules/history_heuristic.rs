@@ -0,0 +1,93 @@
+use chess::{Board, ChessMove, Color};
+
+/// Scores stay within plus/minus this
+pub(crate) const DEFAULT_HISTORY_MAX_SCORE: i32 = 1 << 14;
+
+/// What happens to the history table between moves
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum HistoryDecay {
+    /// Keep all scores, so that history from earlier moves counts as much as new history
+    Keep,
+    /// Halve all scores, so that old history fades out
+    Halve,
+    /// Start every move with an empty table
+    Clear,
+}
+
+/// How often quiet moves caused beta cutoffs, minus how often they were searched without
+/// causing one before another move did, indexed by `[color][from][to]`
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryTable {
+    scores: Vec<i32>,
+    /// Scores stay within plus/minus this. Updates are scaled down the closer the score already
+    /// is to it, so that scores don't saturate and new cutoffs still matter.
+    pub(crate) max_score: i32,
+    pub(crate) decay: HistoryDecay,
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        HistoryTable {
+            scores: vec![0; 2 * 64 * 64],
+            max_score: DEFAULT_HISTORY_MAX_SCORE,
+            decay: HistoryDecay::Halve,
+        }
+    }
+}
+
+impl HistoryTable {
+    fn index(color: Color, chess_move: ChessMove) -> usize {
+        (color.to_index() * 64 + chess_move.get_source().to_index()) * 64
+            + chess_move.get_dest().to_index()
+    }
+
+    fn is_quiet(board: &Board, chess_move: ChessMove) -> bool {
+        board.piece_on(chess_move.get_dest()).is_none() && chess_move.get_promotion().is_none()
+    }
+
+    /// The history score of the move, None for captures and promotions which are ordered by
+    /// other means
+    pub(crate) fn score(&self, board: &Board, chess_move: ChessMove) -> Option<i32> {
+        Self::is_quiet(board, chess_move)
+            .then(|| self.scores[Self::index(board.side_to_move(), chess_move)])
+    }
+
+    /// Rewards a quiet move that caused a beta cutoff and penalizes the quiet moves in
+    /// `searched_before` that were searched first without causing one. Deeper cutoffs save more
+    /// work, so they count for more.
+    pub(crate) fn record_cutoff(
+        &mut self,
+        board: &Board,
+        chess_move: ChessMove,
+        searched_before: &[ChessMove],
+        depth: u32,
+    ) {
+        if !Self::is_quiet(board, chess_move) {
+            return;
+        }
+        let bonus = (depth * depth).min(self.max_score as u32) as i32;
+        self.update(board.side_to_move(), chess_move, bonus);
+        for &searched in searched_before {
+            if Self::is_quiet(board, searched) {
+                self.update(board.side_to_move(), searched, -bonus);
+            }
+        }
+    }
+
+    /// Adds `bonus` to the score, scaled down by how close the score already is to the max
+    /// score in that direction
+    fn update(&mut self, color: Color, chess_move: ChessMove, bonus: i32) {
+        let score = &mut self.scores[Self::index(color, chess_move)];
+        let gravity = *score as i64 * bonus.abs() as i64 / self.max_score as i64;
+        *score += bonus - gravity as i32;
+    }
+
+    /// Applies the decay policy, called before every move
+    pub(crate) fn decay(&mut self) {
+        if self.decay == HistoryDecay::Clear {
+            self.scores.iter_mut().for_each(|score| *score = 0);
+        } else if self.decay != HistoryDecay::Keep {
+            self.scores.iter_mut().for_each(|score| *score /= 2);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use chess::{Board, ChessMove, Color, Piece, Square, ALL_COLORS, ALL_PIECES};
+
+use crate::algorithms::eval;
+use crate::algorithms::psqt_tables::PsqtTables;
+use crate::common::utils::{for_each_piece_change, taper};
+
+/// Middlegame and endgame PeSTO PSQT sums of all pieces, from white's point of view. Kept up
+/// to date move by move instead of being summed over the board at every eval.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PsqtAccumulator {
+    mg: i32,
+    eg: i32,
+}
+
+impl PsqtAccumulator {
+    /// Sums the tables over every piece on `board`
+    pub(crate) fn from_board(board: &Board, tables: &PsqtTables) -> Self {
+        let mut accumulator = PsqtAccumulator::default();
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                for square in board.pieces(piece) & board.color_combined(color) {
+                    accumulator.add(tables, piece, color, square);
+                }
+            }
+        }
+        accumulator
+    }
+
+    /// The accumulator of the position after `chess_move` is made on `board`
+    pub(crate) fn after_move(
+        mut self,
+        board: &Board,
+        chess_move: ChessMove,
+        tables: &PsqtTables,
+    ) -> Self {
+        for_each_piece_change(board, chess_move, |piece, color, square, added| {
+            if added {
+                self.add(tables, piece, color, square);
+            } else {
+                self.remove(tables, piece, color, square);
+            }
+        });
+        self
+    }
+
+    /// The PSQT score from white's point of view, tapered by `phase`
+    pub(crate) fn tapered(&self, phase: i32) -> i32 {
+        taper((self.mg, self.eg), phase)
+    }
+
+    fn add(&mut self, tables: &PsqtTables, piece: Piece, color: Color, square: Square) {
+        let (mg, eg) = table_values(tables, piece, color, square);
+        self.mg += mg;
+        self.eg += eg;
+    }
+
+    fn remove(&mut self, tables: &PsqtTables, piece: Piece, color: Color, square: Square) {
+        let (mg, eg) = table_values(tables, piece, color, square);
+        self.mg -= mg;
+        self.eg -= eg;
+    }
+}
+
+/// Index into the tables of a piece of `color` on `square`. The tables are laid out from a8, as
+/// seen by white, so white's squares are flipped vertically.
+pub(crate) fn table_index(color: Color, square: Square) -> usize {
+    match color {
+        Color::White => square.to_index() ^ 56,
+        Color::Black => square.to_index(),
+    }
+}
+
+/// Table values of `piece` of `color` on `square`, from white's point of view
+fn table_values(tables: &PsqtTables, piece: Piece, color: Color, square: Square) -> (i32, i32) {
+    let index = table_index(color, square);
+    (
+        eval::from_perspective(color, tables.mg[piece.to_index()][index]),
+        eval::from_perspective(color, tables.eg[piece.to_index()][index]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::{Board, ChessMove, MoveGen};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::PsqtAccumulator;
+    use crate::algorithms::psqt_tables::PsqtTables;
+
+    /// Plays every legal move of `board` and checks the updated sums against summing the board
+    fn check_every_move(board: &Board, tables: &PsqtTables) {
+        let accumulator = PsqtAccumulator::from_board(board, tables);
+        for chess_move in MoveGen::new_legal(board) {
+            assert_eq!(
+                accumulator.after_move(board, chess_move, tables),
+                PsqtAccumulator::from_board(&board.make_move_new(chess_move), tables),
+                "after {} from {}",
+                chess_move,
+                board
+            );
+        }
+    }
+
+    fn check_line(fen: &str, moves: &[&str]) {
+        let tables = PsqtTables::default();
+        let mut board = Board::from_str(fen).unwrap();
+        let mut accumulator = PsqtAccumulator::from_board(&board, &tables);
+        for chess_move in moves {
+            check_every_move(&board, &tables);
+            let chess_move = ChessMove::from_san(&board, chess_move).unwrap();
+            accumulator = accumulator.after_move(&board, chess_move, &tables);
+            board = board.make_move_new(chess_move);
+            assert_eq!(
+                accumulator,
+                PsqtAccumulator::from_board(&board, &tables),
+                "after {} in {}",
+                chess_move,
+                board
+            );
+        }
+    }
+
+    #[test]
+    fn matches_random_games() {
+        let tables = PsqtTables::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let mut board = Board::default();
+            let mut accumulator = PsqtAccumulator::from_board(&board, &tables);
+            for _ in 0..200 {
+                check_every_move(&board, &tables);
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                if moves.is_empty() {
+                    break;
+                }
+                let chess_move = moves[rng.gen_range(0..moves.len())];
+                accumulator = accumulator.after_move(&board, chess_move, &tables);
+                board = board.make_move_new(chess_move);
+                assert_eq!(accumulator, PsqtAccumulator::from_board(&board, &tables));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_captures() {
+        check_line(
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+            &["exd5", "Qxd5", "Nc3", "Qxa2", "Rxa2"],
+        );
+    }
+
+    #[test]
+    fn matches_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        check_line(fen, &["O-O", "O-O-O"]);
+        check_line(fen, &["O-O-O", "O-O"]);
+    }
+
+    #[test]
+    fn matches_en_passant() {
+        check_line("4k3/8/8/8/5p2/8/4P3/4K3 w - - 0 1", &["e4", "fxe3"]);
+        check_line("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1", &["d5", "exd6"]);
+    }
+
+    #[test]
+    fn matches_promotions() {
+        let fen = "1r6/P3k3/8/8/8/8/p3K3/1R6 w - - 0 1";
+        for piece in ["Q", "R", "B", "N"] {
+            check_line(fen, &[&format!("a8={}", piece), &format!("a1={}", piece)]);
+            check_line(
+                fen,
+                &[&format!("axb8={}", piece), &format!("axb1={}", piece)],
+            );
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use chess::{Action, Board, ChessMove};
+
+use crate::modules::transposition_table::TranspositionEntry;
+
+/// Nodes closer to the leaves than this are cheap enough to search without a best move
+const MIN_DEPTH: u32 = 4;
+/// How much shallower the internal search is than the node
+pub(crate) const REDUCTION: u32 = 2;
+
+/// Whether a node without a transposition entry should be searched at a reduced depth first
+pub(crate) fn should_search(depth: u32, has_transposition_entry: bool) -> bool {
+    depth >= MIN_DEPTH && !has_transposition_entry
+}
+
+/// Moves the best move of the internal search to the front, keeping the order of the rest
+pub(crate) fn order_first(
+    boards: &mut [(ChessMove, Board, Option<TranspositionEntry>)],
+    best_action: Option<Action>,
+) {
+    if let Some(Action::MakeMove(best_move)) = best_action {
+        if let Some(i) = boards
+            .iter()
+            .position(|(chess_move, _, _)| *chess_move == best_move)
+        {
+            boards[..=i].rotate_right(1);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use tokio::time::Duration;
+
+/// Branching factor assumed before any iteration has been timed
+pub(crate) const DEFAULT_BRANCHING_FACTOR: f32 = 4.;
+/// How much a new estimate moves the running average of the branching factor
+const ESTIMATE_WEIGHT: f32 = 0.25;
+/// Iterations cancelled with less progress than this are too noisy to estimate from
+const MIN_PROGRESS: f32 = 0.05;
+
+/// Whether the next iteration is predicted to finish in `time_left`, taking `branching_factor`
+/// times as long as the previous one
+pub(crate) fn can_complete(
+    previous_iteration: Duration,
+    branching_factor: f32,
+    time_left: Duration,
+) -> bool {
+    previous_iteration.mul_f32(branching_factor) <= time_left
+}
+
+/// Moves the running estimate towards the branching factor of an iteration that took
+/// `iteration` after the previous one took `previous_iteration`. `progress` is how much of the
+/// iteration got done before it was cancelled, 1 if it completed.
+pub(crate) fn update_branching_factor(
+    branching_factor: f32,
+    previous_iteration: Duration,
+    iteration: Duration,
+    progress: f32,
+) -> f32 {
+    if progress < MIN_PROGRESS || previous_iteration.is_zero() {
+        return branching_factor;
+    }
+    let estimate = iteration.as_secs_f32() / progress / previous_iteration.as_secs_f32();
+    branching_factor + (estimate - branching_factor) * ESTIMATE_WEIGHT
+}
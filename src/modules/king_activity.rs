@@ -0,0 +1,45 @@
+use chess::{BitBoard, Board, Color, Square};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::{distance, taper};
+
+/// Endgame bonus for each step the king is closer to the center than the edge of the board, in
+/// centipawns
+pub(crate) const DEFAULT_KING_CENTER_BONUS: i32 = 10;
+/// Endgame bonus per passed pawn of its own color for each step the king is closer to it than the
+/// far side of the board, escorting it to promotion
+pub(crate) const DEFAULT_KING_OWN_PASSER_BONUS: i32 = 3;
+/// Same as `DEFAULT_KING_OWN_PASSER_BONUS`, for enemy passed pawns that the king has to stop
+pub(crate) const DEFAULT_KING_ENEMY_PASSER_BONUS: i32 = 5;
+
+/// The bonus of white's king being active minus that of black's, tapered by `phase` so that it
+/// only counts in the endgame. `passers` are the passed pawns of each color by color index,
+/// e.g. from the pawn hash.
+pub(crate) fn king_activity_bonus(
+    board: &Board,
+    passers: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    let activity = side_activity(board, Color::White, passers, params)
+        - side_activity(board, Color::Black, passers, params);
+    taper((0, activity), phase)
+}
+
+fn side_activity(board: &Board, color: Color, passers: [BitBoard; 2], params: &EvalParams) -> i32 {
+    let king = board.king_square(color);
+    let proximity = |passers: BitBoard, weight: i32| {
+        passers
+            .map(|passer| weight * (7 - distance(king, passer)))
+            .sum::<i32>()
+    };
+    params.king_center_bonus * (3 - center_distance(king))
+        + proximity(passers[color.to_index()], params.king_own_passer_bonus)
+        + proximity(passers[(!color).to_index()], params.king_enemy_passer_bonus)
+}
+
+/// Number of king moves from `square` to the nearest of the four central squares
+fn center_distance(square: Square) -> i32 {
+    let from_center = |index: usize| if index < 4 { 3 - index } else { index - 4 };
+    from_center(square.get_rank().to_index()).max(from_center(square.get_file().to_index())) as i32
+}
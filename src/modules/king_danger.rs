@@ -0,0 +1,45 @@
+use chess::{Board, Color, ALL_PIECES};
+
+use crate::algorithms::attack_maps::{king_zone, AttackMaps};
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::taper;
+
+/// Danger per king zone square attacked by each piece type, for pawn, knight, bishop, rook,
+/// queen and king
+pub(crate) const DEFAULT_KING_ATTACK_WEIGHTS: [i32; 6] = [5, 10, 10, 15, 25, 0];
+/// Percent of the attack weight that counts as danger by the number of attackers. A lone
+/// attacker is rarely dangerous, while every extra attacker makes the attack harder to meet.
+const ATTACKER_SCALE: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+/// How much danger the king of `color` is in from the enemy pieces attacking its zone, with
+/// `weights` per attacked square by piece index
+pub(crate) fn king_danger(
+    board: &Board,
+    attack_maps: &AttackMaps,
+    color: Color,
+    weights: &[i32; 6],
+) -> i32 {
+    let zone = king_zone(board, color);
+    let attack_weight: i32 = ALL_PIECES
+        .iter()
+        .zip(weights)
+        .map(|(&piece, weight)| {
+            weight * (attack_maps.by_piece(!color, piece) & zone).popcnt() as i32
+        })
+        .sum();
+    let attackers =
+        (attack_maps.king_zone_attackers(!color) as usize).min(ATTACKER_SCALE.len() - 1);
+    attack_weight * ATTACKER_SCALE[attackers] / 100
+}
+
+/// The danger black's king is in minus that of white's king, tapered by `phase` so that it only
+/// counts while there are pieces left to attack with
+pub(crate) fn king_danger_score(
+    board: &Board,
+    attack_maps: &AttackMaps,
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    let danger = |color| king_danger(board, attack_maps, color, &params.king_attack_weights);
+    taper((danger(Color::Black) - danger(Color::White), 0), phase)
+}
@@ -0,0 +1,32 @@
+use chess::{Board, Color, Piece};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::{distance, taper};
+
+/// The pieces that get a tropism bonus, in the order of their weights
+const TROPISM_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+/// Middlegame bonus per piece type for each step closer to the enemy king than the far side of
+/// the board, in centipawns. Attacks matter little once the pieces are traded off, so the
+/// endgame bonus is zero.
+pub(crate) const DEFAULT_TROPISM_WEIGHTS: [i32; 4] = [3, 2, 2, 5];
+
+/// The bonus of white's pieces being close to black's king minus the same for black, tapered by
+/// `phase`
+pub(crate) fn king_tropism_bonus(board: &Board, phase: i32, params: &EvalParams) -> i32 {
+    let tropism =
+        side_tropism(board, Color::White, params) - side_tropism(board, Color::Black, params);
+    taper((tropism, 0), phase)
+}
+
+fn side_tropism(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let enemy_king = board.king_square(!color);
+    TROPISM_PIECES
+        .iter()
+        .zip(params.tropism_weights)
+        .map(|(&piece, weight)| {
+            (board.pieces(piece) & board.color_combined(color))
+                .map(|square| weight * (7 - distance(square, enemy_king)))
+                .sum::<i32>()
+        })
+        .sum()
+}
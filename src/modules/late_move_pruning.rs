@@ -0,0 +1,20 @@
+use chess::{Board, ChessMove};
+
+/// Only nodes this close to the leaves are pruned
+const MAX_PRUNING_DEPTH: u32 = 3;
+/// One in this many moves that would be pruned is searched anyway, to check that pruning it
+/// would have been right. Counted by `Stats::late_move_prunes`, as the node count doesn't
+/// advance for the moves that are pruned.
+pub(crate) const VERIFICATION_INTERVAL: u32 = 16;
+
+/// Whether the `i`th move is late enough in the move list to be pruned. Only quiet moves at
+/// shallow depths are pruned, and never when in check, so the best move can't be discarded
+/// the way `skip_bad_moves` can.
+pub(crate) fn should_prune(board: &Board, chess_move: ChessMove, depth: u32, i: usize) -> bool {
+    if depth > MAX_PRUNING_DEPTH || board.checkers().popcnt() > 0 {
+        return false;
+    }
+    let quiet =
+        board.piece_on(chess_move.get_dest()).is_none() && chess_move.get_promotion().is_none();
+    quiet && i > 3 + (depth * depth) as usize
+}
@@ -0,0 +1,27 @@
+use chess::{Board, Color, Piece};
+
+use crate::algorithms::eval_params::EvalParams;
+
+/// Centipawns each knight gains per pawn on the board above `PAWN_BASELINE`, as knights need
+/// outposts and closed positions to be better than bishops
+pub(crate) const DEFAULT_KNIGHT_PAWN_ADJUSTMENT: i32 = 4;
+/// Centipawns each bishop gains per pawn on the board above `PAWN_BASELINE`, negative as
+/// bishops get better the more the position opens up
+pub(crate) const DEFAULT_BISHOP_PAWN_ADJUSTMENT: i32 = -3;
+/// Pawns on the board, both sides together, at which knights and bishops keep their material
+/// values
+const PAWN_BASELINE: i32 = 8;
+
+/// How much the material of white is worth more than its material value minus the same for
+/// black, by the mix of pieces on the board. Knights and bishops are adjusted by how many pawns
+/// are left.
+pub(crate) fn material_imbalance_score(board: &Board, params: &EvalParams) -> i32 {
+    let pawns_above_baseline = board.pieces(Piece::Pawn).popcnt() as i32 - PAWN_BASELINE;
+    let difference = |piece| {
+        let white = (board.pieces(piece) & board.color_combined(Color::White)).popcnt() as i32;
+        white - (board.pieces(piece).popcnt() as i32 - white)
+    };
+    pawns_above_baseline
+        * (difference(Piece::Knight) * params.knight_pawn_adjustment
+            + difference(Piece::Bishop) * params.bishop_pawn_adjustment)
+}
@@ -1,5 +1,39 @@
 pub(crate) mod alpha_beta;
 pub(crate) mod analyze;
+pub(crate) mod aspiration_windows;
+pub(crate) mod bishop_color;
+pub(crate) mod early_queen;
+pub(crate) mod early_termination;
+pub(crate) mod easy_move;
+pub(crate) mod endgames;
+pub(crate) mod halfmove_scaling;
+pub(crate) mod history_heuristic;
+pub(crate) mod incremental_psqt;
+pub(crate) mod internal_iterative_deepening;
+pub(crate) mod iteration_prediction;
+pub(crate) mod king_activity;
+pub(crate) mod king_danger;
+pub(crate) mod king_tropism;
+pub(crate) mod late_move_pruning;
+pub(crate) mod material_imbalance;
+pub(crate) mod multi_cut;
+pub(crate) mod nnue;
+pub(crate) mod passed_pawns;
+pub(crate) mod pawn_majority;
+pub(crate) mod pawn_shield;
+pub(crate) mod pawn_structure;
+pub(crate) mod principal_variation_search;
+pub(crate) mod probcut;
+pub(crate) mod quiescence;
+pub(crate) mod rook_coordination;
+pub(crate) mod rook_files;
+pub(crate) mod rook_on_seventh;
+pub(crate) mod root_splitting;
 pub(crate) mod search_extensions;
 pub(crate) mod skip_bad_moves;
+pub(crate) mod square_control;
+pub(crate) mod stalemate_trap;
+pub(crate) mod tempo;
+pub(crate) mod threats;
 pub(crate) mod transposition_table;
+pub(crate) mod trapped_pieces;
@@ -0,0 +1,25 @@
+use crate::algorithms::utils::{Evaluation, NodeType};
+
+/// Only nodes at least this far from the leaves are tried, as the reduced searches need depth
+const MIN_DEPTH: u32 = 3;
+/// How much shallower than normal the first moves are searched
+pub(crate) const REDUCTION: u32 = 2;
+/// How many of the first moves are searched at reduced depth
+pub(crate) const MOVES_TRIED: usize = 6;
+/// How many of those need to fail high for the node to be pruned
+pub(crate) const CUTOFFS_NEEDED: u32 = 3;
+/// One in this many pruned nodes is searched in full anyway, to check that pruning it was
+/// right. Counted by `Stats::multi_cut_prunes`.
+pub(crate) const VERIFICATION_INTERVAL: u32 = 16;
+
+/// Whether multi-cut should be tried at this node. Only expected cut-nodes are likely to have
+/// several moves that fail high.
+pub(crate) fn applies(node_type: NodeType, depth: u32) -> bool {
+    node_type == NodeType::Cut && depth >= MIN_DEPTH
+}
+
+/// Whether the move would cause a cutoff at this node, `evaluation` being from this node's
+/// point of view
+pub(crate) fn fails_high(beta: i32, evaluation: &Evaluation) -> bool {
+    evaluation.eval.is_some_and(|eval| eval >= beta)
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+use chess::{Board, ChessMove, Color, Piece, Square, ALL_COLORS, ALL_PIECES};
+
+use crate::common::utils::for_each_piece_change;
+
+/// One input per piece type of each color on each square
+pub(crate) const INPUT_SIZE: usize = 768;
+/// Neurons of the hidden layer. Small enough for the accumulator to be copied to every ply of
+/// the search stack.
+pub(crate) const HIDDEN_SIZE: usize = 32;
+/// Hidden activations are clipped to this, the integer that stands for 1
+const HIDDEN_ONE: i32 = 255;
+/// Output weights are multiplied by this in the weights file
+const OUTPUT_ONE: i32 = 64;
+/// Centipawns of an output of 1
+const OUTPUT_SCALE: i32 = 400;
+
+/// A 768→32→1 network evaluating a position from white's point of view. The hidden layer is
+/// kept in an `NnueAccumulator` that is updated move by move.
+#[derive(Clone, Debug, Hash)]
+pub(crate) struct Network {
+    /// The weights of each input to the hidden layer, by input index
+    input_weights: Vec<[i32; HIDDEN_SIZE]>,
+    hidden_biases: [i32; HIDDEN_SIZE],
+    output_weights: [i32; HIDDEN_SIZE],
+    output_bias: i32,
+}
+
+/// The hidden layer of the network before its activation, for one position
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct NnueAccumulator {
+    hidden: [i32; HIDDEN_SIZE],
+}
+
+impl Network {
+    /// Reads a weights file. It starts with the line `nnue 768 32 1`, followed by whitespace
+    /// separated integers: the 32 weights of every input, the hidden biases, the output
+    /// weights and the output bias. Inputs are ordered by color, then piece, then square.
+    pub(crate) fn load(path: &str) -> std::io::Result<Network> {
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.splitn(2, '\n');
+        let header = lines.next().unwrap_or_default().trim_end();
+        let expected = format!("nnue {} {} 1", INPUT_SIZE, HIDDEN_SIZE);
+        if header != expected {
+            return Err(invalid(&format!(
+                "Weights have header \"{}\" but the network is \"{}\"",
+                header, expected
+            )));
+        }
+        let values = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|value| value.parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()
+            .map_err(|_| invalid("Weights must be integers"))?;
+        if values.len() != (INPUT_SIZE + 2) * HIDDEN_SIZE + 1 {
+            return Err(invalid(&format!(
+                "Wrong number of weights: {}",
+                values.len()
+            )));
+        }
+
+        let mut chunks = values.chunks_exact(HIDDEN_SIZE);
+        let mut next_layer =
+            || -> [i32; HIDDEN_SIZE] { chunks.next().unwrap().try_into().unwrap() };
+        let input_weights = (0..INPUT_SIZE).map(|_| next_layer()).collect();
+        let hidden_biases = next_layer();
+        let output_weights = next_layer();
+        Ok(Network {
+            input_weights,
+            hidden_biases,
+            output_weights,
+            output_bias: values[values.len() - 1],
+        })
+    }
+
+    /// The hidden layer of `board`, computed from scratch
+    pub(crate) fn accumulator(&self, board: &Board) -> NnueAccumulator {
+        let mut accumulator = NnueAccumulator {
+            hidden: self.hidden_biases,
+        };
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                for square in board.pieces(piece) & board.color_combined(color) {
+                    self.update(&mut accumulator, piece, color, square, true);
+                }
+            }
+        }
+        accumulator
+    }
+
+    /// The hidden layer of the position after `chess_move` is made on `board`
+    pub(crate) fn after_move(
+        &self,
+        mut accumulator: NnueAccumulator,
+        board: &Board,
+        chess_move: ChessMove,
+    ) -> NnueAccumulator {
+        for_each_piece_change(board, chess_move, |piece, color, square, added| {
+            self.update(&mut accumulator, piece, color, square, added);
+        });
+        accumulator
+    }
+
+    /// The eval in centipawns from white's point of view
+    pub(crate) fn evaluate(&self, accumulator: &NnueAccumulator) -> i32 {
+        // Summed in i64, as large output weights of a trained network can take the sum past
+        // i32 before it is scaled down
+        let output = accumulator
+            .hidden
+            .iter()
+            .zip(self.output_weights)
+            .map(|(&hidden, weight)| hidden.clamp(0, HIDDEN_ONE) as i64 * weight as i64)
+            .sum::<i64>()
+            + self.output_bias as i64;
+        (output * OUTPUT_SCALE as i64 / (HIDDEN_ONE * OUTPUT_ONE) as i64)
+            .clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    fn update(
+        &self,
+        accumulator: &mut NnueAccumulator,
+        piece: Piece,
+        color: Color,
+        square: Square,
+        added: bool,
+    ) {
+        let input = color.to_index() * 384 + piece.to_index() * 64 + square.to_index();
+        let sign = if added { 1 } else { -1 };
+        for (hidden, weight) in accumulator.hidden.iter_mut().zip(self.input_weights[input]) {
+            *hidden += sign * weight;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Network, NnueAccumulator, HIDDEN_ONE, HIDDEN_SIZE, INPUT_SIZE, OUTPUT_ONE, OUTPUT_SCALE,
+    };
+
+    fn network(output_weight: i32, output_bias: i32) -> Network {
+        Network {
+            input_weights: vec![[0; HIDDEN_SIZE]; INPUT_SIZE],
+            hidden_biases: [0; HIDDEN_SIZE],
+            output_weights: [output_weight; HIDDEN_SIZE],
+            output_bias,
+        }
+    }
+
+    #[test]
+    fn large_weights_do_not_overflow() {
+        let accumulator = NnueAccumulator {
+            hidden: [HIDDEN_ONE; HIDDEN_SIZE],
+        };
+        // 32 * 255 * 1_000_000 * 400 is far past i32
+        let expected = HIDDEN_SIZE as i64 * 1_000_000 * OUTPUT_SCALE as i64 / OUTPUT_ONE as i64;
+        assert_eq!(
+            network(1_000_000, 0).evaluate(&accumulator) as i64,
+            expected
+        );
+        assert_eq!(
+            network(-1_000_000, 0).evaluate(&accumulator) as i64,
+            -expected
+        );
+        assert_eq!(network(i32::MAX, i32::MAX).evaluate(&accumulator), i32::MAX);
+    }
+}
@@ -0,0 +1,150 @@
+use chess::{get_pawn_attacks, BitBoard, Board, Color, Piece, Square, EMPTY};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::file_masks::FILES;
+use crate::common::utils::{adjacent_files, relative_rank, taper};
+
+/// Bonus of a passed pawn by how many ranks it has advanced, in the middlegame and endgame
+pub(crate) const DEFAULT_MG_RANK_BONUS: [i32; 8] = [0, 5, 10, 15, 30, 50, 80, 0];
+pub(crate) const DEFAULT_EG_RANK_BONUS: [i32; 8] = [0, 10, 20, 35, 60, 100, 160, 0];
+/// Extra bonus for a passed pawn defended by a pawn
+pub(crate) const DEFAULT_PROTECTED_BONUS: i32 = 15;
+/// Extra bonus for a passed pawn with another passed pawn on an adjacent file
+pub(crate) const DEFAULT_CONNECTED_BONUS: i32 = 10;
+/// Bonus of a candidate passed pawn by how many ranks it has advanced, in the middlegame and
+/// endgame. A candidate on the sixth rank or further is as good as passed, or blocked for good.
+pub(crate) const DEFAULT_CANDIDATE_MG_RANK_BONUS: [i32; 8] = [0, 2, 4, 6, 12, 20, 0, 0];
+pub(crate) const DEFAULT_CANDIDATE_EG_RANK_BONUS: [i32; 8] = [0, 5, 10, 15, 25, 40, 0, 0];
+/// Penalty for a passed pawn with an enemy piece right in front of it
+pub(crate) const DEFAULT_BLOCKADE_PENALTY: i32 = 10;
+/// Used instead of `DEFAULT_BLOCKADE_PENALTY` for a knight, which blockades best since it
+/// doesn't need the file and can't be driven away by the pawn
+pub(crate) const DEFAULT_KNIGHT_BLOCKADE_PENALTY: i32 = 25;
+
+/// The pawns of `own_pawns`, of `color`, that no pawn of `enemy_pawns` can stop
+pub(crate) fn passed_pawns(own_pawns: BitBoard, enemy_pawns: BitBoard, color: Color) -> BitBoard {
+    let enemy_pawns = enemy_pawns.to_size(0) as u64;
+    own_pawns
+        .filter(|&square| front_span(color, square) & enemy_pawns == 0)
+        .fold(BitBoard::new(0), |passers, square| {
+            passers | BitBoard::from_square(square)
+        })
+}
+
+/// The pawns of `own_pawns`, of `color`, that aren't passed but have no enemy pawn in front of
+/// them on their own file, and at least as many own pawns beside or behind them on the adjacent
+/// files as there are enemy pawns ahead of them there. Pushing them can make a passed pawn.
+pub(crate) fn candidate_passers(
+    own_pawns: BitBoard,
+    enemy_pawns: BitBoard,
+    color: Color,
+) -> BitBoard {
+    let (own, enemy) = (own_pawns.to_size(0) as u64, enemy_pawns.to_size(0) as u64);
+    own_pawns
+        .filter(|&square| {
+            let file = square.get_file().to_index();
+            let ahead = ranks_ahead(color, square.get_rank().to_index());
+            let sentries = (enemy & adjacent_files(file) & ahead).count_ones();
+            let helpers = (own & adjacent_files(file) & !ahead).count_ones();
+            enemy & FILES[file] & ahead == 0 && sentries > 0 && helpers >= sentries
+        })
+        .fold(BitBoard::new(0), |candidates, square| {
+            candidates | BitBoard::from_square(square)
+        })
+}
+
+/// The bonus of white's passed and candidate passed pawns minus that of black's, tapered by
+/// `phase`. `passers` and `candidates` are those pawns of each color by color index, e.g. from
+/// the pawn hash.
+pub(crate) fn passed_pawn_bonus(
+    board: &Board,
+    passers: [BitBoard; 2],
+    candidates: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    side_bonus(board, Color::White, passers, candidates, phase, params)
+        - side_bonus(board, Color::Black, passers, candidates, phase, params)
+}
+
+fn side_bonus(
+    board: &Board,
+    color: Color,
+    passers: [BitBoard; 2],
+    candidates: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    let own_pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+    let passers = passers[color.to_index()];
+    let passer_files = passers.fold(0, |files, square: Square| {
+        files | FILES[square.get_file().to_index()]
+    });
+
+    let passer_bonus: i32 = passers
+        .map(|square| {
+            let rank = relative_rank(square, color);
+            let mut bonus = taper(
+                (
+                    params.passed_mg_rank_bonus[rank],
+                    params.passed_eg_rank_bonus[rank],
+                ),
+                phase,
+            );
+            // Pawns of our color that attack the square are the ones defending it
+            if get_pawn_attacks(square, !color, own_pawns) != EMPTY {
+                bonus += params.passed_protected_bonus;
+            }
+            if adjacent_files(square.get_file().to_index()) & passer_files != 0 {
+                bonus += params.passed_connected_bonus;
+            }
+            bonus - blockade_penalty(board, color, square, params)
+        })
+        .sum();
+    let candidate_bonus: i32 = candidates[color.to_index()]
+        .map(|square| {
+            let rank = relative_rank(square, color);
+            taper(
+                (
+                    params.candidate_mg_rank_bonus[rank],
+                    params.candidate_eg_rank_bonus[rank],
+                ),
+                phase,
+            )
+        })
+        .sum();
+    passer_bonus + candidate_bonus
+}
+
+/// The penalty of the passed pawn of `color` on `square` for an enemy piece standing in front
+/// of it
+fn blockade_penalty(board: &Board, color: Color, square: Square, params: &EvalParams) -> i32 {
+    let stop = match color {
+        Color::White => square.up(),
+        Color::Black => square.down(),
+    };
+    let Some(stop) = stop.filter(|&stop| board.color_on(stop) == Some(!color)) else {
+        return 0;
+    };
+    if board.piece_on(stop) == Some(Piece::Knight) {
+        params.knight_blockade_penalty
+    } else {
+        params.blockade_penalty
+    }
+}
+
+/// The ranks in front of `rank` as seen by `color`
+fn ranks_ahead(color: Color, rank: usize) -> u64 {
+    match color {
+        Color::White if rank < 7 => u64::MAX << (8 * (rank + 1)),
+        Color::Black => (1u64 << (8 * rank)) - 1,
+        Color::White => 0,
+    }
+}
+
+/// The squares in front of a pawn of `color` on `square`, on its own file and the adjacent
+/// ones. The pawn is passed if no enemy pawn is on them.
+fn front_span(color: Color, square: Square) -> u64 {
+    let file = square.get_file().to_index();
+    (FILES[file] | adjacent_files(file)) & ranks_ahead(color, square.get_rank().to_index())
+}
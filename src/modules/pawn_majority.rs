@@ -0,0 +1,62 @@
+use chess::{BitBoard, Board, Color, Piece};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::file_masks::FILES;
+use crate::common::utils::taper;
+
+/// Endgame bonus for having a passed pawn far away from every enemy pawn. The enemy king has to
+/// go after it, leaving the other pawns to our king.
+pub(crate) const DEFAULT_OUTSIDE_PASSER_BONUS: i32 = 40;
+/// Endgame bonus for each wing where our pawns outnumber the enemy pawns, on more files than
+/// there are enemy pawns there, so that the majority can make a passed pawn
+pub(crate) const DEFAULT_PAWN_MAJORITY_BONUS: i32 = 20;
+/// A passed pawn is outside when every enemy pawn is at least this many files away
+const OUTSIDE_FILES: usize = 3;
+/// The a to d files and the e to h files
+const WINGS: [u64; 2] = [0x0F0F_0F0F_0F0F_0F0F, 0xF0F0_F0F0_F0F0_F0F0];
+
+/// The bonus of white's outside passed pawn and pawn majorities minus that of black's, in the
+/// endgame only by `phase`, so that it counts fully in pawn endings. `passers` are the passed
+/// pawns of each color by color index, e.g. from the pawn hash.
+pub(crate) fn pawn_majority_score(
+    board: &Board,
+    passers: [BitBoard; 2],
+    phase: i32,
+    params: &EvalParams,
+) -> i32 {
+    let bonus = side_bonus(board, Color::White, passers, params)
+        - side_bonus(board, Color::Black, passers, params);
+    taper((0, bonus), phase)
+}
+
+fn side_bonus(board: &Board, color: Color, passers: [BitBoard; 2], params: &EvalParams) -> i32 {
+    let pawns = |color| (board.pieces(Piece::Pawn) & board.color_combined(color)).to_size(0) as u64;
+    let (own, enemy) = (pawns(color), pawns(!color));
+
+    let enemy_files: Vec<usize> = (0..FILES.len())
+        .filter(|&file| enemy & FILES[file] != 0)
+        .collect();
+    let mut own_passers = passers[color.to_index()];
+    let outside_passer = !enemy_files.is_empty()
+        && own_passers.any(|passer| {
+            let file = passer.get_file().to_index();
+            enemy_files
+                .iter()
+                .all(|&enemy_file| enemy_file.abs_diff(file) >= OUTSIDE_FILES)
+        });
+
+    let majorities = WINGS
+        .iter()
+        .filter(|&&wing| {
+            let enemy_pawns = (enemy & wing).count_ones();
+            enemy_pawns > 0 && occupied_files(own & wing) > enemy_pawns
+        })
+        .count() as i32;
+
+    outside_passer as i32 * params.outside_passer_bonus + majorities * params.pawn_majority_bonus
+}
+
+/// How many files have at least one of `pawns`
+fn occupied_files(pawns: u64) -> u32 {
+    FILES.iter().filter(|&&file| pawns & file != 0).count() as u32
+}
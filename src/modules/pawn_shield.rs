@@ -0,0 +1,49 @@
+use chess::{BitBoard, Board, Color, Piece, Square};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::{relative_rank, taper};
+
+/// Middlegame penalty for the nearest own pawn in front of the king on a shield file, by how
+/// many ranks it is ahead of the king. Index 0 stands for no pawn at all.
+pub(crate) const DEFAULT_SHIELD_PENALTY: [i32; 8] = [35, 0, 10, 20, 25, 25, 25, 25];
+/// Middlegame penalty for the nearest enemy pawn in front of the king on a shield file, by how
+/// many ranks it is ahead of the king. Index 0 stands for no pawn at all.
+pub(crate) const DEFAULT_STORM_PENALTY: [i32; 8] = [0, 0, 30, 20, 10, 0, 0, 0];
+
+/// The pawn shield and storm score of white's king minus that of black's, tapered by `phase`.
+/// Only kings that have gone to either wing have a shield to speak of.
+pub(crate) fn pawn_shield_score(board: &Board, phase: i32, params: &EvalParams) -> i32 {
+    let shield = side_score(board, Color::White, params) - side_score(board, Color::Black, params);
+    taper((shield, 0), phase)
+}
+
+fn side_score(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let king = board.king_square(color);
+    let king_file = king.get_file().to_index();
+    if relative_rank(king, color) > 1 || (3..=4).contains(&king_file) {
+        return 0;
+    }
+    let own_pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+    let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+    // The three files in front of the king, shifted inwards when it is on the edge
+    let middle = king_file.clamp(1, 6);
+    (middle - 1..=middle + 1)
+        .map(|file| {
+            let shield = nearest_ahead(king, own_pawns, file, color);
+            let storm = nearest_ahead(king, enemy_pawns, file, color);
+            -params.shield_penalty[shield] - params.storm_penalty[storm]
+        })
+        .sum()
+}
+
+/// How many ranks the nearest of `pawns` on `file` is in front of `king` of `color`, or 0 if
+/// there is none
+fn nearest_ahead(king: Square, pawns: BitBoard, file: usize, color: Color) -> usize {
+    let king_rank = relative_rank(king, color);
+    pawns
+        .filter(|pawn| pawn.get_file().to_index() == file)
+        .map(|pawn| relative_rank(pawn, color))
+        .filter(|&rank| rank > king_rank)
+        .min()
+        .map_or(0, |rank| rank - king_rank)
+}
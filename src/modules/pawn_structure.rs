@@ -0,0 +1,55 @@
+use chess::Color;
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::file_masks::FILES;
+use crate::common::utils::adjacent_files;
+
+/// Penalty for every pawn without own pawns on the adjacent files, in the middlegame and endgame
+pub(crate) const DEFAULT_ISOLATED_PENALTY: (i32, i32) = (10, 20);
+/// Penalty for every pawn on a file beyond the first
+pub(crate) const DEFAULT_DOUBLED_PENALTY: (i32, i32) = (10, 25);
+/// Penalty for every pawn whose own pawns on the adjacent files are all ahead of it, so that
+/// none of them can ever defend it
+pub(crate) const DEFAULT_BACKWARD_PENALTY: (i32, i32) = (8, 10);
+
+/// Middlegame and endgame scores of the structure of one side's pawns, given as a bitboard.
+/// Only depends on those pawns, so that it can be hashed by them.
+pub(crate) fn pawn_structure_score(pawns: u64, color: Color, params: &EvalParams) -> (i32, i32) {
+    let (mut mg, mut eg) = (0, 0);
+    let mut penalize = |(mg_penalty, eg_penalty): (i32, i32), count: i32| {
+        mg -= mg_penalty * count;
+        eg -= eg_penalty * count;
+    };
+    for (file, &file_mask) in FILES.iter().enumerate() {
+        let on_file = pawns & file_mask;
+        if on_file == 0 {
+            continue;
+        }
+        let count = on_file.count_ones() as i32;
+        penalize(params.doubled_penalty, count - 1);
+
+        let neighbours = pawns & adjacent_files(file);
+        if neighbours == 0 {
+            penalize(params.isolated_penalty, count);
+            continue;
+        }
+        let mut remaining = on_file;
+        while remaining != 0 {
+            let rank = remaining.trailing_zeros() / 8;
+            remaining &= remaining - 1;
+            if neighbours & level_or_behind(color, rank) == 0 {
+                penalize(params.backward_penalty, 1);
+            }
+        }
+    }
+    (mg, eg)
+}
+
+/// The ranks from `rank` back to where the pawns of `color` start
+fn level_or_behind(color: Color, rank: u32) -> u64 {
+    match color {
+        Color::White if rank >= 7 => u64::MAX,
+        Color::White => (1 << (8 * (rank + 1))) - 1,
+        Color::Black => u64::MAX << (8 * rank),
+    }
+}
@@ -0,0 +1,15 @@
+use crate::algorithms::utils::Evaluation;
+
+/// A window just wide enough to tell if the move is better than the best move so far
+pub(crate) fn null_window(alpha: i32, beta: i32) -> (i32, i32) {
+    (alpha, alpha.saturating_add(1).min(beta))
+}
+
+/// Whether the null window search found the move to be better than the best move so far
+/// without causing a cutoff, in which case its exact eval is needed and it is searched again
+/// with the full window
+pub(crate) fn needs_research(alpha: i32, beta: i32, evaluation: &Evaluation) -> bool {
+    evaluation
+        .eval
+        .is_some_and(|eval| eval > alpha && eval < beta)
+}
@@ -0,0 +1,29 @@
+use crate::algorithms::utils::{Evaluation, NodeType};
+
+/// How far past the window the shallow search has to get, in centipawns
+pub(crate) const DEFAULT_PROBCUT_MARGIN: i32 = 100;
+/// Only nodes at least this far from the leaves are tried
+pub(crate) const DEFAULT_PROBCUT_MIN_DEPTH: u32 = 5;
+/// How much shallower than the node the shallow search is
+pub(crate) const REDUCTION: u32 = 4;
+/// One in this many cutoffs is searched in full anyway, to check that the cutoff was right.
+/// Counted by `Stats::probcut_prunes`.
+pub(crate) const VERIFICATION_INTERVAL: u32 = 16;
+
+/// Whether ProbCut should be tried at this node. Nodes on the principal variation need exact
+/// evals, so they are never cut.
+pub(crate) fn applies(node_type: NodeType, depth: u32, min_depth: u32) -> bool {
+    node_type != NodeType::Pv && depth >= min_depth.max(REDUCTION + 1)
+}
+
+/// The bound that the shallow search has to beat, `margin` past beta. None if the window is
+/// unbounded above.
+pub(crate) fn raised_bound(beta: i32, margin: i32) -> Option<i32> {
+    (beta != i32::MAX).then(|| beta.saturating_add(margin))
+}
+
+/// Whether the shallow search got past the raised bound, making a cutoff of the full depth
+/// search likely
+pub(crate) fn proves_cutoff(bound: i32, evaluation: &Evaluation) -> bool {
+    evaluation.eval.is_some_and(|eval| eval >= bound)
+}
@@ -0,0 +1,183 @@
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard,
+    Board, ChessMove, Color, MoveGen, Piece, Square, ALL_PIECES, EMPTY,
+};
+
+use crate::common::utils::piece_value;
+
+/// Quiescence search stops after this many captures even if there are more
+pub(crate) const MAX_QUIESCENCE_DEPTH: u32 = 8;
+
+/// All captures, most valuable victim first and least valuable attacker first among those
+pub(crate) fn ordered_captures(board: &Board) -> Vec<ChessMove> {
+    let mut legal_moves = MoveGen::new_legal(board);
+    legal_moves.set_iterator_mask(*board.color_combined(!board.side_to_move()));
+    let mut captures: Vec<ChessMove> = legal_moves.collect();
+    captures.sort_by_key(|chess_move| {
+        let victim = board.piece_on(chess_move.get_dest()).map_or(0, piece_value);
+        let attacker = board
+            .piece_on(chess_move.get_source())
+            .map_or(0, piece_value);
+        (u32::MAX - victim, attacker)
+    });
+    captures
+}
+
+/// All moves out of check, the captures first as ordered by `ordered_captures`. Standing pat is
+/// no option in check, as the eval doesn't see the threat to the king, so the quiet evasions
+/// have to be searched too.
+pub(crate) fn evasions(board: &Board) -> Vec<ChessMove> {
+    let mut evasions = ordered_captures(board);
+    let mut quiet_moves = MoveGen::new_legal(board);
+    quiet_moves.set_iterator_mask(!*board.color_combined(!board.side_to_move()));
+    evasions.extend(quiet_moves);
+    evasions
+}
+
+/// Quiet moves that give check, for the first layer of quiescence search
+pub(crate) fn quiet_checks(board: &Board) -> Vec<ChessMove> {
+    MoveGen::new_legal(board)
+        .filter(|chess_move| {
+            board.piece_on(chess_move.get_dest()).is_none()
+                && board.make_move_new(*chess_move).checkers().popcnt() > 0
+        })
+        .collect()
+}
+
+/// All pieces of both colors in `occupied` that attack `square`
+fn attackers(board: &Board, square: Square, occupied: BitBoard) -> BitBoard {
+    let pawns = board.pieces(Piece::Pawn);
+    let diagonal = board.pieces(Piece::Bishop) | board.pieces(Piece::Queen);
+    let straight = board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    let attackers = get_pawn_attacks(
+        square,
+        Color::White,
+        pawns & board.color_combined(Color::Black),
+    ) | get_pawn_attacks(
+        square,
+        Color::Black,
+        pawns & board.color_combined(Color::White),
+    ) | (get_knight_moves(square) & board.pieces(Piece::Knight))
+        | (get_bishop_moves(square, occupied) & diagonal)
+        | (get_rook_moves(square, occupied) & straight)
+        | (get_king_moves(square) & board.pieces(Piece::King));
+    attackers & occupied
+}
+
+/// Static exchange evaluation, the material the side to move wins by capturing on the
+/// destination square if both sides then keep recapturing with their least valuable piece
+/// for as long as it pays off. Pins and checks are ignored.
+pub(crate) fn static_exchange_evaluation(board: &Board, chess_move: ChessMove) -> i32 {
+    let square = chess_move.get_dest();
+    let mut occupied = *board.combined() ^ BitBoard::from_square(chess_move.get_source());
+    let mut attacker = board
+        .piece_on(chess_move.get_source())
+        .expect("Move has a piece");
+    let mut side = !board.side_to_move();
+    let mut gains = vec![board.piece_on(square).map_or(0, piece_value) as i32];
+
+    loop {
+        let side_attackers = attackers(board, square, occupied) & board.color_combined(side);
+        if side_attackers == EMPTY {
+            break;
+        }
+        // The king can't recapture if the other side can capture it back
+        if attacker == Piece::King {
+            if gains.len() > 1 {
+                gains.pop();
+            }
+            break;
+        }
+        let least_valuable = ALL_PIECES
+            .into_iter()
+            .find(|&piece| side_attackers & board.pieces(piece) != EMPTY)
+            .expect("Attackers are pieces");
+        gains.push(piece_value(attacker) as i32 - gains.last().unwrap());
+        occupied ^=
+            BitBoard::from_square((side_attackers & board.pieces(least_valuable)).to_square());
+        attacker = least_valuable;
+        side = !side;
+    }
+
+    // Either side may stop recapturing if that loses material
+    while gains.len() > 1 {
+        let last = gains.pop().unwrap();
+        let previous = gains.last_mut().unwrap();
+        *previous = -(-*previous).max(last);
+    }
+    gains[0]
+}
+
+/// Updates the window with the stand-pat score, the eval if the side to move stops capturing.
+/// Returns true if the stand-pat is already good enough for a cutoff.
+pub(crate) fn stand_pat(stand_pat: i32, alpha: &mut i32, beta: i32) -> bool {
+    if stand_pat >= beta {
+        return true;
+    }
+    *alpha = (*alpha).max(stand_pat);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::{Board, ChessMove, MoveGen};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{evasions, ordered_captures};
+
+    fn check_evasions(board: &Board) {
+        let mut evasions: Vec<String> = evasions(board).iter().map(ChessMove::to_string).collect();
+        let mut legal_moves: Vec<String> = MoveGen::new_legal(board)
+            .map(|chess_move| chess_move.to_string())
+            .collect();
+        evasions.sort();
+        legal_moves.sort();
+        assert_eq!(evasions, legal_moves, "evasions of {}", board);
+    }
+
+    #[test]
+    fn evasions_are_every_legal_move() {
+        for fen in [
+            // Perpetual check by the queen, only quiet evasions
+            "b1r5/6pk/7p/5Q2/8/8/8/6K1 b - - 0 1",
+            // Double check, only king moves
+            "4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1",
+            // The checking pawn captured en passant
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+            // The checking rook captured with every promotion
+            "3r2k1/4P3/8/8/8/8/8/3K4 w - - 0 1",
+        ] {
+            let board = Board::from_str(fen).unwrap();
+            assert!(board.checkers().popcnt() > 0, "{} is not in check", fen);
+            check_evasions(&board);
+        }
+    }
+
+    #[test]
+    fn evasions_match_random_games() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let mut board = Board::default();
+            for _ in 0..200 {
+                if board.checkers().popcnt() > 0 {
+                    check_evasions(&board);
+                }
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+                if moves.is_empty() {
+                    break;
+                }
+                board = board.make_move_new(moves[rng.gen_range(0..moves.len())]);
+            }
+        }
+    }
+
+    #[test]
+    fn perpetual_check_has_only_quiet_evasions() {
+        let board = Board::from_str("b1r5/6pk/7p/5Q2/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(ordered_captures(&board).is_empty());
+        assert_eq!(evasions(&board).len(), 3);
+    }
+}
@@ -0,0 +1,59 @@
+use chess::{get_rook_moves, BitBoard, Board, Color, Piece, Square};
+
+use crate::algorithms::eval_params::EvalParams;
+
+/// Bonus for each rook behind a passed pawn of its own color, supporting its advance
+pub(crate) const DEFAULT_BEHIND_OWN_PASSER_BONUS: i32 = 20;
+/// Bonus for each rook behind a passed pawn of the enemy, holding it back from behind
+pub(crate) const DEFAULT_BEHIND_ENEMY_PASSER_BONUS: i32 = 15;
+/// Bonus for two rooks defending each other along a rank or file
+pub(crate) const DEFAULT_CONNECTED_ROOKS_BONUS: i32 = 15;
+
+/// The bonus of white's rooks being behind passed pawns and connected minus that of black's.
+/// `passers` are the passed pawns of each color by color index, e.g. from the pawn hash.
+pub(crate) fn rook_coordination_bonus(
+    board: &Board,
+    passers: [BitBoard; 2],
+    params: &EvalParams,
+) -> i32 {
+    side_bonus(board, Color::White, passers, params)
+        - side_bonus(board, Color::Black, passers, params)
+}
+
+fn side_bonus(board: &Board, color: Color, passers: [BitBoard; 2], params: &EvalParams) -> i32 {
+    let rooks = board.pieces(Piece::Rook) & board.color_combined(color);
+    let mut bonus = 0;
+    for rook in rooks {
+        // Squares the rook sees up to and including the first piece in every direction
+        let seen = get_rook_moves(rook, *board.combined());
+        for passer_color in [color, !color] {
+            let behind_passers = (seen & passers[passer_color.to_index()])
+                .filter(|&passer| is_behind(rook, passer, passer_color))
+                .count() as i32;
+            bonus += behind_passers
+                * if passer_color == color {
+                    params.behind_own_passer_bonus
+                } else {
+                    params.behind_enemy_passer_bonus
+                };
+        }
+        // Each connected pair is seen from both rooks, so only count it from the lower one
+        let connected = (seen & rooks)
+            .filter(|other| other.to_index() > rook.to_index())
+            .count() as i32;
+        bonus += connected * params.connected_rooks_bonus;
+    }
+    bonus
+}
+
+/// Whether `rook` is on the file of a pawn of `pawn_color` on `pawn`, on the side the pawn
+/// moves away from
+fn is_behind(rook: Square, pawn: Square, pawn_color: Color) -> bool {
+    if rook.get_file() != pawn.get_file() {
+        return false;
+    }
+    match pawn_color {
+        Color::White => rook.get_rank().to_index() < pawn.get_rank().to_index(),
+        Color::Black => rook.get_rank().to_index() > pawn.get_rank().to_index(),
+    }
+}
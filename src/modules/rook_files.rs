@@ -0,0 +1,94 @@
+use chess::{Board, Color, Piece};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::constants::file_masks::FILES;
+
+/// Bonus for each rook on a file without pawns, in centipawns
+pub(crate) const DEFAULT_OPEN_FILE_BONUS: i32 = 25;
+/// Bonus for each rook on a file with only enemy pawns, in centipawns
+pub(crate) const DEFAULT_SEMI_OPEN_FILE_BONUS: i32 = 10;
+
+/// The bonus of white's rooks on open and semi-open files minus that of black's
+pub(crate) fn rook_file_bonus(board: &Board, params: &EvalParams) -> i32 {
+    side_bonus(board, Color::White, params) - side_bonus(board, Color::Black, params)
+}
+
+fn side_bonus(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let all_pawns = board.pieces(Piece::Pawn).to_size(0) as u64;
+    let own_pawns = (board.pieces(Piece::Pawn) & board.color_combined(color)).to_size(0) as u64;
+    let rooks = (board.pieces(Piece::Rook) & board.color_combined(color)).to_size(0) as u64;
+    FILES
+        .iter()
+        .map(|&file| {
+            let rooks_on_file = (rooks & file).count_ones() as i32;
+            if all_pawns & file == 0 {
+                rooks_on_file * params.open_file_bonus
+            } else if own_pawns & file == 0 {
+                rooks_on_file * params.semi_open_file_bonus
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::Board;
+
+    use super::rook_file_bonus;
+    use crate::algorithms::eval_params::EvalParams;
+
+    fn bonus(fen: &str) -> i32 {
+        rook_file_bonus(&Board::from_str(fen).unwrap(), &EvalParams::default())
+    }
+
+    #[test]
+    fn open_edge_files() {
+        let params = EvalParams::default();
+        assert_eq!(
+            bonus("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"),
+            params.open_file_bonus
+        );
+        assert_eq!(
+            bonus("4k2r/8/8/8/8/8/8/4K3 w - - 0 1"),
+            -params.open_file_bonus
+        );
+        // Pawns on the other edge file don't close it
+        assert_eq!(
+            bonus("4k3/8/8/8/8/8/P7/4K2R w - - 0 1"),
+            params.open_file_bonus
+        );
+        assert_eq!(
+            bonus("r3k3/8/8/8/8/8/7P/4K3 w - - 0 1"),
+            -params.open_file_bonus
+        );
+    }
+
+    #[test]
+    fn semi_open_edge_files() {
+        let params = EvalParams::default();
+        assert_eq!(
+            bonus("4k3/p7/8/8/8/8/8/R3K3 w - - 0 1"),
+            params.semi_open_file_bonus
+        );
+        assert_eq!(
+            bonus("4k2r/8/8/8/8/8/7P/4K3 w - - 0 1"),
+            -params.semi_open_file_bonus
+        );
+        assert_eq!(
+            bonus("r3k3/8/8/8/8/8/P7/4K3 w - - 0 1"),
+            -params.semi_open_file_bonus
+        );
+    }
+
+    #[test]
+    fn edge_files_closed_by_own_pawns() {
+        assert_eq!(bonus("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1"), 0);
+        assert_eq!(bonus("4k3/p7/8/8/8/8/P7/R3K3 w - - 0 1"), 0);
+        assert_eq!(bonus("4k2r/7p/8/8/8/8/8/4K3 w - - 0 1"), 0);
+        assert_eq!(bonus("4k2r/7p/8/8/8/8/7P/4K3 w - - 0 1"), 0);
+    }
+}
@@ -0,0 +1,33 @@
+use chess::{get_rank, get_rook_moves, Board, Color, Piece, Rank, EMPTY};
+
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::{relative_rank, taper};
+
+/// Bonus for a rook on the seventh rank, the second for black, in the middlegame and endgame.
+/// Only given while the enemy king is held on its back rank or enemy pawns are left to attack
+/// on the seventh.
+pub(crate) const DEFAULT_ROOK_ON_SEVENTH_BONUS: (i32, i32) = (20, 40);
+
+/// The bonus of white's rooks on the seventh rank minus that of black's, tapered by `phase`
+pub(crate) fn rook_on_seventh_bonus(board: &Board, phase: i32, params: &EvalParams) -> i32 {
+    side_bonus(board, Color::White, phase, params) - side_bonus(board, Color::Black, phase, params)
+}
+
+fn side_bonus(board: &Board, color: Color, phase: i32, params: &EvalParams) -> i32 {
+    let seventh = get_rank(match color {
+        Color::White => Rank::Seventh,
+        Color::Black => Rank::Second,
+    });
+    let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+    if relative_rank(board.king_square(!color), !color) != 0 && enemy_pawns & seventh == EMPTY {
+        return 0;
+    }
+    let rooks = board.pieces(Piece::Rook) & board.color_combined(color) & seventh;
+    let mut bonus = rooks.popcnt() as i32 * taper(params.rook_on_seventh_bonus, phase);
+    // Two rooks on the seventh that defend each other are doubled
+    let mut remaining = rooks;
+    if remaining.any(|rook| get_rook_moves(rook, *board.combined()) & rooks != EMPTY) {
+        bonus *= 2;
+    }
+    bonus
+}
@@ -0,0 +1,16 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Threads that the root moves are split between unless set on the algorithm
+pub(crate) const DEFAULT_SEARCH_THREADS: usize = 4;
+/// Shallower iterations are done before the threads would have started
+pub(crate) const MIN_DEPTH: u32 = 3;
+
+/// The window for a root move, narrowed by `best`, the best eval of the finished siblings
+pub(crate) fn window(window: (i32, i32), best: &AtomicI32) -> (i32, i32) {
+    (window.0.max(best.load(Ordering::Relaxed)), window.1)
+}
+
+/// Lets the threads still searching know about the eval of a finished root move
+pub(crate) fn record_eval(best: &AtomicI32, eval: i32) {
+    best.fetch_max(eval, Ordering::Relaxed);
+}
@@ -1,15 +1,116 @@
-use chess::Board;
-
-pub fn calculate(
-    num_extensions: u32,
-    num_legal_moves: usize,
-    new_board: Board,
-) -> u32 {
-    if num_extensions > 3 {
-        0
-    } else if num_legal_moves <= 3 || new_board.checkers().popcnt() >= 2 {
-        1
-    } else {
-        0
+use chess::{Board, ChessMove, Piece};
+
+use crate::algorithms::attack_maps::AttackMaps;
+use crate::algorithms::utils::LastMove;
+use crate::common::utils::piece_value;
+use crate::modules::king_danger::{king_danger, DEFAULT_KING_ATTACK_WEIGHTS};
+
+/// Boards with at most this much material besides pawns and kings, both sides together, count
+/// as endgames
+const ENDGAME_MATERIAL: u32 = 20;
+
+/// Which moves are searched deeper, and how many times a single line may be extended
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtensionPolicy {
+    /// Extend moves that give check
+    pub(crate) check: bool,
+    /// Extend moves that give double check, even if other checks aren't extended
+    pub(crate) double_check: bool,
+    /// Extend all moves of nodes with at most this many legal moves. 1 gives one-reply
+    /// extensions.
+    pub(crate) forced_moves: Option<usize>,
+    /// Extend captures back on the square where the opponent just captured
+    pub(crate) recapture: bool,
+    /// Extend pawn pushes to the rank before promotion, where the promotion often falls just
+    /// past the horizon
+    pub(crate) pawn_push: bool,
+    /// Extend moves after which the king of the side to move is in at least this much danger,
+    /// by `king_danger` with the default attack weights, so that sharp attacks are searched
+    /// deeper
+    pub(crate) king_danger: Option<i32>,
+    /// Most extensions in a line before the endgame
+    pub(crate) max_extensions_middlegame: u32,
+    /// Most extensions in a line in the endgame, where lines are narrower and extending them is
+    /// cheaper
+    pub(crate) max_extensions_endgame: u32,
+}
+
+impl Default for ExtensionPolicy {
+    fn default() -> Self {
+        ExtensionPolicy {
+            check: false,
+            double_check: true,
+            forced_moves: Some(3),
+            recapture: false,
+            pawn_push: false,
+            king_danger: None,
+            max_extensions_middlegame: 4,
+            max_extensions_endgame: 4,
+        }
+    }
+}
+
+impl ExtensionPolicy {
+    /// How many plies `chess_move` from `board` to `new_board` should be extended by.
+    /// `last_move` is the move that led to `board`.
+    pub(crate) fn calculate(
+        &self,
+        num_extensions: u32,
+        num_legal_moves: usize,
+        board: &Board,
+        chess_move: ChessMove,
+        new_board: &Board,
+        last_move: Option<LastMove>,
+    ) -> u32 {
+        let max_extensions = if is_endgame(new_board) {
+            self.max_extensions_endgame
+        } else {
+            self.max_extensions_middlegame
+        };
+        if num_extensions >= max_extensions {
+            return 0;
+        }
+        let checkers = new_board.checkers().popcnt();
+        let extend = self.forced_moves.is_some_and(|max| num_legal_moves <= max)
+            || self.check && checkers >= 1
+            || self.double_check && checkers >= 2
+            || self.recapture && is_recapture(board, chess_move, last_move)
+            || self.pawn_push && is_pawn_push_to_seventh(board, chess_move)
+            || self
+                .king_danger
+                .is_some_and(|threshold| is_king_in_danger(new_board, threshold));
+        extend as u32
     }
 }
+
+fn is_recapture(board: &Board, chess_move: ChessMove, last_move: Option<LastMove>) -> bool {
+    last_move.is_some_and(|last_move| {
+        last_move.captured.is_some()
+            && last_move.chess_move.get_dest() == chess_move.get_dest()
+            && board.piece_on(chess_move.get_dest()).is_some()
+    })
+}
+
+fn is_pawn_push_to_seventh(board: &Board, chess_move: ChessMove) -> bool {
+    board.piece_on(chess_move.get_source()) == Some(Piece::Pawn)
+        && chess_move.get_dest().get_rank() == board.side_to_move().to_seventh_rank()
+}
+
+fn is_king_in_danger(board: &Board, threshold: i32) -> bool {
+    let attack_maps = AttackMaps::new(board);
+    let danger = king_danger(
+        board,
+        &attack_maps,
+        board.side_to_move(),
+        &DEFAULT_KING_ATTACK_WEIGHTS,
+    );
+    danger >= threshold
+}
+
+fn is_endgame(board: &Board) -> bool {
+    let material: u32 = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+        .into_iter()
+        .map(|piece| board.pieces(piece).popcnt() * piece_value(piece))
+        .sum();
+    material <= ENDGAME_MATERIAL
+}
@@ -1,3 +1,29 @@
-pub fn should_skip(num_legal_moves: usize, i: usize) -> bool {
-    i as f32 > num_legal_moves as f32 * 1.
+use crate::algorithms::utils::NodeType;
+
+/// Fraction of the legal moves searched before the rest are skipped, unless set on the algorithm
+pub(crate) const DEFAULT_SKIP_FRACTION: f32 = 1.;
+/// How far below the static eval the best move so far may be before the skipped moves are
+/// searched after all, in centipawns
+const SUSPICIOUS_MARGIN: i32 = 200;
+
+pub fn should_skip(num_legal_moves: usize, i: usize, skip_fraction: f32) -> bool {
+    i as f32 > num_legal_moves as f32 * skip_fraction
+}
+
+/// Whether the moves past the skip threshold have to be searched anyway. That is when the node
+/// is on the principal variation, either expected to be or by the best move so far being inside
+/// the window starting at `alpha`, or when the best move so far is suspiciously far below the
+/// static eval, as a move that was ordered late may be the only one holding the position.
+pub(crate) fn must_search_tail(
+    node_type: NodeType,
+    alpha: i32,
+    best_eval: Option<i32>,
+    static_eval: i32,
+) -> bool {
+    let Some(best_eval) = best_eval else {
+        return true;
+    };
+    node_type == NodeType::Pv
+        || best_eval > alpha
+        || best_eval < static_eval.saturating_sub(SUSPICIOUS_MARGIN)
 }
@@ -0,0 +1,42 @@
+use chess::{BitBoard, Board, Color, ALL_PIECES};
+
+use crate::algorithms::attack_maps::{self, AttackMaps};
+use crate::algorithms::eval_params::EvalParams;
+
+/// Centipawns per attacked square by the piece type attacking it: pawn, knight, bishop, rook,
+/// queen and king. Cheaper pieces hold a square more firmly, since they can't be chased away by
+/// anything but pawns.
+pub(crate) const DEFAULT_CONTROL_WEIGHTS: [i32; 6] = [3, 2, 2, 1, 1, 0];
+/// How many extra times a central square counts
+pub(crate) const DEFAULT_CENTER_CONTROL_BONUS: i32 = 1;
+/// How many extra times a square next to the enemy king counts
+pub(crate) const DEFAULT_KING_ZONE_CONTROL_BONUS: i32 = 2;
+
+/// d4, e4, d5 and e5
+const CENTER: BitBoard = BitBoard(0x0000_0018_1800_0000);
+
+/// The weighted squares attacked by white minus those attacked by black. Unlike counting the
+/// legal moves, both sides are scored, so the term doesn't flip with the side to move.
+pub(crate) fn square_control_score(
+    board: &Board,
+    attacks: &AttackMaps,
+    params: &EvalParams,
+) -> i32 {
+    side_control(board, attacks, Color::White, params)
+        - side_control(board, attacks, Color::Black, params)
+}
+
+fn side_control(board: &Board, attacks: &AttackMaps, color: Color, params: &EvalParams) -> i32 {
+    let enemy_king_zone = attack_maps::king_zone(board, !color);
+    ALL_PIECES
+        .iter()
+        .zip(params.control_weights)
+        .map(|(&piece, weight)| {
+            let attacked = attacks.by_piece(color, piece);
+            let squares = attacked.popcnt() as i32
+                + (attacked & CENTER).popcnt() as i32 * params.center_control_bonus
+                + (attacked & enemy_king_zone).popcnt() as i32 * params.king_zone_control_bonus;
+            weight * squares
+        })
+        .sum()
+}
@@ -0,0 +1,43 @@
+use chess::{Board, Color, MoveGen};
+
+use crate::algorithms::eval;
+use crate::algorithms::eval_params::EvalParams;
+
+/// Evals at least this far from 0 count as clearly winning for one side
+pub(crate) const DEFAULT_STALEMATE_WINNING_MARGIN: i32 = 300;
+/// Taken off the winning side for each legal move the losing side is short of having
+/// `STALEMATE_TRAP_MOVES + 1`, while it isn't in check
+pub(crate) const DEFAULT_STALEMATE_TRAP_PENALTY: i32 = 40;
+/// The losing side is close to stalemate with at most this many legal moves. Kept low, since
+/// boxing the king in is also how it gets mated, and mating nets are in check anyway.
+const STALEMATE_TRAP_MOVES: usize = 1;
+
+/// How much the eval of the side that is clearly winning by `evaluation`, from white's point of
+/// view, should come down as the losing side is close to being stalemated. Returned from white's
+/// point of view and 0 unless the losing side has very few legal moves without being in check.
+pub(crate) fn stalemate_trap_score(board: &Board, evaluation: i32, params: &EvalParams) -> i32 {
+    let losing = if evaluation >= params.stalemate_winning_margin {
+        Color::Black
+    } else if evaluation <= -params.stalemate_winning_margin {
+        Color::White
+    } else {
+        return 0;
+    };
+    // The moves the losing side would have if it were its turn. There is no null move out of
+    // check, which is fine as a king in check isn't stalemated.
+    let losing_to_move = if board.side_to_move() == losing {
+        Some(*board)
+    } else {
+        board.null_move()
+    };
+    let Some(losing_to_move) = losing_to_move.filter(|board| board.checkers().popcnt() == 0) else {
+        return 0;
+    };
+    let moves = MoveGen::new_legal(&losing_to_move).len();
+    if moves > STALEMATE_TRAP_MOVES {
+        return 0;
+    }
+    let penalty = params.stalemate_trap_penalty * (STALEMATE_TRAP_MOVES + 1 - moves) as i32;
+    // In favor of the losing side
+    eval::from_perspective(losing, penalty)
+}
@@ -0,0 +1,14 @@
+use chess::Color;
+
+use crate::algorithms::eval;
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::taper;
+
+/// Bonus for being the side to move, in the middlegame and endgame. Having the move is worth
+/// more while there are many pieces to improve.
+pub(crate) const DEFAULT_TEMPO_BONUS: (i32, i32) = (20, 10);
+
+/// The bonus of `side_to_move` having the move, from white's point of view and tapered by `phase`
+pub(crate) fn tempo_bonus(side_to_move: Color, phase: i32, params: &EvalParams) -> i32 {
+    eval::from_perspective(side_to_move, taper(params.tempo_bonus, phase))
+}
@@ -0,0 +1,42 @@
+use chess::{BitBoard, Board, Color, Piece, ALL_PIECES, EMPTY};
+
+use crate::algorithms::attack_maps::AttackMaps;
+use crate::algorithms::eval_params::EvalParams;
+use crate::common::utils::piece_value;
+
+/// Bonus per pawn of value of an enemy piece that is attacked and not defended, in centipawns
+pub(crate) const DEFAULT_HANGING_BONUS: i32 = 15;
+/// Bonus per pawn of value that an enemy piece is worth more than its cheapest attacker
+pub(crate) const DEFAULT_LOWER_ATTACKER_BONUS: i32 = 10;
+
+/// The bonus of white's threats against black's pieces minus that of black's threats
+pub(crate) fn threat_bonus(board: &Board, attacks: &AttackMaps, params: &EvalParams) -> i32 {
+    side_bonus(board, attacks, Color::White, params)
+        - side_bonus(board, attacks, Color::Black, params)
+}
+
+/// The bonus of `color` for threatening the pieces of the other side
+fn side_bonus(board: &Board, attacks: &AttackMaps, color: Color, params: &EvalParams) -> i32 {
+    let defended = attacks.by_color(!color);
+    let mut bonus = 0;
+    for target in ALL_PIECES.iter().filter(|&&piece| piece != Piece::King) {
+        let target_value = piece_value(*target) as i32;
+        for square in board.pieces(*target) & board.color_combined(!color) {
+            let square = BitBoard::from_square(square);
+            let Some(cheapest) = ALL_PIECES
+                .iter()
+                .filter(|&&attacker| attacks.by_piece(color, attacker) & square != EMPTY)
+                .map(|&attacker| piece_value(attacker) as i32)
+                .min()
+            else {
+                continue;
+            };
+            if defended & square == EMPTY {
+                bonus += params.hanging_bonus * target_value;
+            } else if cheapest < target_value {
+                bonus += params.lower_attacker_bonus * (target_value - cheapest);
+            }
+        }
+    }
+    bonus
+}
@@ -1,49 +1,740 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
-use chess::Board;
+use chess::{Action, Board, ChessMove, ALL_PIECES, ALL_SQUARES};
 use tokio::time::Instant;
 
-use crate::algorithms::utils::Evaluation;
-use crate::common::utils::Stats;
+use crate::algorithms::eval;
+use crate::algorithms::utils::{Bound, Evaluation};
+use crate::common::constants::{MATE_SCORE, MAX_MATE_PLIES, TIMING_SAMPLE_INTERVAL};
+use crate::common::utils::{self, Stats};
+use crate::io::{check_eval_version, eval_version_header};
 
+/// Score stored for entries without an eval
+const NO_SCORE: i16 = i16::MIN;
+/// Mates are stored as these minus/plus the number of plies to the mate, for the side to move
+/// mating and being mated
+const MATING_SCORE: i16 = i16::MAX;
+const MATED_SCORE: i16 = i16::MIN + 1;
+const MATE_PLIES: i16 = MAX_MATE_PLIES as i16;
+/// Encodes no move, as a move can't have the same source and destination
+const NO_MOVE: u16 = 0;
+/// Small enough to be cleared between moves at the short time controls that games are played at
+pub(crate) const DEFAULT_TRANSPOSITION_TABLE_MB: usize = 1;
+/// Boards whose hashes index the same bucket share its entries
+const BUCKET_ENTRIES: usize = 2;
+const BYTES_PER_MB: usize = 1 << 20;
+/// The bound takes the lowest 2 bits of the flags of an entry and the generation the rest
+const BOUND_BITS: u8 = 2;
+const BOUND_MASK: u8 = (1 << BOUND_BITS) - 1;
+/// Generations wrap around after this many searches
+const GENERATIONS: u8 = 1 << (8 - BOUND_BITS);
+/// Slots that `hashfull` looks at
+const HASHFULL_SAMPLE: usize = 1000;
+/// Deeper entries are stored as this deep, as the depth is packed into a byte plus one
+const MAX_DEPTH: u8 = u8::MAX - 1;
+
+/// Packed into 8 bytes so that many more entries fit in the same memory than if a full
+/// `Evaluation` was stored, and so that the table can store an entry in a single atomic. Only
+/// the eval and the move of the evaluation are kept, along with the static eval of the board if
+/// it was evaluated. The table keeps the hash of the board.
 #[derive(Debug, Copy, Clone)]
 pub struct TranspositionEntry {
-    pub depth: u32,
-    pub(crate) evaluation: Evaluation,
+    score: i16,
+    depth: u8,
+    /// Bound of the score, and the generation of the table that the entry was stored in
+    flags: u8,
+    /// Source square, destination square and promotion piece + 1, 6 + 6 + 3 bits
+    best_move: u16,
+    /// Eval of the board without searching it, so that it doesn't have to be evaluated again
+    static_eval: i16,
 }
 
 impl TranspositionEntry {
-    pub(crate) fn new(depth: u32, evaluation: Evaluation) -> Self {
-        TranspositionEntry { depth, evaluation }
+    pub(crate) fn new(depth: u32, evaluation: Evaluation, static_eval: Option<i32>) -> Self {
+        let score = Self::pack_eval(evaluation.eval);
+        utils::search_assert!(
+            evaluation.eval.and_then(eval::mate_plies)
+                == Self::unpack_eval(score).and_then(eval::mate_plies),
+            "mate distance of {:?} not kept in the transposition table",
+            evaluation.eval
+        );
+        TranspositionEntry {
+            score,
+            depth: depth.min(MAX_DEPTH as u32) as u8,
+            flags: evaluation.bound as u8,
+            best_move: match evaluation.next_action {
+                Some(Action::MakeMove(chess_move)) => Self::pack_move(chess_move),
+                _ => NO_MOVE,
+            },
+            static_eval: static_eval.map_or(NO_SCORE, |eval| {
+                eval.clamp(NO_SCORE as i32 + 1, i16::MAX as i32) as i16
+            }),
+        }
+    }
+
+    /// The depth is packed plus one, so that no entry is packed into the 0 of an empty slot
+    fn pack(&self) -> u64 {
+        self.score as u16 as u64
+            | (self.depth as u64 + 1) << 16
+            | (self.flags as u64) << 24
+            | (self.best_move as u64) << 32
+            | (self.static_eval as u16 as u64) << 48
+    }
+
+    /// `None` for an empty slot
+    fn unpack(data: u64) -> Option<Self> {
+        let depth = (data >> 16) as u8;
+        (depth != 0).then(|| TranspositionEntry {
+            score: data as u16 as i16,
+            depth: depth - 1,
+            flags: (data >> 24) as u8,
+            best_move: (data >> 32) as u16,
+            static_eval: (data >> 48) as u16 as i16,
+        })
+    }
+
+    pub(crate) fn depth(&self) -> u32 {
+        self.depth as u32
+    }
+
+    pub(crate) fn bound(&self) -> Bound {
+        match self.flags & BOUND_MASK {
+            1 => Bound::Lower,
+            2 => Bound::Upper,
+            _ => Bound::Exact,
+        }
+    }
+
+    /// Static eval of the board from the point of view of the side to move, if it was evaluated
+    /// when the entry was stored
+    pub(crate) fn static_eval(&self) -> Option<i32> {
+        (self.static_eval != NO_SCORE).then_some(self.static_eval as i32)
+    }
+
+    fn generation(&self) -> u8 {
+        self.flags >> BOUND_BITS
+    }
+
+    /// Searches since the entry was stored, up to `GENERATIONS - 1` after which it wraps around
+    fn age(&self, generation: u8) -> u8 {
+        generation.wrapping_sub(self.generation()) % GENERATIONS
+    }
+
+    /// The best move found from the board, if the search got to one. It is only legal if the
+    /// entry really belongs to the board, see `legal_best_move`.
+    pub(crate) fn best_move(&self) -> Option<ChessMove> {
+        Self::unpack_move(self.best_move)
+    }
+
+    /// The best move if it is legal on `board`. Boards whose hashes collide share their entries
+    /// even though the full hash is verified, so a move that isn't legal is counted in `stats`
+    /// as a detected collision and left out.
+    pub(crate) fn legal_best_move(&self, board: &Board, stats: &mut Stats) -> Option<ChessMove> {
+        let chess_move = self.best_move()?;
+        if !board.legal(chess_move) {
+            stats.transposition_collisions += 1;
+            return None;
+        }
+        Some(chess_move)
+    }
+
+    pub(crate) fn evaluation(&self) -> Evaluation {
+        let next_action = self.best_move().map(Action::MakeMove);
+        let mut evaluation = Evaluation::new(Self::unpack_eval(self.score), next_action);
+        evaluation.bound = self.bound();
+        evaluation
+    }
+
+    fn pack_eval(eval: Option<i32>) -> i16 {
+        match eval {
+            None => NO_SCORE,
+            Some(eval) => match eval::mate_plies(eval) {
+                Some(plies) if eval > 0 => MATING_SCORE - plies as i16,
+                Some(plies) => MATED_SCORE + plies as i16,
+                None => eval.clamp(
+                    (MATED_SCORE + MATE_PLIES + 1) as i32,
+                    (MATING_SCORE - MATE_PLIES - 1) as i32,
+                ) as i16,
+            },
+        }
+    }
+
+    fn unpack_eval(score: i16) -> Option<i32> {
+        match score {
+            NO_SCORE => None,
+            score if score >= MATING_SCORE - MATE_PLIES => {
+                Some(MATE_SCORE - (MATING_SCORE - score) as i32)
+            }
+            score if score <= MATED_SCORE + MATE_PLIES => {
+                Some(-MATE_SCORE + (score - MATED_SCORE) as i32)
+            }
+            score => Some(score as i32),
+        }
+    }
+
+    fn pack_move(chess_move: ChessMove) -> u16 {
+        let promotion = chess_move
+            .get_promotion()
+            .map_or(0, |piece| piece.to_index() as u16 + 1);
+        chess_move.get_source().to_index() as u16
+            | (chess_move.get_dest().to_index() as u16) << 6
+            | promotion << 12
+    }
+
+    fn unpack_move(best_move: u16) -> Option<ChessMove> {
+        if best_move == NO_MOVE {
+            return None;
+        }
+        let promotion = match best_move >> 12 {
+            0 => None,
+            piece => Some(ALL_PIECES[piece as usize - 1]),
+        };
+        Some(ChessMove::new(
+            ALL_SQUARES[(best_move & 0x3F) as usize],
+            ALL_SQUARES[(best_move >> 6 & 0x3F) as usize],
+            promotion,
+        ))
+    }
+}
+
+/// How a full bucket decides which entry a new one replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ReplacementScheme {
+    /// The entry from the oldest search is replaced, the shallowest of those
+    DepthAndAge,
+    /// The first entry of a bucket is only replaced by entries at least as deep or when it is
+    /// from an earlier search, and the other entry takes everything else. Deep entries that
+    /// were expensive to search then aren't lost to the many shallow ones.
+    #[default]
+    TwoTier,
+}
+
+pub(crate) const ALL_REPLACEMENT_SCHEMES: [ReplacementScheme; 2] =
+    [ReplacementScheme::DepthAndAge, ReplacementScheme::TwoTier];
+
+/// When an algorithm clears its transposition table, from least to most often. Each policy
+/// also clears at the times of the policies before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub(crate) enum ClearPolicy {
+    /// The table is kept for as long as the algorithm is. Needed for the table to be saved and
+    /// loaded between runs.
+    Never,
+    /// Both games of a pair share a table, so the second game starts with the entries of the
+    /// first one from the same opening
+    EveryGamePair,
+    EveryGame,
+    /// Every search starts with an empty table
+    #[default]
+    EveryMove,
+}
+
+/// An entry together with the hash of its board XORed with it. Threads that share the table
+/// read and write the two halves without a lock, so a slot written by two threads at once can
+/// end up with halves of different entries. The XOR then doesn't give back the hash of either
+/// board, so the torn entry is never found.
+#[derive(Debug, Default)]
+struct Slot {
+    checked_hash: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    /// The hash of the board of the entry together with the entry, `None` if the slot is empty
+    fn load(&self) -> Option<(u64, TranspositionEntry)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let hash = self.checked_hash.load(Ordering::Relaxed) ^ data;
+        TranspositionEntry::unpack(data).map(|entry| (hash, entry))
+    }
+
+    fn store(&self, hash: u64, entry: TranspositionEntry) {
+        let data = entry.pack();
+        self.checked_hash.store(hash ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+impl Clone for Slot {
+    fn clone(&self) -> Self {
+        Slot {
+            checked_hash: AtomicU64::new(self.checked_hash.load(Ordering::Relaxed)),
+            data: AtomicU64::new(self.data.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// The hash of the board and the entry in each slot of a bucket, when they were loaded
+type LoadedSlots = [Option<(u64, TranspositionEntry)>; BUCKET_ENTRIES];
+
+#[derive(Debug, Clone, Default)]
+struct Bucket {
+    slots: [Slot; BUCKET_ENTRIES],
+}
+
+impl Bucket {
+    /// The entry of the board with `hash`. The whole hash is compared, not only the low bits
+    /// that picked the bucket, so entries of other boards in the bucket aren't mistaken for it.
+    fn get(&self, hash: u64) -> Option<TranspositionEntry> {
+        self.slots
+            .iter()
+            .filter_map(Slot::load)
+            .find(|&(slot_hash, _)| slot_hash == hash)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Stores `entry` in the slot chosen by `scheme`. Returns how many more entries the bucket
+    /// holds than before.
+    fn store(
+        &self,
+        hash: u64,
+        mut entry: TranspositionEntry,
+        generation: u8,
+        scheme: ReplacementScheme,
+    ) -> usize {
+        let loaded = self.slots.each_ref().map(Slot::load);
+        let mut filled = loaded.map(|slot| slot.is_some());
+        let occupied = filled.iter().filter(|&&filled| filled).count();
+        entry.flags = (entry.flags & BOUND_MASK) | (generation << BOUND_BITS);
+        let slot = match scheme {
+            ReplacementScheme::DepthAndAge => Self::depth_and_age_slot(&loaded, hash, generation),
+            ReplacementScheme::TwoTier => Self::two_tier_slot(&loaded, hash, &entry, generation),
+        };
+        // A replaced depth-preferred entry of another board still gets the always-replace slot
+        if scheme == ReplacementScheme::TwoTier && slot == 0 {
+            if let Some((old_hash, old)) = loaded[0].filter(|&(old_hash, _)| old_hash != hash) {
+                self.slots[1].store(old_hash, old);
+                filled[1] = true;
+            }
+        }
+        self.slots[slot].store(hash, entry);
+        filled[slot] = true;
+        filled.iter().filter(|&&filled| filled).count() - occupied
+    }
+
+    /// The slot of the same board if there is one, or else an empty slot, or else the slot of
+    /// the entry from the oldest search, the shallowest of those
+    fn depth_and_age_slot(loaded: &LoadedSlots, hash: u64, generation: u8) -> usize {
+        let same_board = loaded
+            .iter()
+            .position(|slot| slot.is_some_and(|(slot_hash, _)| slot_hash == hash));
+        let empty = loaded.iter().position(Option::is_none);
+        same_board.or(empty).unwrap_or_else(|| {
+            (0..BUCKET_ENTRIES)
+                .min_by_key(|&i| {
+                    loaded[i].map_or((Reverse(0), 0), |(_, old)| {
+                        (Reverse(old.age(generation)), old.depth)
+                    })
+                })
+                .unwrap_or(0)
+        })
+    }
+
+    /// The depth-preferred first slot if the entry there is of the same board, from an earlier
+    /// search or no deeper than `entry`, and otherwise the always-replace second slot
+    fn two_tier_slot(
+        loaded: &LoadedSlots,
+        hash: u64,
+        entry: &TranspositionEntry,
+        generation: u8,
+    ) -> usize {
+        match loaded[0] {
+            Some((old_hash, old))
+                if old_hash != hash && old.age(generation) == 0 && old.depth > entry.depth =>
+            {
+                1
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// A fixed number of buckets indexed by the low bits of the board hash, so that the table never
+/// grows and probing it never hashes more than the board hash. The entries are kept in atomics,
+/// so that threads can share the table by reference without locking it. A single thread pays
+/// nothing extra for that, as relaxed atomic loads and stores are plain ones.
+#[derive(Debug)]
+pub(crate) struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    size_mb: usize,
+    /// Entries in the table, for its occupancy
+    occupied: AtomicUsize,
+    /// Bumped for every search, so that entries left from earlier searches are replaced first
+    generation: u8,
+    pub(crate) replacement_scheme: ReplacementScheme,
+}
+
+impl Clone for TranspositionTable {
+    fn clone(&self) -> Self {
+        TranspositionTable {
+            buckets: self.buckets.clone(),
+            size_mb: self.size_mb,
+            occupied: AtomicUsize::new(self.occupied()),
+            generation: self.generation,
+            replacement_scheme: self.replacement_scheme,
+        }
+    }
+}
+
+impl TranspositionTable {
+    /// A table of at most `size_mb` megabytes, with the number of buckets rounded down to a
+    /// power of two. There is always at least one bucket.
+    pub(crate) fn new(size_mb: usize) -> Self {
+        let buckets = (size_mb * BYTES_PER_MB / mem::size_of::<Bucket>()).max(1);
+        TranspositionTable {
+            buckets: vec![Bucket::default(); 1 << buckets.ilog2()],
+            size_mb,
+            occupied: AtomicUsize::new(0),
+            generation: 0,
+            replacement_scheme: ReplacementScheme::default(),
+        }
+    }
+
+    /// Makes the entries stored so far older than the ones stored from now on
+    pub(crate) fn new_search(&mut self) {
+        self.generation = (self.generation + 1) % GENERATIONS;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.buckets.fill(Bucket::default());
+        *self.occupied.get_mut() = 0;
+    }
+
+    fn bucket_index(&self, hash: u64) -> usize {
+        (hash & (self.buckets.len() as u64 - 1)) as usize
+    }
+
+    fn get(&self, hash: u64) -> Option<TranspositionEntry> {
+        self.buckets[self.bucket_index(hash)].get(hash)
+    }
+
+    fn store(&self, hash: u64, entry: TranspositionEntry) {
+        self.store_in_generation(hash, entry, self.generation);
+    }
+
+    fn store_in_generation(&self, hash: u64, entry: TranspositionEntry, generation: u8) {
+        let added = self.buckets[self.bucket_index(hash)].store(
+            hash,
+            entry,
+            generation,
+            self.replacement_scheme,
+        );
+        self.occupied.fetch_add(added, Ordering::Relaxed);
+    }
+
+    /// Stores every entry of `other` in this table, as if the current search stored them
+    pub(crate) fn merge(&self, other: &TranspositionTable) {
+        for (hash, entry) in other.entries() {
+            self.store(hash, entry);
+        }
+    }
+
+    /// The line of best moves stored from `board` on, for as long as the moves are legal. Stops
+    /// after `max_plies` moves, as the entries may lead around in a cycle.
+    pub(crate) fn line(&self, board: &Board, max_plies: u32) -> Vec<ChessMove> {
+        let mut line = Vec::new();
+        let mut board = *board;
+        while line.len() < max_plies as usize {
+            let Some(chess_move) = self
+                .get(board.get_hash())
+                .and_then(|entry| entry.best_move())
+                .filter(|chess_move| board.legal(*chess_move))
+            else {
+                break;
+            };
+            line.push(chess_move);
+            board = board.make_move_new(chess_move);
+        }
+        line
+    }
+
+    /// Entries along with the hashes of their boards
+    fn entries(&self) -> impl Iterator<Item = (u64, TranspositionEntry)> + '_ {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.slots.iter().filter_map(Slot::load))
+    }
+
+    /// The size the table was created with, which it doesn't go over
+    pub(crate) fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    pub(crate) fn occupied(&self) -> usize {
+        self.occupied.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_ENTRIES
+    }
+
+    /// Fraction of the entries that are in use
+    pub(crate) fn occupancy(&self) -> f32 {
+        self.occupied() as f32 / self.capacity() as f32
+    }
+
+    /// Permille of the first slots of the table that hold an entry from the current search, as
+    /// the hashfull of UCI. Entries from earlier searches are left out, as they are the first to
+    /// be replaced. Only a sample is looked at, so that it is cheap enough to measure every move.
+    pub(crate) fn hashfull(&self) -> u32 {
+        let sample = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| &bucket.slots)
+            .take(HASHFULL_SAMPLE);
+        let (mut sampled, mut full) = (0, 0);
+        for slot in sample {
+            sampled += 1;
+            if slot
+                .load()
+                .is_some_and(|(_, entry)| entry.age(self.generation) == 0)
+            {
+                full += 1;
+            }
+        }
+        full * 1000 / sampled.max(1)
+    }
+
+    /// Bytes allocated for the table
+    pub(crate) fn memory(&self) -> usize {
+        self.buckets.len() * mem::size_of::<Bucket>()
+    }
+}
+
+/// Whether a stored `evaluation` can stand in for searching the board with the window
+/// `alpha`..`beta`, from the same point of view. An exact eval always can, while a lower bound
+/// only can if it fails high and an upper bound only if it fails low, as the real eval could be
+/// anywhere past the bound.
+pub(crate) fn settles(evaluation: &Evaluation, alpha: i32, beta: i32) -> bool {
+    match (evaluation.bound, evaluation.eval) {
+        (_, None) => false,
+        (Bound::Exact, Some(_)) => true,
+        (Bound::Lower, Some(eval)) => eval >= beta,
+        (Bound::Upper, Some(eval)) => eval <= alpha,
     }
 }
 
 pub(crate) fn insert_in_transposition_table(
-    transposition_table: &mut HashMap<u64, TranspositionEntry>,
+    transposition_table: &TranspositionTable,
     board: &Board,
     depth: u32,
     stats: &mut Stats,
     evaluation: Evaluation,
+    static_eval: Option<i32>,
 ) {
-    let start = Instant::now();
-    transposition_table.insert(board.get_hash(), TranspositionEntry::new(depth, evaluation));
-    stats.time_for_transposition_access += Instant::now() - start;
+    let sample_timing = stats
+        .transposition_table_entries
+        .is_multiple_of(TIMING_SAMPLE_INTERVAL);
+    let start = sample_timing.then(Instant::now);
+    utils::search_assert!(depth > 0, "leaf stored in transposition table");
+    let hash = board.get_hash();
+    transposition_table.store(
+        hash,
+        TranspositionEntry::new(depth, evaluation, static_eval),
+    );
+    if let Some(start) = start {
+        stats.time_for_transposition_access += (Instant::now() - start) * TIMING_SAMPLE_INTERVAL;
+    }
     stats.transposition_table_entries += 1
 }
 
 pub(crate) fn get_transposition_entry(
-    transposition_table: &HashMap<u64, TranspositionEntry>,
+    transposition_table: &TranspositionTable,
     stats: &mut Stats,
     board: &Board,
 ) -> Option<TranspositionEntry> {
-    let start = Instant::now();
+    let sample_timing = stats
+        .transposition_table_probes
+        .is_multiple_of(TIMING_SAMPLE_INTERVAL);
+    let start = sample_timing.then(Instant::now);
+    stats.transposition_table_probes += 1;
 
-    let transposition_entry = transposition_table.get(&board.get_hash()).copied();
+    let hash = board.get_hash();
+    let transposition_entry = transposition_table.get(hash);
     if transposition_entry.is_some() {
         stats.transposition_table_accesses += 1;
     }
-    let time_for_transposition_access = Instant::now() - start;
-    stats.time_for_transposition_access += time_for_transposition_access;
+    if let Some(start) = start {
+        stats.time_for_transposition_access += (Instant::now() - start) * TIMING_SAMPLE_INTERVAL;
+    }
 
     transposition_entry
 }
+
+/// Bytes of each entry in a saved table, the hash of its board followed by the packed entry
+const SAVED_ENTRY_BYTES: usize = 16;
+
+/// Writes the table in a compact binary format: the eval version header on its own line,
+/// followed by the hash of the board and the packed entry of every entry, as little endian u64s.
+/// `weights` is the hash of the eval weights the entries were searched with.
+pub(crate) fn save_transposition_table(
+    transposition_table: &TranspositionTable,
+    modules: u64,
+    weights: u64,
+    path: &str,
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", eval_version_header(modules, weights))?;
+    for (hash, entry) in transposition_table.entries() {
+        writer.write_all(&hash.to_le_bytes())?;
+        writer.write_all(&entry.pack().to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Reads a table written by `save_transposition_table` into a table of `size_mb` megabytes.
+/// Fails if the file was written by a different eval version, module set or weights. The
+/// entries count as stored by the first search after loading them, so they aren't replaced
+/// before the entries of that search.
+pub(crate) fn load_transposition_table(
+    modules: u64,
+    weights: u64,
+    path: &str,
+    size_mb: usize,
+) -> std::io::Result<TranspositionTable> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    check_eval_version(&header, modules, weights)?;
+
+    let transposition_table = TranspositionTable::new(size_mb);
+    // The generation that `new_search` moves the table to when the first search starts
+    let generation = (transposition_table.generation + 1) % GENERATIONS;
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let records = bytes.chunks_exact(SAVED_ENTRY_BYTES);
+    if !records.remainder().is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Transposition table file ends in the middle of an entry",
+        ));
+    }
+    for record in records {
+        let (hash, data) = record.split_at(SAVED_ENTRY_BYTES / 2);
+        let hash = u64::from_le_bytes(hash.try_into().unwrap());
+        let data = u64::from_le_bytes(data.try_into().unwrap());
+        let entry = TranspositionEntry::unpack(data)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Empty transposition entry"))?;
+        transposition_table.store_in_generation(hash, entry, generation);
+    }
+    Ok(transposition_table)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::{Action, Board};
+    use tokio::time::{Duration, Instant};
+
+    use super::{
+        get_transposition_entry, insert_in_transposition_table, load_transposition_table,
+        save_transposition_table, TranspositionTable,
+    };
+    use crate::algorithms::eval;
+    use crate::algorithms::the_algorithm::Algorithm;
+    use crate::algorithms::utils::Evaluation;
+    use crate::common::constants::modules::{ALPHA_BETA, TRANSPOSITION_TABLE};
+    use crate::common::constants::MATE_SCORE;
+    use crate::common::utils::Stats;
+
+    /// Stores `eval`, found by the search at `stored_ply`, and returns it as the search would
+    /// read it back at `probed_ply`
+    fn store_and_probe(eval: i32, stored_ply: u32, probed_ply: u32) -> Option<i32> {
+        let board = Board::default();
+        let transposition_table = TranspositionTable::new(1);
+        let mut stats = Stats::default();
+        let evaluation = Evaluation::new(eval::mate_score_to_node(Some(eval), stored_ply), None);
+        insert_in_transposition_table(
+            &transposition_table,
+            &board,
+            4,
+            &mut stats,
+            evaluation,
+            None,
+        );
+        let entry = get_transposition_entry(&transposition_table, &mut stats, &board).unwrap();
+        eval::mate_score_from_node(entry.evaluation().eval, probed_ply)
+    }
+
+    #[test]
+    fn mates_keep_their_distance_from_the_node() {
+        // Mating 4 plies after a node at ply 3 is mating 4 plies after it at ply 5 too
+        assert_eq!(store_and_probe(MATE_SCORE - 7, 3, 5), Some(MATE_SCORE - 9));
+        assert_eq!(store_and_probe(MATE_SCORE - 7, 3, 1), Some(MATE_SCORE - 5));
+        // Being mated 4 plies after a node at ply 2
+        assert_eq!(
+            store_and_probe(-(MATE_SCORE - 6), 2, 6),
+            Some(-(MATE_SCORE - 10))
+        );
+        assert_eq!(
+            store_and_probe(-(MATE_SCORE - 6), 2, 0),
+            Some(-(MATE_SCORE - 4))
+        );
+        // Mated right at the node
+        assert_eq!(
+            store_and_probe(-(MATE_SCORE - 5), 5, 2),
+            Some(-(MATE_SCORE - 2))
+        );
+        assert_eq!(store_and_probe(123, 3, 7), Some(123));
+    }
+
+    #[test]
+    fn finds_exact_mate_in_two() {
+        // Rb7 takes the seventh rank, then Ra8 mates. Neither rook mates at once.
+        let board = Board::from_str("7k/8/8/8/8/8/1R6/R5K1 w - - 0 1").unwrap();
+        let mut algorithm =
+            Algorithm::new(ALPHA_BETA | TRANSPOSITION_TABLE, Duration::from_secs(1));
+        algorithm.fixed_depth = Some(4);
+        let (action, _, _, lines) = algorithm
+            .next_action_iterative_deepening(&board, Instant::now() + Duration::from_secs(60));
+        assert!(matches!(action, Action::MakeMove(_)));
+        assert_eq!(lines[0].eval, Some(MATE_SCORE - 3));
+    }
+
+    #[test]
+    fn loaded_entries_belong_to_the_first_search() {
+        let board = Board::default();
+        let mut transposition_table = TranspositionTable::new(1);
+        transposition_table.new_search();
+        let evaluation = Evaluation::new(Some(50), None);
+        insert_in_transposition_table(
+            &transposition_table,
+            &board,
+            3,
+            &mut Stats::default(),
+            evaluation,
+            Some(20),
+        );
+        let path = std::env::temp_dir().join("loaded_entries_belong_to_the_first_search.tt");
+        let path = path.to_str().unwrap();
+        save_transposition_table(&transposition_table, ALPHA_BETA, 0, path).unwrap();
+        let mut loaded = load_transposition_table(ALPHA_BETA, 0, path, 1).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        loaded.new_search();
+        let entry = loaded.get(board.get_hash()).unwrap();
+        assert_eq!(entry.age(loaded.generation), 0);
+        assert_eq!(entry.depth(), 3);
+        assert_eq!(entry.evaluation().eval, Some(50));
+        assert_eq!(entry.static_eval(), Some(20));
+    }
+
+    #[test]
+    fn tables_of_other_weights_are_refused() {
+        let mut weighted = Algorithm::new(ALPHA_BETA, Duration::ZERO);
+        weighted.eval_params.tempo_bonus.0 += 1;
+        let algorithm = Algorithm::new(ALPHA_BETA, Duration::ZERO);
+        assert_ne!(weighted.eval_weights_hash(), algorithm.eval_weights_hash());
+
+        let path = std::env::temp_dir().join("tables_of_other_weights_are_refused.tt");
+        let path = path.to_str().unwrap();
+        weighted.save_tt(path).unwrap();
+        let error = load_transposition_table(ALPHA_BETA, algorithm.eval_weights_hash(), path, 1)
+            .unwrap_err();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,78 @@
+use chess::{
+    get_knight_moves, get_pawn_attacks, BitBoard, Board, CastleRights, Color, Piece, EMPTY,
+};
+
+use crate::algorithms::eval_params::EvalParams;
+
+/// Penalty for a bishop on the enemy's seventh rank edge cut off by a pawn, in centipawns
+pub(crate) const DEFAULT_TRAPPED_BISHOP_PENALTY: i32 = 100;
+/// Penalty for a knight in an enemy corner with every way out covered
+pub(crate) const DEFAULT_TRAPPED_KNIGHT_PENALTY: i32 = 80;
+/// Penalty for a rook boxed into the corner by its own king that can't castle
+pub(crate) const DEFAULT_TRAPPED_ROOK_PENALTY: i32 = 50;
+
+/// Indices of a bishop's square and of the enemy pawn trapping it there, for white and
+/// mirrored for black: a7 with b6 and h7 with g6
+const BISHOP_TRAPS: [(usize, usize); 2] = [(48, 41), (55, 46)];
+/// Indices of the enemy corners a knight can get stuck in: a8 and h8
+const KNIGHT_CORNERS: [usize; 2] = [56, 63];
+/// Indices of a king's square and of the squares of the rooks it boxes in: Kf1 with Rg1 or
+/// Rh1, Kg1 with Rh1 or Rh2, and the same on the queenside
+const ROOK_TRAPS: [(usize, &[usize]); 4] =
+    [(5, &[6, 7]), (6, &[7, 15]), (2, &[0, 1]), (1, &[0, 8])];
+
+/// The penalties of white's trapped pieces minus those of black's, as a score for white
+pub(crate) fn trapped_piece_score(board: &Board, params: &EvalParams) -> i32 {
+    side_penalty(board, Color::Black, params) - side_penalty(board, Color::White, params)
+}
+
+fn side_penalty(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let own = |piece: Piece| board.pieces(piece) & board.color_combined(color);
+    let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let enemy_pawn_attacks = enemy_pawns.fold(EMPTY, |attacks, pawn| {
+        attacks | get_pawn_attacks(pawn, !color, !EMPTY)
+    });
+    let mut penalty = 0;
+
+    for (bishop, pawn) in BISHOP_TRAPS {
+        if own(Piece::Bishop) & square(bishop, color) != EMPTY
+            && enemy_pawns & square(pawn, color) != EMPTY
+        {
+            penalty += params.trapped_bishop_penalty;
+        }
+    }
+
+    for corner in KNIGHT_CORNERS {
+        let knight = square(corner, color);
+        if own(Piece::Knight) & knight == EMPTY {
+            continue;
+        }
+        let exits = get_knight_moves(knight.to_square()) & !board.color_combined(color);
+        if exits & !enemy_pawn_attacks == EMPTY {
+            penalty += params.trapped_knight_penalty;
+        }
+    }
+
+    if board.castle_rights(color) == CastleRights::NoRights {
+        for (king, rooks) in ROOK_TRAPS {
+            if own(Piece::King) & square(king, color) == EMPTY {
+                continue;
+            }
+            let boxed_in = rooks
+                .iter()
+                .filter(|&&rook| own(Piece::Rook) & square(rook, color) != EMPTY)
+                .count() as i32;
+            penalty += boxed_in * params.trapped_rook_penalty;
+        }
+    }
+    penalty
+}
+
+/// The square with `index` on white's side of the board, mirrored to black's side for black
+fn square(index: usize, color: Color) -> BitBoard {
+    let index = match color {
+        Color::White => index,
+        Color::Black => index ^ 56,
+    };
+    BitBoard::new(1 << index)
+}
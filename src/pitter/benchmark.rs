@@ -0,0 +1,62 @@
+use std::hint::black_box;
+
+use chess::{Action, Board};
+use tokio::time::{Duration, Instant};
+
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::common::utils::{self, Stats};
+use crate::modules::transposition_table::{ClearPolicy, ReplacementScheme};
+
+use super::logic::RANDOM_OPENING_PLIES;
+
+/// Estimates the fraction of search time spent collecting stats. The same random positions are
+/// searched to `depth` with the node counters kept and with them turned off, and the extra time
+/// of keeping them is returned as a fraction of the time without.
+pub(crate) fn stats_overhead(modules: u64, depth: u32, positions: u32) -> f32 {
+    let boards: Vec<Board> = (0..positions)
+        .map(|_| utils::random_starting_position(RANDOM_OPENING_PLIES).current_position())
+        .collect();
+    // Alternated position by position, so that a slow stretch of the machine hits both alike
+    let mut search_times = [Duration::ZERO; 2];
+    for board in &boards {
+        for (search_time, collect_stats) in search_times.iter_mut().zip([true, false]) {
+            let mut algorithm = Algorithm::new(modules, Duration::ZERO);
+            algorithm.fixed_depth = Some(depth);
+            algorithm.collect_stats = collect_stats;
+            let start = Instant::now();
+            black_box(algorithm.next_action_iterative_deepening(board, Instant::now()));
+            *search_time += Instant::now() - start;
+        }
+    }
+    let [with_stats, without_stats] = search_times;
+    with_stats.as_secs_f32() / without_stats.as_secs_f32() - 1.
+}
+
+/// Fraction of the transposition table probes that find an entry when the table is replaced by
+/// `scheme`. Each of `games` self-play games from random openings is played for `plies` plies
+/// with the table kept between the moves, so that full buckets get replaced.
+pub(crate) fn transposition_hit_rate(
+    modules: u64,
+    time_per_move: Duration,
+    scheme: ReplacementScheme,
+    games: u32,
+    plies: u32,
+) -> f32 {
+    let mut total = Stats::default();
+    for _ in 0..games {
+        let mut board = utils::random_starting_position(RANDOM_OPENING_PLIES).current_position();
+        let mut algorithm = Algorithm::new(modules, time_per_move);
+        algorithm.transposition_clear_policy = ClearPolicy::Never;
+        algorithm.transposition_table.replacement_scheme = scheme;
+        for _ in 0..plies {
+            let (action, _, stats, _) =
+                algorithm.next_action_iterative_deepening(&board, Instant::now() + time_per_move);
+            total += stats;
+            match action {
+                Action::MakeMove(chess_move) => board = board.make_move_new(chess_move),
+                _ => break,
+            }
+        }
+    }
+    total.transposition_table_accesses as f32 / total.transposition_table_probes.max(1) as f32
+}
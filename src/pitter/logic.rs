@@ -1,18 +1,168 @@
+use std::fmt;
 use std::mem;
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::time::{Duration, Instant};
 
-use chess::{Action, Board, Color, Game, GameResult};
+use chess::{Action, Board, Color, Game, GameResult, Piece};
 use tokio::sync::Mutex;
 
 use crate::algorithms::the_algorithm::Algorithm;
-use crate::common::constants::modules::ANALYZE;
-use crate::common::utils::{self, Stats};
+use crate::algorithms::time_management;
+use crate::common::constants::modules::{ANALYZE, TRANSPOSITION_TABLE};
+use crate::common::constants::FIFTY_MOVE_PLIES;
+use crate::common::utils::{self, module_enabled, piece_value, Stats, StatsAverage};
+use crate::io;
+use crate::pitter::pgn_archive;
+
+/// Material difference (in pawns) at the ply cap above which the game is awarded to the side
+/// that is ahead instead of being left inconclusive
+const ADJUDICATION_MATERIAL_MARGIN: u32 = 5;
+
+/// Time each side gets for a whole game, instead of a fixed time per move
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimeControl {
+    pub(crate) base: Duration,
+    /// Added to the clock after every move
+    pub(crate) increment: Duration,
+}
+
+/// Time one side has left for its moves in a game. Running out of time doesn't lose the game,
+/// the side only gets to search the first depth of its moves.
+#[derive(Debug, Clone, Copy)]
+struct GameClock {
+    remaining: Duration,
+    increment: Duration,
+    /// Whether the time manager decides how much of the remaining time a move gets, otherwise
+    /// every move may use all of it
+    allocate: bool,
+}
+
+impl GameClock {
+    fn new(time_control: TimeControl) -> Self {
+        GameClock {
+            remaining: time_control.base,
+            increment: time_control.increment,
+            allocate: true,
+        }
+    }
+
+    /// Every move gets the time per move of the algorithm plus whatever the earlier moves left
+    /// unused
+    fn per_move(time_per_move: Duration) -> Self {
+        GameClock {
+            remaining: time_per_move,
+            increment: time_per_move,
+            allocate: false,
+        }
+    }
+
+    /// When a move started at `start` has to be done
+    fn deadline(&self, board: &Board, start: Instant) -> Instant {
+        if self.allocate {
+            start + time_management::allocate(board, self.remaining, self.increment)
+        } else {
+            start + self.remaining
+        }
+    }
+
+    /// Runs the clock for a move that took `time_spent` and adds the increment
+    fn record_move(&mut self, time_spent: Duration) {
+        self.remaining = self.remaining.saturating_sub(time_spent) + self.increment;
+    }
+}
+
+/// Number of random moves played from the initial position before each game pair
+pub(crate) const RANDOM_OPENING_PLIES: u32 = 5;
+
+/// How much a competition prints. Every level prints everything the levels below it do.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Default)]
+pub(crate) enum Verbosity {
+    /// Nothing is printed, results are only written to files
+    Quiet,
+    /// The results and average stats when the competition is done
+    #[default]
+    ResultsOnly,
+    /// The outcome of every game pair as it finishes
+    PairSummary,
+    /// The moves of every game
+    FullGames,
+}
+
+impl FromStr for Verbosity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quiet" => Ok(Verbosity::Quiet),
+            "results" => Ok(Verbosity::ResultsOnly),
+            "pairs" => Ok(Verbosity::PairSummary),
+            "games" => Ok(Verbosity::FullGames),
+            _ => Err(format!(
+                "Unknown verbosity {}, expected quiet, results, pairs or games",
+                s
+            )),
+        }
+    }
+}
 
 pub(crate) struct Competition {
     pub(crate) algo1: Algorithm,
     pub(crate) algo2: Algorithm,
     results: Option<CompetitionResults>,
+    /// Every played game is appended to this file if set
+    pub(crate) pgn_archive: Option<String>,
+    /// algo1's transposition table is saved to this file after the competition if set, with the
+    /// entries of every game pair merged into it
+    pub(crate) transposition_table_file: Option<String>,
+    /// Directory of Syzygy tablebases that games cut off at the ply cap are adjudicated with
+    /// before falling back to material. Probing them isn't implemented yet, so this has no
+    /// effect.
+    pub(crate) tablebase_path: Option<String>,
+    pub(crate) verbosity: Verbosity,
+    /// Games are played on a clock with this time control if set, otherwise with the time per
+    /// move of each algorithm
+    pub(crate) time_control: Option<TimeControl>,
+}
+
+/// Which game pair of a competition a game belongs to, in the order the pairs were started
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub(crate) struct PairId(pub(crate) u32);
+
+/// Identifies a game within a competition so that it can be found in the PGN archive and
+/// replayed. Displayed as `pair.game`, where game 1 has algo1 playing white and game 2 has algo1
+/// playing black.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Default)]
+pub(crate) struct GameId {
+    pub(crate) pair: PairId,
+    /// Reversed == true means that algo1 plays black
+    pub(crate) reversed: bool,
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.pair.0, if self.reversed { 2 } else { 1 })
+    }
+}
+
+impl FromStr for GameId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pair, game) = s.split_once('.').ok_or(())?;
+        let pair = PairId(pair.parse().map_err(|_| ())?);
+        match game {
+            "1" => Ok(GameId {
+                pair,
+                reversed: false,
+            }),
+            "2" => Ok(GameId {
+                pair,
+                reversed: true,
+            }),
+            _ => Err(()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -60,6 +210,58 @@ pub(crate) enum GameOutcome {
     InconclusiveTooLong,
 }
 
+/// How a game that didn't end on the board was given an outcome
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum Adjudication {
+    /// Fifty moves by each side without a capture or pawn move
+    FiftyMoveRule,
+    /// Neither side has enough material left to deliver mate
+    InsufficientMaterial,
+    /// One side was ahead by at least `ADJUDICATION_MATERIAL_MARGIN`
+    MaterialAdvantage,
+    /// The tablebases had the position
+    Tablebase,
+}
+
+impl Adjudication {
+    /// Tries to assign an outcome to a position where the game was cut off, from the tablebases
+    /// at `tablebase_path` if set and otherwise from material. Returns `None` when the position
+    /// is too unclear, in which case the game stays inconclusive.
+    fn adjudicate(
+        board: &Board,
+        tablebase_path: Option<&str>,
+    ) -> Option<(GameOutcome, Adjudication)> {
+        if let Some(outcome) = tablebase_path.and_then(|path| probe_tablebase(path, board)) {
+            return Some((outcome, Adjudication::Tablebase));
+        }
+
+        let (white_material, black_material) = utils::material_each_side(board);
+        let white_material = white_material - piece_value(Piece::King);
+        let black_material = black_material - piece_value(Piece::King);
+
+        // Bare kings, or a lone minor piece against a bare king
+        if board.pieces(Piece::Pawn).popcnt() == 0
+            && white_material + black_material <= piece_value(Piece::Knight)
+        {
+            return Some((GameOutcome::Draw, Adjudication::InsufficientMaterial));
+        }
+
+        if white_material >= black_material + ADJUDICATION_MATERIAL_MARGIN {
+            Some((GameOutcome::WhiteWin, Adjudication::MaterialAdvantage))
+        } else if black_material >= white_material + ADJUDICATION_MATERIAL_MARGIN {
+            Some((GameOutcome::BlackWin, Adjudication::MaterialAdvantage))
+        } else {
+            None
+        }
+    }
+}
+
+/// Looks up the outcome of `board` in the tablebases at `path`. Probing isn't implemented yet,
+/// so this always returns `None` and the position is adjudicated by material.
+fn probe_tablebase(_path: &str, _board: &Board) -> Option<GameOutcome> {
+    None
+}
+
 #[allow(unused_assignments)]
 #[derive(Default, Debug, Copy, Clone)]
 pub(crate) struct CompetitionResults {
@@ -79,18 +281,64 @@ pub(crate) struct CompetitionResults {
     pub algo1_half_wins: usize,
     /// Pairs of games that wins when Algo2 is playing and draw on the other
     pub algo2_half_wins: usize,
+
+    pub algo1_nodes_per_second: f32,
+    pub algo2_nodes_per_second: f32,
+    /// Bytes allocated for the transposition table, 0 without TRANSPOSITION_TABLE
+    pub algo1_memory: f32,
+    pub algo2_memory: f32,
+    /// Average permille of the transposition table filled by each move, to tell whether the
+    /// size or the replacement scheme of the table holds the search back
+    pub algo1_hashfull: f32,
+    pub algo2_hashfull: f32,
+    /// Hashes of the eval weights, PSQT tables and network, as results of other weights don't
+    /// carry over
+    pub algo1_weights: u64,
+    pub algo2_weights: u64,
 }
 
 #[derive(Debug, Default)]
 pub(crate) struct GameInfo {
+    pub(crate) id: GameId,
     pub(crate) outcome: GameOutcome,
     /// First is algo1 stats and second is algo2 stats
     stats: (Stats, Stats),
 
     pub(crate) game: Option<Game>,
+    /// Set when the outcome was decided by adjudication, after the ply cap or the fifty-move rule
+    pub(crate) adjudication: Option<Adjudication>,
+    /// Expected score of white by the algorithm that made each move, from its best line. `None`
+    /// for moves made without an eval.
+    pub(crate) win_probabilities: Vec<Option<f32>>,
 }
 
 impl CompetitionResults {
+    /// Fraction of the points that algo1 got, counting a half win as half a point and
+    /// inconclusive pairs as draws
+    pub(crate) fn score(&self) -> f32 {
+        let pairs = self.algo1_wins
+            + self.algo2_wins
+            + self.draws
+            + self.inconclusive_same_color_win
+            + self.inconclusive_too_long
+            + self.algo1_half_wins
+            + self.algo2_half_wins;
+        let points = self.algo1_wins as f32
+            + 0.75 * self.algo1_half_wins as f32
+            + 0.25 * self.algo2_half_wins as f32
+            + 0.5
+                * (self.draws + self.inconclusive_same_color_win + self.inconclusive_too_long)
+                    as f32;
+        points / pairs.max(1) as f32
+    }
+
+    /// Estimated Elo of algo1 relative to algo2
+    pub(crate) fn elo_difference(&self) -> f32 {
+        // Keep the score away from 0 and 1 where the Elo difference is infinite
+        let score = self.score().clamp(0.001, 0.999);
+        -400. * (1. / score - 1.).log10()
+    }
+
     /// Reversed == true means that algo1 played black
     fn register_game_outcome(&mut self, game_outcome: GamePairOutcome) {
         match game_outcome {
@@ -112,20 +360,48 @@ impl Competition {
             algo1,
             algo2,
             results: None,
+            pgn_archive: None,
+            transposition_table_file: None,
+            tablebase_path: None,
+            verbosity: Verbosity::default(),
+            time_control: None,
         }
     }
 
-    pub(crate) fn play_game(&self, mut game: Game, reversed: bool, max_plies: usize) -> GameInfo {
-        let mut game_info = GameInfo::default();
-        let mut algo1 = self.algo1.clone();
+    pub(crate) fn play_game(&self, id: GameId, game: Game, max_plies: usize) -> GameInfo {
+        let (mut algo1, mut algo2) = (self.algo1.clone(), self.algo2.clone());
+        self.play_game_with(id, game, max_plies, &mut algo1, &mut algo2)
+    }
+
+    /// Plays a game with `algo1` and `algo2` themselves instead of copies, so that both games of
+    /// a pair can share what the algorithms keep between games
+    fn play_game_with(
+        &self,
+        id: GameId,
+        mut game: Game,
+        max_plies: usize,
+        mut algo1: &mut Algorithm,
+        mut algo2: &mut Algorithm,
+    ) -> GameInfo {
+        let reversed = id.reversed;
+        let mut game_info = GameInfo {
+            id,
+            ..Default::default()
+        };
         algo1.reset();
-        let mut algo2 = self.algo2.clone();
         algo2.reset();
         if reversed {
             mem::swap(&mut algo1, &mut algo2);
         };
+        let clock = |time_per_move| match self.time_control {
+            Some(time_control) => GameClock::new(time_control),
+            None => GameClock::per_move(time_per_move),
+        };
+        let mut white_clock = clock(algo1.time_per_move);
+        let mut black_clock = clock(algo2.time_per_move);
 
         let mut num_plies = 0;
+        let mut halfmove_clock = 0;
         loop {
             let start = Instant::now();
             let side_to_move = game.side_to_move();
@@ -135,24 +411,30 @@ impl Competition {
                     analyze = algo1.modules & ANALYZE != 0;
                     algo1.next_action_iterative_deepening(
                         &game.current_position(),
-                        Instant::now() + algo1.time_per_move,
+                        white_clock.deadline(&game.current_position(), start),
                     )
                 }
                 Color::Black => {
                     analyze = algo1.modules & ANALYZE != 0;
                     algo2.next_action_iterative_deepening(
                         &game.current_position(),
-                        Instant::now() + algo2.time_per_move,
+                        black_clock.deadline(&game.current_position(), start),
                     )
                 }
             };
             let end = Instant::now();
             next_action.2.time_spent = end - start;
             next_action.2.num_plies = 1;
+            match side_to_move {
+                Color::White => white_clock.record_move(end - start),
+                Color::Black => black_clock.record_move(end - start),
+            }
 
             if analyze {
-                // Add stats field to the debug thing
-                utils::vector_push_debug!(next_action.1, next_action.2);
+                // Add the expected lines and stats field to the debug thing
+                let principal_variations: Vec<String> =
+                    next_action.3.iter().map(ToString::to_string).collect();
+                utils::vector_push_debug!(next_action.1, principal_variations, next_action.2);
             }
 
             if side_to_move == Color::Black && !reversed || side_to_move == Color::White && reversed
@@ -164,7 +446,22 @@ impl Competition {
                 game_info.stats.0 += next_action.2;
             }
 
+            if let Action::MakeMove(_) = next_action.0 {
+                let algo = match side_to_move {
+                    Color::White => &*algo1,
+                    Color::Black => &*algo2,
+                };
+                game_info.win_probabilities.push(
+                    next_action
+                        .3
+                        .first()
+                        .and_then(|line| line.eval)
+                        .map(|eval| algo.win_probability(eval)),
+                );
+            }
+
             let mut declared_draw = false;
+            let before = game.current_position();
             let success = match next_action.0 {
                 Action::MakeMove(chess_move) => game.make_move(chess_move),
                 Action::OfferDraw(color) => game.offer_draw(color),
@@ -179,7 +476,7 @@ impl Competition {
 
             if !success {
                 dbg!(utils::to_pgn(&game));
-                panic!("Algorithm made illegal action");
+                panic!("Algorithm made illegal action in game {}", id);
             }
 
             if declared_draw {
@@ -187,6 +484,23 @@ impl Competition {
                 break;
             }
 
+            let after = game.current_position();
+            halfmove_clock = if utils::is_irreversible(
+                *before.pieces(Piece::Pawn),
+                before.combined().popcnt(),
+                *after.pieces(Piece::Pawn),
+                after.combined().popcnt(),
+            ) {
+                0
+            } else {
+                halfmove_clock + 1
+            };
+            if halfmove_clock >= FIFTY_MOVE_PLIES {
+                game_info.outcome = GameOutcome::Draw;
+                game_info.adjudication = Some(Adjudication::FiftyMoveRule);
+                break;
+            }
+
             if let Some(result) = game.result() {
                 game_info.outcome = match result {
                     GameResult::WhiteCheckmates => GameOutcome::WhiteWin,
@@ -200,7 +514,14 @@ impl Competition {
                 break;
             }
             if num_plies >= max_plies {
-                game_info.outcome = GameOutcome::InconclusiveTooLong;
+                let tablebase_path = self.tablebase_path.as_deref();
+                match Adjudication::adjudicate(&game.current_position(), tablebase_path) {
+                    Some((outcome, adjudication)) => {
+                        game_info.outcome = outcome;
+                        game_info.adjudication = Some(adjudication);
+                    }
+                    None => game_info.outcome = GameOutcome::InconclusiveTooLong,
+                }
                 break;
             }
             num_plies += 1
@@ -210,9 +531,35 @@ impl Competition {
         game_info
     }
 
-    fn play_game_pair(&self, game: Game) -> (GameInfo, GameInfo) {
-        let outcome1 = self.play_game(game.clone(), false, 150);
-        let outcome2 = self.play_game(game, true, 150);
+    fn play_game_pair(&self, pair: PairId, game: Game) -> (GameInfo, GameInfo) {
+        let (mut algo1, mut algo2) = (self.algo1.clone(), self.algo2.clone());
+        algo1.new_game_pair();
+        algo2.new_game_pair();
+        let outcome1 = self.play_game_with(
+            GameId {
+                pair,
+                reversed: false,
+            },
+            game.clone(),
+            150,
+            &mut algo1,
+            &mut algo2,
+        );
+        let outcome2 = self.play_game_with(
+            GameId {
+                pair,
+                reversed: true,
+            },
+            game,
+            150,
+            &mut algo1,
+            &mut algo2,
+        );
+        if self.transposition_table_file.is_some() {
+            self.algo1
+                .transposition_table
+                .merge(&algo1.transposition_table);
+        }
 
         (outcome1, outcome2)
     }
@@ -227,18 +574,41 @@ impl Competition {
         let sum_stats = Arc::new(Mutex::new((Stats::default(), Stats::default())));
 
         let mut tasks = Vec::new();
-        for _ in 0..num_game_pairs {
+        for pair in 0..num_game_pairs {
             let results = results.clone();
             let sum_stats = sum_stats.clone();
             let self_arc = self_arc.clone();
             let task = tokio::spawn(async move {
-                let game = utils::random_starting_position(5);
-
-                let game_pair_info = self_arc.play_game_pair(game);
+                let game = utils::random_starting_position(RANDOM_OPENING_PLIES);
+
+                let game_pair_info = self_arc.play_game_pair(PairId(pair), game);
+                if let Some(path) = &self_arc.pgn_archive {
+                    let (algo1, algo2) = (&self_arc.algo1, &self_arc.algo2);
+                    let entries = pgn_archive::format_game(&game_pair_info.0, algo1, algo2)
+                        + &pgn_archive::format_game(&game_pair_info.1, algo2, algo1);
+                    let _ = io::write_result(entries.as_bytes(), path);
+                }
                 let combined_outcome = GamePairOutcome::combine_outcomes(
                     game_pair_info.0.outcome,
                     game_pair_info.1.outcome,
                 );
+                if self_arc.verbosity >= Verbosity::PairSummary {
+                    println!("Pair {}: {:?}", pair, combined_outcome);
+                }
+                if self_arc.verbosity >= Verbosity::FullGames {
+                    for game_info in [&game_pair_info.0, &game_pair_info.1] {
+                        println!(
+                            "Game {}: {:?}\n{}",
+                            game_info.id,
+                            game_info.outcome,
+                            game_info
+                                .game
+                                .as_ref()
+                                .map(utils::to_pgn)
+                                .unwrap_or_default()
+                        );
+                    }
+                }
 
                 results.lock().await.register_game_outcome(combined_outcome);
 
@@ -257,19 +627,71 @@ impl Competition {
         for task in tasks {
             let _ = task.await;
         }
+        if let Some(path) = &self_arc.transposition_table_file {
+            if let Err(error) = self_arc.algo1.save_tt(path) {
+                println!("Could not save transposition table {}: {}", path, error);
+            }
+        }
         let sum_stats = sum_stats.lock().await;
         let avg_stats = (
             sum_stats.0 / sum_stats.0.num_plies,
             sum_stats.1 / sum_stats.1.num_plies,
         );
 
-        println!("Stats for algo1: {:#?}", avg_stats.0);
-        println!("Stats for algo2: {:#?}", avg_stats.1);
+        if self_arc.verbosity >= Verbosity::ResultsOnly {
+            println!("Stats for algo1: {:#?}", avg_stats.0);
+            println!("Stats for algo2: {:#?}", avg_stats.1);
+        }
+
+        let mut results = *results.lock().await;
+        let nodes_per_second =
+            |stats: &StatsAverage| stats.nodes_visited / stats.time_spent.as_secs_f32();
+        let memory = |algorithm: &Algorithm| {
+            if module_enabled(algorithm.modules, TRANSPOSITION_TABLE) {
+                algorithm.transposition_table.memory() as f32
+            } else {
+                0.
+            }
+        };
+        results.algo1_nodes_per_second = nodes_per_second(&avg_stats.0);
+        results.algo2_nodes_per_second = nodes_per_second(&avg_stats.1);
+        results.algo1_memory = memory(&self_arc.algo1);
+        results.algo2_memory = memory(&self_arc.algo2);
+        results.algo1_hashfull = avg_stats.0.hashfull;
+        results.algo2_hashfull = avg_stats.1.hashfull;
+        results.algo1_weights = self_arc.algo1.eval_weights_hash();
+        results.algo2_weights = self_arc.algo2.eval_weights_hash();
+
+        for (name, stats, modules) in [
+            ("algo1", avg_stats.0, self_arc.algo1.modules),
+            ("algo2", avg_stats.1, self_arc.algo2.modules),
+        ] {
+            if self_arc.verbosity < Verbosity::ResultsOnly {
+                break;
+            }
+            println!("Module costs for {}:", name);
+            for (i, time) in stats.eval_module_time.iter().enumerate() {
+                if module_enabled(modules, 1 << i) {
+                    println!(
+                        "  {} eval time per ply: {:?}",
+                        io::modules_to_string(1 << i),
+                        time
+                    );
+                }
+            }
+            for (i, searches) in stats.verification_searches.iter().enumerate() {
+                if *searches > 0. {
+                    println!(
+                        "  {} verification searches per ply: {}, fail rate: {}",
+                        io::modules_to_string(1 << i),
+                        searches,
+                        stats.verification_fail_rate[i]
+                    );
+                }
+            }
+        }
 
-        // Gives E0597 otherwise
-        #[allow(clippy::let_and_return)]
-        let results_copy = *results.lock().await;
-        results_copy
+        results
     }
 
     // #[allow(dead_code)]
@@ -287,7 +709,7 @@ impl Competition {
         loop {
             let game = utils::random_starting_position((i % 100) * 2 + 4);
 
-            let game_pair_info = self.play_game_pair(game);
+            let game_pair_info = self.play_game_pair(PairId(i), game);
             let combined_outcome = GamePairOutcome::combine_outcomes(
                 game_pair_info.0.outcome,
                 game_pair_info.1.outcome,
@@ -314,6 +736,7 @@ impl Competition {
         let game = game.unwrap();
 
         let mut i = 1;
+        println!("Game {}", game.0.id);
         println!("{}", utils::to_pgn(game.0.game.as_ref().unwrap()));
         let mut board = Board::default();
 
@@ -339,8 +762,10 @@ impl Competition {
             };
             let end = Instant::now();
             algo_out.2.time_spent = end - start;
-            // Add stats field to the debug thing
-            utils::vector_push_debug!(algo_out.1, algo_out.2);
+            // Add the expected lines and stats field to the debug thing
+            let principal_variations: Vec<String> =
+                algo_out.3.iter().map(ToString::to_string).collect();
+            utils::vector_push_debug!(algo_out.1, principal_variations, algo_out.2);
 
             if i % 2 == 1 {
                 println!("{}. {} ...", (i + 1) / 2, chess_move);
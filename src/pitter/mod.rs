@@ -1,2 +1,9 @@
+pub(crate) mod benchmark;
 pub(crate) mod logic;
+pub(crate) mod pgn_archive;
+pub(crate) mod psqt_fit;
+pub(crate) mod spsa;
+pub(crate) mod summary;
+pub(crate) mod symmetry;
 pub(crate) mod ui;
+pub(crate) mod win_probability;
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+use chess::{Action, Board, ChessMove, Color, Game};
+
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::common::constants::EVAL_VERSION;
+use crate::io::eval_version_header;
+
+use super::logic::{GameId, GameInfo, GameOutcome};
+
+/// Games lost in at most this many plies count as short losses
+pub(crate) const SHORT_LOSS_MAX_PLIES: usize = 20;
+
+/// A finished game read back from the archive
+#[derive(Debug, Clone)]
+pub(crate) struct ArchivedGame {
+    pub(crate) id: Option<GameId>,
+    pub(crate) white_modules: u64,
+    pub(crate) black_modules: u64,
+    /// Hashes of the eval weights of each side, see `Algorithm::eval_weights_hash`
+    pub(crate) white_weights: u64,
+    pub(crate) black_weights: u64,
+    pub(crate) outcome: GameOutcome,
+    pub(crate) moves: Vec<ChessMove>,
+}
+
+fn outcome_to_result_tag(outcome: GameOutcome) -> &'static str {
+    match outcome {
+        GameOutcome::WhiteWin => "1-0",
+        GameOutcome::BlackWin => "0-1",
+        GameOutcome::Draw => "1/2-1/2",
+        GameOutcome::InconclusiveTooLong => "*",
+    }
+}
+
+fn result_tag_to_outcome(tag: &str) -> GameOutcome {
+    match tag {
+        "1-0" => GameOutcome::WhiteWin,
+        "0-1" => GameOutcome::BlackWin,
+        "1/2-1/2" => GameOutcome::Draw,
+        _ => GameOutcome::InconclusiveTooLong,
+    }
+}
+
+/// Formats a played game as a PGN entry. The modules of each side are stored in the player tags,
+/// next to the eval version and the hashes of the eval weights that the comments were evaluated
+/// with. The moves use the same notation as `utils::to_pgn`. Every move that an algorithm made is
+/// followed by a comment with the expected score of white by that algorithm.
+pub(crate) fn format_game(game_info: &GameInfo, white: &Algorithm, black: &Algorithm) -> String {
+    let moves = game_info
+        .game
+        .as_ref()
+        .map(|game| annotated_moves(game, &game_info.win_probabilities))
+        .unwrap_or_default();
+    let result = outcome_to_result_tag(game_info.outcome);
+    format!(
+        "[GameId \"{}\"]\n[EvalVersion \"{}\"]\n[White \"{}\"]\n[Black \"{}\"]\n\
+         [WhiteWeights \"{:016x}\"]\n[BlackWeights \"{:016x}\"]\n[Result \"{}\"]\n\n{} {}\n\n",
+        game_info.id,
+        EVAL_VERSION,
+        white.modules,
+        black.modules,
+        white.eval_weights_hash(),
+        black.eval_weights_hash(),
+        result,
+        moves,
+        result
+    )
+}
+
+/// The moves of `game` like `utils::to_pgn`, where the last moves are followed by
+/// `win_probabilities` as comments. The random opening moves come before those.
+fn annotated_moves(game: &Game, win_probabilities: &[Option<f32>]) -> String {
+    let moves: Vec<ChessMove> = game
+        .actions()
+        .iter()
+        .filter_map(|action| match action {
+            Action::MakeMove(chess_move) => Some(*chess_move),
+            _ => None,
+        })
+        .collect();
+    let opening_plies = moves.len().saturating_sub(win_probabilities.len());
+    let mut output = Vec::new();
+    for (i, chess_move) in moves.iter().enumerate() {
+        if i % 2 == 0 {
+            output.push(format!("{}.", i / 2 + 1));
+        }
+        output.push(chess_move.to_string());
+        if let Some(Some(win_probability)) = i
+            .checked_sub(opening_plies)
+            .and_then(|ply| win_probabilities.get(ply))
+        {
+            output.push(format!("{{{:.3}}}", win_probability));
+        }
+    }
+    output.join(" ")
+}
+
+fn tag_value<'a>(line: &'a str, tag: &str) -> Option<&'a str> {
+    line.strip_prefix('[')?
+        .strip_prefix(tag)?
+        .strip_prefix(' ')?
+        .trim()
+        .strip_suffix(']')?
+        .trim_matches('"')
+        .into()
+}
+
+/// Reads every game written by `format_game`. Entries that can't be parsed are skipped. Fails if
+/// a game was played with another eval version, as its modules and comments may mean something
+/// else now.
+pub(crate) fn read_archive(path: &str) -> std::io::Result<Vec<ArchivedGame>> {
+    let mut games = Vec::new();
+    let mut id = None;
+    let mut eval_version = None;
+    let mut white_modules = None;
+    let mut black_modules = None;
+    let mut white_weights = None;
+    let mut black_weights = None;
+    let mut outcome = None;
+    let parse_weights = |value: &str| u64::from_str_radix(value, 16).ok();
+    for line in read_to_string(path)?.lines() {
+        if let Some(value) = tag_value(line, "GameId") {
+            id = value.parse().ok();
+        } else if let Some(value) = tag_value(line, "EvalVersion") {
+            eval_version = value.parse().ok();
+        } else if let Some(value) = tag_value(line, "WhiteWeights") {
+            white_weights = parse_weights(value);
+        } else if let Some(value) = tag_value(line, "BlackWeights") {
+            black_weights = parse_weights(value);
+        } else if let Some(value) = tag_value(line, "White") {
+            white_modules = value.parse().ok();
+        } else if let Some(value) = tag_value(line, "Black") {
+            black_modules = value.parse().ok();
+        } else if let Some(value) = tag_value(line, "Result") {
+            outcome = Some(result_tag_to_outcome(value));
+        } else if !line.is_empty() {
+            // The move text is a single line. Move numbers and the result aren't moves.
+            let moves = line
+                .split_whitespace()
+                .filter_map(|token| ChessMove::from_str(token).ok())
+                .collect();
+            let id = id.take();
+            if eval_version.take() != Some(EVAL_VERSION) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Game {:?} wasn't played with eval version {}",
+                        id, EVAL_VERSION
+                    ),
+                ));
+            }
+            if let (
+                Some(white_modules),
+                Some(black_modules),
+                Some(white_weights),
+                Some(black_weights),
+                Some(outcome),
+            ) = (
+                white_modules.take(),
+                black_modules.take(),
+                white_weights.take(),
+                black_weights.take(),
+                outcome.take(),
+            ) {
+                games.push(ArchivedGame {
+                    id,
+                    white_modules,
+                    black_modules,
+                    white_weights,
+                    black_weights,
+                    outcome,
+                    moves,
+                });
+            }
+        }
+    }
+    Ok(games)
+}
+
+/// Finds games where `modules` lost within `SHORT_LOSS_MAX_PLIES` and groups them by the position
+/// the game started from after the random opening and the eval weights of the loser. Returns an
+/// EPD suite with the most common positions first, each annotated with how many short losses it
+/// led to, in which games and the eval version header of the loser.
+pub(crate) fn mine_short_losses(
+    games: &[ArchivedGame],
+    modules: u64,
+    opening_plies: usize,
+) -> String {
+    let mut losses_per_position: HashMap<(Board, u64), Vec<Option<GameId>>> = HashMap::new();
+    for game in games {
+        let lost_as = match game.outcome {
+            GameOutcome::WhiteWin => Color::Black,
+            GameOutcome::BlackWin => Color::White,
+            _ => continue,
+        };
+        let (loser_modules, loser_weights) = match lost_as {
+            Color::White => (game.white_modules, game.white_weights),
+            Color::Black => (game.black_modules, game.black_weights),
+        };
+        if loser_modules != modules || game.moves.len() > opening_plies + SHORT_LOSS_MAX_PLIES {
+            continue;
+        }
+
+        let mut board = Board::default();
+        for chess_move in game.moves.iter().take(opening_plies) {
+            board = board.make_move_new(*chess_move);
+        }
+        losses_per_position
+            .entry((board, loser_weights))
+            .or_default()
+            .push(game.id);
+    }
+
+    let mut positions: Vec<((Board, u64), Vec<Option<GameId>>)> =
+        losses_per_position.into_iter().collect();
+    positions.sort_by_key(|(_, losses)| std::cmp::Reverse(losses.len()));
+
+    let mut epd = String::new();
+    for ((board, weights), losses) in positions {
+        // EPD is the first four FEN fields followed by operations
+        let fen = board.to_string();
+        let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+        let ids: Vec<String> = losses
+            .iter()
+            .map(|id| id.map_or("?".to_string(), |id| id.to_string()))
+            .collect();
+        epd.push_str(&format!(
+            "{} c0 \"{} short losses\"; c1 \"{}\"; c2 \"{}\";\n",
+            fields.join(" "),
+            losses.len(),
+            ids.join(" "),
+            eval_version_header(modules, weights)
+        ));
+    }
+    epd
+}
@@ -0,0 +1,201 @@
+use std::f32::consts::LN_10;
+
+use chess::{Action, Board, Color, ALL_COLORS, ALL_PIECES, NUM_PIECES};
+
+use crate::algorithms::eval;
+use crate::algorithms::psqt_tables::PsqtTables;
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::common::constants::{DEFAULT_WIN_PROBABILITY_SCALE, MAX_PHASE};
+use crate::common::utils;
+use crate::modules::incremental_psqt::table_index;
+use crate::pitter::logic::{
+    Competition, GameId, GameOutcome, PairId, Verbosity, RANDOM_OPENING_PLIES,
+};
+
+/// Plies after which a self-play game is adjudicated, as in the game pairs of a competition
+const MAX_PLIES: usize = 150;
+/// Entries of all middlegame tables together, and of all endgame tables
+const TABLE_ENTRIES: usize = NUM_PIECES * 64;
+
+/// How much self-play to fit the tables to and how the fit proceeds
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PsqtFitSettings {
+    /// Self-play games that positions are taken from. Games that end inconclusive are dropped.
+    pub(crate) games: u32,
+    /// Gradient descent steps over every position
+    pub(crate) epochs: u32,
+    /// Centipawns that a table entry moves per unit of its gradient, averaged over the positions
+    /// where the entry is used so that rarely used squares learn as fast as common ones
+    pub(crate) learning_rate: f32,
+}
+
+impl Default for PsqtFitSettings {
+    fn default() -> Self {
+        PsqtFitSettings {
+            games: 200,
+            epochs: 500,
+            learning_rate: 1000.,
+        }
+    }
+}
+
+/// A position of a self-play game, reduced to what the fitted eval depends on
+struct Sample {
+    /// Table entry of every piece, with 1 for white pieces and -1 for black ones
+    entries: Vec<(usize, f32)>,
+    /// How much of the middlegame tables is used, from the game phase
+    mg_weight: f32,
+    /// Material difference in centipawns, from white's point of view
+    material: f32,
+    /// 1 if white won the game, 0.5 for a draw and 0 if black won
+    result: f32,
+}
+
+impl Sample {
+    fn new(board: &Board, result: f32) -> Self {
+        let mut entries = Vec::new();
+        for color in ALL_COLORS {
+            let sign = if color == Color::White { 1. } else { -1. };
+            for piece in ALL_PIECES {
+                for square in board.pieces(piece) & board.color_combined(color) {
+                    entries.push((piece.to_index() * 64 + table_index(color, square), sign));
+                }
+            }
+        }
+        let (white_material, black_material) = utils::material_each_side(board);
+        Sample {
+            entries,
+            mg_weight: utils::game_phase(board) as f32 / MAX_PHASE as f32,
+            material: 100. * (white_material as f32 - black_material as f32),
+            result,
+        }
+    }
+
+    /// Material plus the tables, from white's point of view. `weights` holds the middlegame
+    /// tables followed by the endgame tables.
+    fn eval(&self, weights: &[f32]) -> f32 {
+        self.entries
+            .iter()
+            .map(|&(entry, sign)| {
+                sign * (self.mg_weight * weights[entry]
+                    + (1. - self.mg_weight) * weights[TABLE_ENTRIES + entry])
+            })
+            .sum::<f32>()
+            + self.material
+    }
+}
+
+/// Fits the middlegame and endgame tables of `base` to self-play games of `base` against
+/// itself. The naive tables are kept as they are. Write the result with `PsqtTables`' Display
+/// to load it into an `Algorithm` again.
+pub(crate) fn generate(
+    base: &Algorithm,
+    settings: PsqtFitSettings,
+    verbosity: Verbosity,
+) -> PsqtTables {
+    let mut competition = Competition::new(base.clone(), base.clone());
+    competition.verbosity = Verbosity::Quiet;
+    let positions = self_play_positions(&competition, settings.games);
+    let tables = fit(&positions, &base.psqt_tables, settings);
+    if verbosity >= Verbosity::ResultsOnly {
+        println!(
+            "Fitted the PSQT to {} positions of {} games",
+            positions.len(),
+            settings.games
+        );
+    }
+    tables
+}
+
+/// Plays `games` games of the competition from random openings and returns every position after
+/// the opening together with the result of its game, 1 for a white win down to 0 for a black
+/// win. Positions in check are left out, since the tables can't tell how the check ends.
+pub(crate) fn self_play_positions(competition: &Competition, games: u32) -> Vec<(Board, f32)> {
+    let mut positions = Vec::new();
+    for i in 0..games {
+        let id = GameId {
+            pair: PairId(i / 2),
+            reversed: i % 2 == 1,
+        };
+        let opening = utils::random_starting_position(RANDOM_OPENING_PLIES);
+        let game_info = competition.play_game(id, opening, MAX_PLIES);
+        let result = match game_info.outcome {
+            GameOutcome::WhiteWin => 1.,
+            GameOutcome::Draw => 0.5,
+            GameOutcome::BlackWin => 0.,
+            GameOutcome::InconclusiveTooLong => continue,
+        };
+        let Some(game) = game_info.game else {
+            continue;
+        };
+
+        let mut board = Board::default();
+        let moves = game.actions().iter().filter_map(|action| match action {
+            Action::MakeMove(chess_move) => Some(*chess_move),
+            _ => None,
+        });
+        for (ply, chess_move) in moves.enumerate() {
+            board = board.make_move_new(chess_move);
+            if ply >= RANDOM_OPENING_PLIES as usize && board.checkers().popcnt() == 0 {
+                positions.push((board, result));
+            }
+        }
+    }
+    positions
+}
+
+/// Fits the middlegame and endgame tables of `start` by logistic regression of the results of
+/// `positions` on material plus the tapered tables. Entries of squares that no piece stood on
+/// keep their values from `start`.
+pub(crate) fn fit(
+    positions: &[(Board, f32)],
+    start: &PsqtTables,
+    settings: PsqtFitSettings,
+) -> PsqtTables {
+    let samples: Vec<Sample> = positions
+        .iter()
+        .map(|(board, result)| Sample::new(board, *result))
+        .collect();
+    let mut weights: Vec<f32> = start
+        .mg
+        .iter()
+        .chain(&start.eg)
+        .flatten()
+        .map(|&value| value as f32)
+        .collect();
+    let mut uses = vec![0u32; TABLE_ENTRIES];
+    for sample in &samples {
+        for &(entry, _) in &sample.entries {
+            uses[entry] += 1;
+        }
+    }
+
+    for _ in 0..settings.epochs {
+        let mut gradient = vec![0.; 2 * TABLE_ENTRIES];
+        for sample in &samples {
+            // Derivative of the cross-entropy loss by the eval
+            let expected =
+                eval::win_probability(sample.eval(&weights), DEFAULT_WIN_PROBABILITY_SCALE);
+            let error = (expected - sample.result) * LN_10 / DEFAULT_WIN_PROBABILITY_SCALE;
+            for &(entry, sign) in &sample.entries {
+                gradient[entry] += error * sign * sample.mg_weight;
+                gradient[TABLE_ENTRIES + entry] += error * sign * (1. - sample.mg_weight);
+            }
+        }
+        let mg_and_eg_uses = uses.iter().chain(&uses);
+        for ((weight, gradient), &uses) in weights.iter_mut().zip(gradient).zip(mg_and_eg_uses) {
+            if uses > 0 {
+                *weight -= settings.learning_rate * gradient / uses as f32;
+            }
+        }
+    }
+
+    let mut tables = start.clone();
+    let mut fitted = weights.into_iter().map(|weight| weight.round() as i32);
+    for table in tables.mg.iter_mut().chain(tables.eg.iter_mut()) {
+        for value in table.iter_mut() {
+            *value = fitted.next().unwrap_or(*value);
+        }
+    }
+    tables
+}
@@ -0,0 +1,119 @@
+use rand::Rng;
+
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::pitter::logic::{Competition, Verbosity};
+
+/// A parameter of `Algorithm` for SPSA to tune, by the name `Algorithm::set_parameter` knows it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpsaParameter {
+    pub(crate) name: &'static str,
+    /// How far the parameter is perturbed in the first iteration. Should be at least 1 for
+    /// integer parameters, smaller perturbations round away.
+    pub(crate) step: f32,
+    pub(crate) min: f32,
+    pub(crate) max: f32,
+}
+
+/// Gains of simultaneous perturbation stochastic approximation. The perturbation of iteration k
+/// is `step / (k + 1)^gamma` and the update is `learning_rate / (k + 1 + stability)^alpha`
+/// steps per unit of score difference.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SpsaSettings {
+    pub(crate) iterations: u32,
+    /// Game pairs played between the two perturbed algorithms of every iteration
+    pub(crate) game_pairs: u32,
+    pub(crate) learning_rate: f32,
+    pub(crate) stability: f32,
+    pub(crate) alpha: f32,
+    pub(crate) gamma: f32,
+}
+
+impl Default for SpsaSettings {
+    fn default() -> Self {
+        SpsaSettings {
+            iterations: 100,
+            game_pairs: 20,
+            learning_rate: 2.,
+            stability: 10.,
+            // The asymptotically optimal exponents from Spall
+            alpha: 0.602,
+            gamma: 0.101,
+        }
+    }
+}
+
+/// Tunes `parameters` of `base` by self-play. Every iteration moves all parameters at once by a
+/// random sign, plays the algorithm moved up against the one moved down, and walks every
+/// parameter towards the side that scored better. Returns `base` with the tuned parameters.
+pub(crate) async fn tune(
+    base: &Algorithm,
+    parameters: &[SpsaParameter],
+    settings: SpsaSettings,
+    verbosity: Verbosity,
+) -> Result<Algorithm, String> {
+    let mut values = parameters
+        .iter()
+        .map(|parameter| {
+            base.parameter(parameter.name)
+                .map(|value| value.clamp(parameter.min, parameter.max))
+                .ok_or(format!(
+                    "{} has no value to start tuning from",
+                    parameter.name
+                ))
+        })
+        .collect::<Result<Vec<f32>, String>>()?;
+
+    for k in 0..settings.iterations {
+        let perturbation = 1. / (k as f32 + 1.).powf(settings.gamma);
+        let gain =
+            settings.learning_rate / (k as f32 + 1. + settings.stability).powf(settings.alpha);
+        let signs: Vec<f32> = parameters
+            .iter()
+            .map(|_| {
+                if rand::thread_rng().gen_bool(0.5) {
+                    1.
+                } else {
+                    -1.
+                }
+            })
+            .collect();
+
+        let mut plus = base.clone();
+        let mut minus = base.clone();
+        for ((parameter, value), sign) in parameters.iter().zip(&values).zip(&signs) {
+            let offset = sign * perturbation * parameter.step;
+            plus.set_parameter(
+                parameter.name,
+                (value + offset).clamp(parameter.min, parameter.max),
+            )?;
+            minus.set_parameter(
+                parameter.name,
+                (value - offset).clamp(parameter.min, parameter.max),
+            )?;
+        }
+        let mut competition = Competition::new(plus, minus);
+        competition.verbosity = Verbosity::Quiet;
+        let results = competition.start_competition(settings.game_pairs).await;
+        // Positive when moving up along the signs was better
+        let difference = 2. * results.score() - 1.;
+
+        for ((parameter, value), sign) in parameters.iter().zip(&mut values).zip(&signs) {
+            *value = (*value + gain * parameter.step * difference / (2. * perturbation * sign))
+                .clamp(parameter.min, parameter.max);
+        }
+        if verbosity >= Verbosity::ResultsOnly {
+            let line: Vec<String> = parameters
+                .iter()
+                .zip(&values)
+                .map(|(parameter, value)| format!("{} {:.2}", parameter.name, value))
+                .collect();
+            println!("SPSA iteration {}: {}", k + 1, line.join(", "));
+        }
+    }
+
+    let mut tuned = base.clone();
+    for (parameter, value) in parameters.iter().zip(values) {
+        tuned.set_parameter(parameter.name, value)?;
+    }
+    Ok(tuned)
+}
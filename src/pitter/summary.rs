@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs::{self, read_to_string};
+use std::io::{Error, ErrorKind};
+
+use crate::algorithms::eval_profile::EvalProfile;
+use crate::common::constants::EVAL_VERSION;
+use crate::io::{modules_to_string, write_result};
+
+use super::logic::CompetitionResults;
+
+/// Version of the layout of the results file, raised whenever a column changes meaning
+const RESULTS_VERSION: u32 = 2;
+/// The tab separated columns of the results file, in the order they are written. The second
+/// line of the file names them, so columns are read by name.
+const COLUMNS: [&str; 9] = [
+    "modules",
+    "opponent_modules",
+    "profile",
+    "opponent_profile",
+    "weights",
+    "opponent_weights",
+    "elo",
+    "nodes_per_second",
+    "memory",
+];
+
+/// First line of the results file. Elo from another eval version isn't comparable, so such
+/// files are refused.
+fn results_header() -> String {
+    format!(
+        "# results-version {} eval-version {}",
+        RESULTS_VERSION, EVAL_VERSION
+    )
+}
+
+/// One competition from the results file, seen from algo1
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ConfigurationRecord {
+    pub(crate) modules: u64,
+    /// The modules of the algorithm that `elo` is relative to
+    pub(crate) opponent_modules: u64,
+    /// The eval profiles that the algorithms were made with, if they were, since the same
+    /// modules play differently with the weights of another profile
+    pub(crate) profile: Option<EvalProfile>,
+    pub(crate) opponent_profile: Option<EvalProfile>,
+    /// Hashes of the eval weights, PSQT tables and network of the algorithms
+    pub(crate) weights: u64,
+    pub(crate) opponent_weights: u64,
+    pub(crate) elo: f32,
+    pub(crate) nodes_per_second: f32,
+    pub(crate) memory: f32,
+}
+
+impl ConfigurationRecord {
+    pub(crate) fn new(modules: u64, opponent_modules: u64, results: &CompetitionResults) -> Self {
+        Self {
+            modules,
+            opponent_modules,
+            profile: None,
+            opponent_profile: None,
+            weights: results.algo1_weights,
+            opponent_weights: results.algo2_weights,
+            elo: results.elo_difference(),
+            nodes_per_second: results.algo1_nodes_per_second,
+            memory: results.algo1_memory,
+        }
+    }
+
+    /// Tab separated line as stored in the results file, in the order of `COLUMNS`. Algorithms
+    /// that weren't made from a profile have `-` as their profile.
+    fn to_line(self) -> String {
+        let profile_name = |profile: Option<EvalProfile>| {
+            profile.map_or("-".to_string(), |profile| profile.to_string())
+        };
+        format!(
+            "{}\t{}\t{}\t{}\t{:016x}\t{:016x}\t{}\t{}\t{}\n",
+            self.modules,
+            self.opponent_modules,
+            profile_name(self.profile),
+            profile_name(self.opponent_profile),
+            self.weights,
+            self.opponent_weights,
+            self.elo,
+            self.nodes_per_second,
+            self.memory
+        )
+    }
+
+    /// Parses a line of the results file whose columns are named by `columns`
+    fn from_line(line: &str, columns: &[&str]) -> Option<Self> {
+        let values: Vec<&str> = line.split('\t').collect();
+        if values.len() != columns.len() {
+            return None;
+        }
+        let fields: HashMap<&str, &str> = columns.iter().copied().zip(values).collect();
+        let field = |name: &str| fields.get(name).copied();
+        let parse_profile = |name: &str| match name {
+            "-" => Some(None),
+            name => name.parse().ok().map(Some),
+        };
+        let parse_weights = |hash: &str| u64::from_str_radix(hash, 16).ok();
+        Some(Self {
+            modules: field("modules")?.parse().ok()?,
+            opponent_modules: field("opponent_modules")?.parse().ok()?,
+            profile: parse_profile(field("profile")?)?,
+            opponent_profile: parse_profile(field("opponent_profile")?)?,
+            weights: parse_weights(field("weights")?)?,
+            opponent_weights: parse_weights(field("opponent_weights")?)?,
+            elo: field("elo")?.parse().ok()?,
+            nodes_per_second: field("nodes_per_second")?.parse().ok()?,
+            memory: field("memory")?.parse().ok()?,
+        })
+    }
+
+    /// At least as good on Elo, speed and memory, and strictly better on one of them
+    fn dominates(&self, other: &Self) -> bool {
+        self.elo >= other.elo
+            && self.nodes_per_second >= other.nodes_per_second
+            && self.memory <= other.memory
+            && (self.elo > other.elo
+                || self.nodes_per_second > other.nodes_per_second
+                || self.memory < other.memory)
+    }
+}
+
+/// Appends `record` to the results file at `path`, starting the file with the header and the
+/// column names if it is new
+pub(crate) fn write_record(record: ConfigurationRecord, path: &str) -> std::io::Result<()> {
+    if !fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0) {
+        let header = format!("{}\n{}\n", results_header(), COLUMNS.join("\t"));
+        write_result(header.as_bytes(), path)?;
+    }
+    write_result(record.to_line().as_bytes(), path)
+}
+
+/// Reads the records of the results file at `path`, refusing files of another results or eval
+/// version
+pub(crate) fn read_records(path: &str) -> std::io::Result<Vec<ConfigurationRecord>> {
+    let contents = read_to_string(path)?;
+    let mut lines = contents.lines();
+    let header = lines.next().unwrap_or_default();
+    if header.trim_end() != results_header() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Results file has header \"{}\" but the current one is \"{}\"",
+                header.trim_end(),
+                results_header()
+            ),
+        ));
+    }
+    let columns: Vec<&str> = lines.next().unwrap_or_default().split('\t').collect();
+    if let Some(missing) = COLUMNS.iter().find(|name| !columns.contains(name)) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Results file has no column {}", missing),
+        ));
+    }
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            ConfigurationRecord::from_line(line, &columns)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Bad record: {}", line)))
+        })
+        .collect()
+}
+
+/// The modules of a configuration, followed by its eval profile if it was made from one
+fn configuration_name(modules: u64, profile: Option<EvalProfile>) -> String {
+    match profile {
+        Some(profile) => format!("{} ({})", modules_to_string(modules), profile),
+        None => modules_to_string(modules),
+    }
+}
+
+/// Prints the module combinations that no other combination beats on Elo, nodes per second and
+/// memory at once. Elo is only comparable against the same opponent, so every opponent gets its
+/// own table. Later records of the same pairing replace earlier ones.
+pub(crate) fn print_recommendations(records: &[ConfigurationRecord]) {
+    type Configuration = (u64, Option<EvalProfile>);
+    let mut by_opponent: HashMap<Configuration, HashMap<Configuration, ConfigurationRecord>> =
+        HashMap::new();
+    for record in records {
+        by_opponent
+            .entry((record.opponent_modules, record.opponent_profile))
+            .or_default()
+            .insert((record.modules, record.profile), *record);
+    }
+
+    for ((opponent_modules, opponent_profile), records) in by_opponent {
+        let records: Vec<ConfigurationRecord> = records.into_values().collect();
+        let mut pareto_optimal: Vec<ConfigurationRecord> = records
+            .iter()
+            .filter(|record| !records.iter().any(|other| other.dominates(record)))
+            .copied()
+            .collect();
+        pareto_optimal.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+
+        println!(
+            "Pareto-optimal configurations against {}:",
+            configuration_name(opponent_modules, opponent_profile)
+        );
+        println!(
+            "{:>8}\t{:>12}\t{:>12}\tModules",
+            "Elo", "Nodes/s", "Memory (B)"
+        );
+        for record in pareto_optimal {
+            println!(
+                "{:>8.1}\t{:>12.0}\t{:>12.0}\t{}",
+                record.elo,
+                record.nodes_per_second,
+                record.memory,
+                configuration_name(record.modules, record.profile)
+            );
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chess::Board;
+use tokio::time::Duration;
+
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::common::constants::modules::{
+    ALL_EVAL_MODULES, ENDGAMES, HALFMOVE_SCALING, NAIVE_PSQT, NNUE, STALEMATE_TRAP,
+    TAPERED_EVERY_PESTO_PSQT, TAPERED_INCREMENTAL_PESTO_PSQT,
+};
+use crate::common::utils;
+use crate::io::modules_to_string;
+
+/// Random plies played from the starting position for each test position, enough for some
+/// pieces to be traded and pawns to be advanced
+const RANDOM_PLIES: u32 = 30;
+/// Modules that add a term to the static eval. NNUE is left out since it needs a network file.
+const EVAL_MODULES: u64 = ALL_EVAL_MODULES & !NNUE;
+/// Eval modules that scale the sum of the other terms instead of adding one, so they may only
+/// break a symmetry together with another module
+const SCALING_MODULES: u64 = ENDGAMES | HALFMOVE_SCALING | STALEMATE_TRAP;
+/// Eval modules whose tables tell the kingside from the queenside, so that mirroring the files
+/// may change their eval
+const FILE_ASYMMETRIC_MODULES: u64 =
+    NAIVE_PSQT | TAPERED_EVERY_PESTO_PSQT | TAPERED_INCREMENTAL_PESTO_PSQT;
+
+/// A change of a position that should keep the eval of the side to move the same
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Symmetry {
+    /// Ranks flipped and colors swapped, the side to move included
+    Colors,
+    /// The a-file swapped with the h-file and so on. Castling rights are dropped from both
+    /// positions.
+    Files,
+}
+
+/// A position whose eval changed under a symmetry
+#[derive(Clone, Debug)]
+pub(crate) struct SymmetryFailure {
+    pub(crate) modules: u64,
+    pub(crate) symmetry: Symmetry,
+    pub(crate) fen: String,
+    pub(crate) eval: i32,
+    pub(crate) transformed_eval: i32,
+}
+
+impl fmt::Display for SymmetryFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} breaks {:?} symmetry: {} evaluates to {} but to {} transformed",
+            modules_to_string(self.modules),
+            self.symmetry,
+            self.fen,
+            self.eval,
+            self.transformed_eval
+        )
+    }
+}
+
+/// Evaluates `positions` random positions next to their color flipped and file mirrored
+/// versions, with each eval module alone and with each module that scales the eval paired with
+/// every other one. Returns the smallest combinations that change the eval of the side to move,
+/// each with the first position that shows it.
+pub(crate) fn check_eval_symmetry(positions: u32) -> Vec<SymmetryFailure> {
+    let boards: Vec<Board> = (0..positions)
+        .map(|_| utils::random_starting_position(RANDOM_PLIES).current_position())
+        .collect();
+    let modules: Vec<u64> = (0..u64::BITS)
+        .map(|bit| 1u64 << bit)
+        .filter(|&module| EVAL_MODULES & module != 0)
+        .collect();
+    // Single modules first, so that the pairs containing a known failure can be skipped
+    let mut combinations = modules.clone();
+    for &scaling in modules
+        .iter()
+        .filter(|&&module| SCALING_MODULES & module != 0)
+    {
+        for &other in &modules {
+            let pair = scaling | other;
+            if other != scaling && !combinations.contains(&pair) {
+                combinations.push(pair);
+            }
+        }
+    }
+
+    let mut algorithm = Algorithm::new(0, Duration::ZERO);
+    let mut failures: Vec<SymmetryFailure> = Vec::new();
+    for modules in combinations {
+        algorithm.modules = modules;
+        for symmetry in [Symmetry::Colors, Symmetry::Files] {
+            let explained = failures.iter().any(|failure| {
+                failure.symmetry == symmetry && modules & failure.modules == failure.modules
+            });
+            if explained || (symmetry == Symmetry::Files && modules & FILE_ASYMMETRIC_MODULES != 0)
+            {
+                continue;
+            }
+            let failure = boards.iter().find_map(|board| {
+                let (fen, transformed) = match symmetry {
+                    Symmetry::Colors => {
+                        let fen = board.to_string();
+                        let flipped = flip_colors(&fen);
+                        (fen, flipped)
+                    }
+                    Symmetry::Files => {
+                        let fen = without_castling(&board.to_string());
+                        let mirrored = mirror_files(&fen);
+                        (fen, mirrored)
+                    }
+                };
+                let eval = algorithm.static_eval(&parse(&fen));
+                let transformed_eval = algorithm.static_eval(&parse(&transformed));
+                (eval != transformed_eval).then_some(SymmetryFailure {
+                    modules,
+                    symmetry,
+                    fen,
+                    eval,
+                    transformed_eval,
+                })
+            });
+            failures.extend(failure);
+        }
+    }
+    failures
+}
+
+fn parse(fen: &str) -> Board {
+    Board::from_str(fen).unwrap_or_else(|error| panic!("{} is not a valid FEN: {:?}", fen, error))
+}
+
+/// `fen` with the ranks flipped, the colors swapped and the other side to move
+fn flip_colors(fen: &str) -> String {
+    let mut fields: Vec<String> = fen.split_whitespace().map(String::from).collect();
+    fields[0] = fields[0]
+        .split('/')
+        .rev()
+        .map(swap_case)
+        .collect::<Vec<String>>()
+        .join("/");
+    fields[1] = if fields[1] == "w" { "b" } else { "w" }.to_string();
+    fields[2] = swap_case(&fields[2]);
+    fields[3] = map_square(&fields[3], |file, rank| (file, b'1' + b'8' - rank));
+    fields.join(" ")
+}
+
+/// `fen` with the files mirrored. Only valid without castling rights.
+fn mirror_files(fen: &str) -> String {
+    let mut fields: Vec<String> = fen.split_whitespace().map(String::from).collect();
+    // A rank is pieces and counts of empty squares, so reversing it mirrors it
+    fields[0] = fields[0]
+        .split('/')
+        .map(|rank| rank.chars().rev().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("/");
+    fields[3] = map_square(&fields[3], |file, rank| (b'a' + b'h' - file, rank));
+    fields.join(" ")
+}
+
+fn without_castling(fen: &str) -> String {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    fields[2] = "-";
+    fields.join(" ")
+}
+
+fn swap_case(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii_uppercase() {
+                c.to_ascii_lowercase()
+            } else {
+                c.to_ascii_uppercase()
+            }
+        })
+        .collect()
+}
+
+/// Moves a square written like `e3` by `change` of its file and rank characters, keeping `-`
+fn map_square(square: &str, change: impl Fn(u8, u8) -> (u8, u8)) -> String {
+    match square.as_bytes() {
+        &[file, rank] => {
+            let (file, rank) = change(file, rank);
+            format!("{}{}", file as char, rank as char)
+        }
+        _ => square.to_string(),
+    }
+}
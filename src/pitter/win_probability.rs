@@ -0,0 +1,52 @@
+use crate::algorithms::eval;
+use crate::algorithms::the_algorithm::Algorithm;
+use crate::pitter::logic::{Competition, Verbosity};
+use crate::pitter::psqt_fit;
+
+/// Scales tried by the calibration, in centipawns
+const MIN_SCALE: u32 = 50;
+const MAX_SCALE: u32 = 2000;
+const SCALE_STEP: usize = 10;
+
+/// Finds the `Algorithm::win_probability_scale` that best predicts the results of `games`
+/// self-play games of `base` from its static evals, by the cross-entropy of the predictions.
+/// Two module sets calibrated this way report the same expected score for the same position
+/// even if their evals are in different units.
+pub(crate) fn calibrate_scale(base: &Algorithm, games: u32, verbosity: Verbosity) -> f32 {
+    let mut competition = Competition::new(base.clone(), base.clone());
+    competition.verbosity = Verbosity::Quiet;
+    let positions = psqt_fit::self_play_positions(&competition, games);
+
+    let mut algorithm = base.clone();
+    let samples: Vec<(f32, f32)> = positions
+        .iter()
+        .map(|(board, result)| {
+            let eval = algorithm.static_eval(board);
+            let eval = eval::from_perspective(board.side_to_move(), eval);
+            (eval as f32, *result)
+        })
+        .collect();
+    let loss = |scale: f32| -> f32 {
+        samples
+            .iter()
+            .map(|&(eval, result)| {
+                let expected = eval::win_probability(eval, scale).clamp(1e-6, 1. - 1e-6);
+                -(result * expected.ln() + (1. - result) * (1. - expected).ln())
+            })
+            .sum()
+    };
+    let scale = (MIN_SCALE..=MAX_SCALE)
+        .step_by(SCALE_STEP)
+        .map(|scale| scale as f32)
+        .min_by(|a, b| loss(*a).total_cmp(&loss(*b)))
+        .unwrap_or(MIN_SCALE as f32);
+
+    if verbosity >= Verbosity::ResultsOnly {
+        println!(
+            "Calibrated the win probability scale to {} on {} positions",
+            scale,
+            samples.len()
+        );
+    }
+    scale
+}